@@ -0,0 +1,90 @@
+// Library
+use super::CommandInfo;
+use crate::shell::{Shell, State};
+
+// --
+// BG
+// --
+
+/// # The `bg` command.
+/// This command will continue stopped jobs in the background, as if they had been started
+/// with `&`. Without a job spec, the current job is used. A job that is already running in the
+/// background is left as it is.
+///
+/// ## Example
+///
+/// ```sh
+/// $ make  # Ctrl+Z
+/// $ bg
+/// ```
+/// ```output
+/// [1]+ make &
+/// ```
+pub struct Bg;
+
+// Implement the `CommandInfo` trait for the `Bg` struct.
+impl super::CommandInfo for Bg {
+    /// Get the name of the command.
+    fn name(&self) -> String {
+        String::from("bg")
+    }
+
+    /// Get the description of the command.
+    fn description(&self) -> String {
+        String::from("Move jobs to the background")
+    }
+
+    /// Get the usage of the command.
+    fn usage(&self) -> String {
+        String::from("bg [job_spec ...]")
+    }
+}
+
+// Implement the `ExecutableCommand` trait for the `Bg` struct.
+impl super::ExecutableCommand for Bg {
+    /// Execute the `bg` command.
+    /// The exit status is 1 if any of the job specs doesn't match a job.
+    /// ```sh
+    /// $ bg %1 %make
+    /// ```
+    fn execute<T>(
+        &self,
+        args: Vec<String>,
+        shell: &mut Shell,
+        out_writer: &mut T,
+        err_writer: &mut T,
+    ) -> std::io::Result<i32>
+    where
+        T: std::io::Write,
+    {
+        let specs: Vec<&str> = match args.len() {
+            1 => vec!["%+"],
+            _ => args.iter().skip(1).map(String::as_str).collect(),
+        };
+
+        if let Some(opt) = specs.iter().find(|spec| spec.starts_with('-')) {
+            writeln!(err_writer, "bg: {}: invalid option", opt)?;
+            writeln!(err_writer, "bg: usage: {}", self.usage())?;
+            return Ok(2);
+        }
+
+        let mut status = 0;
+        for spec in specs {
+            match shell.jobs().find(spec) {
+                Ok(id) if shell.jobs().get(id).map(|job| job.state()) == Some(State::Running) => {
+                    writeln!(err_writer, "bg: job {} already in background", id)?;
+                }
+                Ok(id) => shell.background_job(id, out_writer)?,
+                Err(_) if args.len() == 1 => {
+                    writeln!(err_writer, "bg: current: no such job")?;
+                    status = 1;
+                }
+                Err(e) => {
+                    writeln!(err_writer, "bg: {}", e)?;
+                    status = 1;
+                }
+            }
+        }
+        Ok(status)
+    }
+}
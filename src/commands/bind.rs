@@ -0,0 +1,201 @@
+// Library
+use super::CommandInfo;
+use crate::shell::{
+    keymap::{Action, Binding, KeySeq},
+    Shell,
+};
+
+// ----
+// BIND
+// ----
+
+/// # The `bind` command.
+/// This command will list and change the key bindings of the line editor.
+///
+/// ## Example
+///
+/// ```sh
+/// $ bind '"\C-a": end-of-line'
+/// $ bind -x '"\C-t": date'
+/// $ bind -p
+/// ```
+/// ```output
+/// "\C-a": end-of-line
+/// ...
+/// ```
+pub struct Bind;
+
+// Implement the `CommandInfo` trait for the `Bind` struct.
+impl super::CommandInfo for Bind {
+    /// Get the name of the command.
+    fn name(&self) -> String {
+        String::from("bind")
+    }
+
+    /// Get the description of the command.
+    fn description(&self) -> String {
+        String::from("List and change the key bindings of the line editor")
+    }
+
+    /// Get the usage of the command.
+    fn usage(&self) -> String {
+        String::from(
+            "bind [-lpPX] [-f filename] [-q name] [-u name] [-r keyseq] [-x keyseq:shell-command] [keyseq:function-name]",
+        )
+    }
+}
+
+// Implement the `ExecutableCommand` trait for the `Bind` struct.
+impl super::ExecutableCommand for Bind {
+    /// Execute the `bind` command.
+    /// Each argument is either an option, or a `keyseq: function-name` binding.
+    /// ```sh
+    /// $ bind -q end-of-line
+    /// ```
+    /// ```output
+    /// end-of-line can be invoked via "\C-e", "\e[F".
+    /// ```
+    fn execute<T>(
+        &self,
+        args: Vec<String>,
+        shell: &mut Shell,
+        out_writer: &mut T,
+        err_writer: &mut T,
+    ) -> std::io::Result<i32>
+    where
+        T: std::io::Write,
+    {
+        let keymap = shell.keymap();
+        let mut status = 0;
+
+        // Skip the first argument (the command name)
+        let mut args = args.iter().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                // List the names of all the editor actions
+                "-l" => {
+                    for action in Action::ALL {
+                        writeln!(out_writer, "{}", action)?;
+                    }
+                }
+
+                // List the key bindings in a form that can be read back in
+                "-p" => {
+                    for (keyseq, binding) in keymap.bindings() {
+                        if !matches!(binding, Binding::Command(_)) {
+                            writeln!(out_writer, "{}: {}", keyseq, binding)?;
+                        }
+                    }
+                }
+
+                // List the editor actions and the keys they are bound to
+                "-P" => {
+                    for action in Action::ALL {
+                        let keys = keys_for(keymap.bindings(), *action);
+                        if keys.is_empty() {
+                            writeln!(out_writer, "{} is not bound to any keys", action)?;
+                        } else {
+                            writeln!(out_writer, "{} can be found on {}.", action, keys)?;
+                        }
+                    }
+                }
+
+                // List the key sequences bound to shell commands
+                "-X" => {
+                    for (keyseq, binding) in keymap.bindings() {
+                        if let Binding::Command(_) = binding {
+                            writeln!(out_writer, "{}: {}", keyseq, binding)?;
+                        }
+                    }
+                }
+
+                // Options that take a value
+                "-f" | "-q" | "-u" | "-r" | "-x" => {
+                    let Some(value) = args.next() else {
+                        writeln!(err_writer, "bind: {}: option requires an argument", arg)?;
+                        writeln!(err_writer, "bind: usage: {}", self.usage())?;
+                        return Ok(2);
+                    };
+                    match arg.as_str() {
+                        "-f" => match keymap.read_file(std::path::Path::new(value)) {
+                            Ok(errors) => {
+                                for error in errors {
+                                    writeln!(err_writer, "bind: {}", error)?;
+                                    status = 1;
+                                }
+                            }
+                            Err(e) => {
+                                writeln!(err_writer, "bind: {}: {}", value, e)?;
+                                status = 1;
+                            }
+                        },
+                        "-q" | "-u" => match value.parse::<Action>() {
+                            Ok(action) if arg == "-u" => keymap.unbind_action(action),
+                            Ok(action) => {
+                                let keys = keys_for(keymap.bindings(), action);
+                                if keys.is_empty() {
+                                    writeln!(out_writer, "{} is not bound to any keys.", action)?;
+                                    status = 1;
+                                } else {
+                                    writeln!(
+                                        out_writer,
+                                        "{} can be invoked via {}.",
+                                        action, keys
+                                    )?;
+                                }
+                            }
+                            Err(e) => {
+                                writeln!(err_writer, "bind: {}", e)?;
+                                status = 1;
+                            }
+                        },
+                        "-r" => match value.parse::<KeySeq>() {
+                            Ok(keyseq) => {
+                                keymap.unbind(&keyseq);
+                            }
+                            Err(e) => {
+                                writeln!(err_writer, "bind: {}: {}", value, e)?;
+                                status = 1;
+                            }
+                        },
+                        _ => match Binding::parse_command(value) {
+                            Ok((keyseq, binding)) => keymap.bind(keyseq, binding),
+                            Err(e) => {
+                                writeln!(err_writer, "bind: {}: {}", value, e)?;
+                                status = 1;
+                            }
+                        },
+                    }
+                }
+
+                // Unknown options
+                opt if opt.starts_with('-') => {
+                    writeln!(err_writer, "bind: {}: invalid option", opt)?;
+                    writeln!(err_writer, "bind: usage: {}", self.usage())?;
+                    return Ok(2);
+                }
+
+                // Anything else is a `keyseq: function-name` binding
+                line => match Binding::parse(line) {
+                    Ok((keyseq, binding)) => keymap.bind(keyseq, binding),
+                    Err(e) => {
+                        writeln!(err_writer, "bind: {}: {}", line, e)?;
+                        status = 1;
+                    }
+                },
+            }
+        }
+
+        Ok(status)
+    }
+}
+
+/// Lists the key sequences bound to the action, as a comma-separated string
+fn keys_for(bindings: Vec<(&KeySeq, &Binding)>, action: Action) -> String {
+    bindings
+        .into_iter()
+        .filter(|(_, binding)| **binding == Binding::Action(action))
+        .map(|(keyseq, _)| keyseq.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
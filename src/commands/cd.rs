@@ -1,6 +1,13 @@
 // Library
-use crate::helpers;
-use std::path::PathBuf;
+use super::CommandInfo;
+use crate::{
+    helpers::{dirs, vars},
+    shell::Shell,
+};
+use std::{
+    env,
+    path::{Component, Path, PathBuf},
+};
 
 // ----------------
 // CHANGE DIRECTORY
@@ -9,10 +16,18 @@ use std::path::PathBuf;
 /// # The `cd` command.
 /// This command will change the current working directory.
 ///
+/// Without a directory, it changes to `HOME`, and `cd -` changes back to the directory before
+/// (`OLDPWD`). A relative directory that doesn't start with `.` or `..` is looked up in the
+/// directories listed in `CDPATH` first.
+///
 /// ## Example
 ///
 /// ```sh
 /// $ cd /path/to/directory
+/// $ cd -
+/// ```
+/// ```output
+/// /path/to/previous/directory
 /// ```
 pub struct CD;
 
@@ -30,48 +45,133 @@ impl super::CommandInfo for CD {
 
     /// Get the usage of the command.
     fn usage(&self) -> String {
-        String::from("cd [path]")
+        String::from("cd [-L|-P] [dir]")
     }
 }
 
 // Implement the `ExecutableCommand` trait for the `CD` struct.
 impl super::ExecutableCommand for CD {
     /// Execute the `cd` command.
-    /// This command will change the current working directory.
+    /// This command will change the current working directory, and keep `PWD` and `OLDPWD` up
+    /// to date.
+    ///
+    /// With `-L` (the default), symbolic links are followed logically: `PWD` keeps the link, and
+    /// `..` goes back out of it. With `-P`, they are resolved, and `PWD` is the physical path.
+    ///
+    /// The new directory is printed when it comes from `cd -` or from `CDPATH`.
     /// ```sh
-    /// $ cd /path/to/directory
+    /// $ CDPATH=/usr
+    /// $ cd share
+    /// ```
+    /// ```output
+    /// /usr/share
     /// ```
     fn execute<T>(
         &self,
         args: Vec<String>,
-        _writer: &mut T,
+        _shell: &mut Shell,
+        out_writer: &mut T,
         err_writer: &mut T,
-    ) -> std::io::Result<()>
+    ) -> std::io::Result<i32>
     where
         T: std::io::Write,
     {
-        // Get the path from the arguments
-        let path = match args.get(1) {
-            Some(arg) => {
-                if arg.starts_with("~") {
-                    let home_path = helpers::home::get().expect("cd: HOME path not found");
-                    let path = PathBuf::from(arg).components().skip(1).collect::<PathBuf>();
-                    home_path.join(path)
-                } else {
-                    PathBuf::from(arg)
+        // Skip the first argument (the command name)
+        let mut physical = false;
+        let mut operands = Vec::new();
+        let mut args = args.iter().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--" => {
+                    operands.extend(args);
+                    break;
                 }
+                opt if opt.len() > 1 && opt.starts_with('-') => {
+                    for letter in opt.chars().skip(1) {
+                        match letter {
+                            'L' => physical = false,
+                            'P' => physical = true,
+                            _ => {
+                                writeln!(err_writer, "cd: -{}: invalid option", letter)?;
+                                writeln!(err_writer, "cd: usage: {}", self.usage())?;
+                                return Ok(2);
+                            }
+                        }
+                    }
+                }
+                _ => operands.push(arg),
             }
-            None => {
-                writeln!(err_writer, "cd: missing argument")?;
-                return Ok(());
-            }
+        }
+        if operands.len() > 1 {
+            writeln!(err_writer, "cd: too many arguments")?;
+            return Ok(1);
+        }
+
+        // Work out the directory, and whether to print it
+        let (target, print) = match operands.first().map(|dir| dir.as_str()) {
+            None => match vars::get("HOME").filter(|home| !home.is_empty()) {
+                Some(home) => (home, false),
+                None => {
+                    writeln!(err_writer, "cd: HOME not set")?;
+                    return Ok(1);
+                }
+            },
+            Some("-") => match vars::get("OLDPWD").filter(|old| !old.is_empty()) {
+                Some(old) => (old, true),
+                None => {
+                    writeln!(err_writer, "cd: OLDPWD not set")?;
+                    return Ok(1);
+                }
+            },
+            Some(dir) => (dir.to_owned(), false),
         };
+        let (dir, found) = search_cdpath(&target);
 
         // Change the current working directory
-        if let Err(_) = std::env::set_current_dir(&path) {
-            writeln!(err_writer, "{}: No such file or directory", path.display())?;
+        let current = dirs::logical().unwrap_or_default();
+        let path = match physical {
+            true => dir,
+            false => dirs::normalize(&current.join(dir)),
+        };
+        if let Err(e) = env::set_current_dir(&path) {
+            // The message without the error number, like "No such file or directory"
+            let message = e.to_string();
+            let message = message.split(" (os error").next().unwrap_or_default();
+            writeln!(err_writer, "{}: {}", target, message)?;
+            return Ok(1);
         }
 
-        Ok(())
+        let new = match physical {
+            true => env::current_dir()?,
+            false => path,
+        };
+        vars::set("OLDPWD", &current.to_string_lossy());
+        vars::set("PWD", &new.to_string_lossy());
+        vars::export("OLDPWD", true);
+        if print || found {
+            writeln!(out_writer, "{}", new.display())?;
+        }
+        Ok(0)
+    }
+}
+
+/// Looks the directory up in the directories listed in `CDPATH`, unless it starts with `/`,
+/// `.` or `..`. An empty entry stands for the current directory.
+///
+/// Returns the directory to change to, and whether it was found through an entry that isn't
+/// empty, in which case it is printed.
+fn search_cdpath(dir: &str) -> (PathBuf, bool) {
+    let relative = matches!(
+        Path::new(dir).components().next(),
+        Some(Component::Normal(_))
+    );
+    if let (true, Some(cdpath)) = (relative, vars::get("CDPATH")) {
+        for entry in env::split_paths(&cdpath) {
+            let candidate = entry.join(dir);
+            if candidate.is_dir() {
+                return (candidate, !entry.as_os_str().is_empty());
+            }
+        }
     }
+    (PathBuf::from(dir), false)
 }
@@ -0,0 +1,124 @@
+// Library
+use super::CommandInfo;
+use crate::{
+    helpers,
+    shell::{CompSpec, Shell},
+};
+
+// -------
+// COMPGEN
+// -------
+
+/// # The `compgen` command.
+/// This command will print the completion candidates for a word.
+/// It is mostly useful inside the functions registered with `complete -F`.
+///
+/// ## Example
+///
+/// ```sh
+/// $ compgen -W 'start stop status' st
+/// ```
+/// ```output
+/// start
+/// stop
+/// status
+/// ```
+pub struct Compgen;
+
+// Implement the `CommandInfo` trait for the `Compgen` struct.
+impl super::CommandInfo for Compgen {
+    /// Get the name of the command.
+    fn name(&self) -> String {
+        String::from("compgen")
+    }
+
+    /// Get the description of the command.
+    fn description(&self) -> String {
+        String::from("Print the completion candidates for a word")
+    }
+
+    /// Get the usage of the command.
+    fn usage(&self) -> String {
+        String::from("compgen [-cdf] [-W wordlist] [-V varname] [word]")
+    }
+}
+
+// Implement the `ExecutableCommand` trait for the `Compgen` struct.
+impl super::ExecutableCommand for Compgen {
+    /// Execute the `compgen` command.
+    /// The candidates are printed one per line, or stored in the array given with `-V`.
+    /// The exit status is 1 if there are no candidates.
+    /// ```sh
+    /// $ _svc() { compgen -V COMPREPLY -W 'start stop' -- "${COMP_WORDS[COMP_CWORD]}"; }
+    /// ```
+    fn execute<T>(
+        &self,
+        args: Vec<String>,
+        _shell: &mut Shell,
+        out_writer: &mut T,
+        err_writer: &mut T,
+    ) -> std::io::Result<i32>
+    where
+        T: std::io::Write,
+    {
+        let mut spec = CompSpec::default();
+        let mut variable = None;
+        let mut words = Vec::new();
+
+        // Skip the first argument (the command name)
+        let mut args = args.iter().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--" => words.extend(args.by_ref()),
+                "-V" => match args.next() {
+                    Some(name) if helpers::vars::is_name(name) => variable = Some(name),
+                    Some(name) => {
+                        writeln!(err_writer, "compgen: `{}': not a valid identifier", name)?;
+                        return Ok(2);
+                    }
+                    None => {
+                        writeln!(err_writer, "compgen: -V: option requires an argument")?;
+                        writeln!(err_writer, "compgen: usage: {}", self.usage())?;
+                        return Ok(2);
+                    }
+                },
+                opt if opt.starts_with('-') && opt != "-" => {
+                    match super::complete::parse_option(&mut spec, opt, &mut args) {
+                        Ok(true) => {}
+                        Ok(false) => {
+                            writeln!(err_writer, "compgen: {}: invalid option", opt)?;
+                            writeln!(err_writer, "compgen: usage: {}", self.usage())?;
+                            return Ok(2);
+                        }
+                        Err(e) => {
+                            writeln!(err_writer, "compgen: {}", e)?;
+                            writeln!(err_writer, "compgen: usage: {}", self.usage())?;
+                            return Ok(2);
+                        }
+                    }
+                }
+                _ => words.push(arg),
+            }
+        }
+
+        // Functions can only be run by `complete`
+        if spec.function.is_some() {
+            writeln!(err_writer, "compgen: -F: not supported")?;
+            return Ok(2);
+        }
+
+        let word = words.first().map_or("", |w| w.as_str());
+        let candidates = spec.generate(word);
+        let status = if candidates.is_empty() { 1 } else { 0 };
+
+        match variable {
+            Some(name) => helpers::vars::set_array(name, candidates),
+            None => {
+                for candidate in candidates {
+                    writeln!(out_writer, "{}", candidate)?;
+                }
+            }
+        }
+        Ok(status)
+    }
+}
@@ -0,0 +1,165 @@
+// Library
+use super::CommandInfo;
+use crate::shell::{CompSpec, Shell};
+
+// --------
+// COMPLETE
+// --------
+
+/// # The `complete` command.
+/// This command will register how the arguments of a command are completed.
+///
+/// ## Example
+///
+/// ```sh
+/// $ complete -W 'start stop status' svc
+/// $ complete -F _mycmd mycmd
+/// $ complete -p
+/// ```
+/// ```output
+/// complete -F _mycmd mycmd
+/// complete -W 'start stop status' svc
+/// ```
+pub struct Complete;
+
+// Implement the `CommandInfo` trait for the `Complete` struct.
+impl super::CommandInfo for Complete {
+    /// Get the name of the command.
+    fn name(&self) -> String {
+        String::from("complete")
+    }
+
+    /// Get the description of the command.
+    fn description(&self) -> String {
+        String::from("Specify how the arguments of a command are completed")
+    }
+
+    /// Get the usage of the command.
+    fn usage(&self) -> String {
+        String::from("complete [-pr] [-cdf] [-W wordlist] [-F function] [name ...]")
+    }
+}
+
+// Implement the `ExecutableCommand` trait for the `Complete` struct.
+impl super::ExecutableCommand for Complete {
+    /// Execute the `complete` command.
+    /// Without any options (or with `-p`), the completion specifications are printed in a form
+    /// that can be read back in. With `-r` they are removed.
+    /// ```sh
+    /// $ complete -d cd
+    /// $ complete -p cd
+    /// ```
+    /// ```output
+    /// complete -d cd
+    /// ```
+    fn execute<T>(
+        &self,
+        args: Vec<String>,
+        shell: &mut Shell,
+        out_writer: &mut T,
+        err_writer: &mut T,
+    ) -> std::io::Result<i32>
+    where
+        T: std::io::Write,
+    {
+        let mut spec = CompSpec::default();
+        let (mut print, mut remove) = (false, false);
+        let mut names = Vec::new();
+
+        // Skip the first argument (the command name)
+        let mut args = args.iter().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-p" => print = true,
+                "-r" => remove = true,
+                "--" => {
+                    names.extend(args.by_ref().cloned());
+                }
+                opt if opt.starts_with('-') => match parse_option(&mut spec, opt, &mut args) {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        writeln!(err_writer, "complete: {}: invalid option", opt)?;
+                        writeln!(err_writer, "complete: usage: {}", self.usage())?;
+                        return Ok(2);
+                    }
+                    Err(e) => {
+                        writeln!(err_writer, "complete: {}", e)?;
+                        writeln!(err_writer, "complete: usage: {}", self.usage())?;
+                        return Ok(2);
+                    }
+                },
+                name => names.push(name.to_owned()),
+            }
+        }
+
+        let specs = shell.completion_specs();
+
+        // Remove the specs of the given commands, or all of them
+        if remove {
+            if names.is_empty() {
+                specs.clear();
+            }
+            for name in &names {
+                specs.remove(name);
+            }
+            return Ok(0);
+        }
+
+        // Print the specs of the given commands, or all of them
+        if print || spec == CompSpec::default() {
+            let mut status = 0;
+            if names.is_empty() {
+                names = specs.keys().cloned().collect();
+                names.sort();
+            }
+            for name in &names {
+                match specs.get(name) {
+                    Some(spec) => writeln!(out_writer, "complete {} {}", spec, name)?,
+                    None => {
+                        writeln!(
+                            err_writer,
+                            "complete: {}: no completion specification",
+                            name
+                        )?;
+                        status = 1;
+                    }
+                }
+            }
+            return Ok(status);
+        }
+
+        if names.is_empty() {
+            writeln!(err_writer, "complete: usage: {}", self.usage())?;
+            return Ok(2);
+        }
+        for name in names {
+            specs.insert(name, spec.clone());
+        }
+        Ok(0)
+    }
+}
+
+/// Applies an option that tells where the candidates come from to the spec.
+/// Returns `false` if the option isn't one of them, and an error if its value is missing.
+pub(super) fn parse_option<'a>(
+    spec: &mut CompSpec,
+    option: &str,
+    args: &mut impl Iterator<Item = &'a String>,
+) -> Result<bool, String> {
+    match option {
+        "-c" => spec.commands = true,
+        "-d" => spec.directories = true,
+        "-f" => spec.files = true,
+        "-W" | "-F" => {
+            let value = args
+                .next()
+                .ok_or(format!("{}: option requires an argument", option))?;
+            match option {
+                "-W" => spec.wordlist = Some(value.clone()),
+                _ => spec.function = Some(value.clone()),
+            }
+        }
+        _ => return Ok(false),
+    }
+    Ok(true)
+}
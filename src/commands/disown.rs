@@ -0,0 +1,121 @@
+// Library
+use super::CommandInfo;
+use crate::shell::{Shell, State};
+
+// ------
+// DISOWN
+// ------
+
+/// # The `disown` command.
+/// This command will take jobs out of the job table, so that the shell forgets about them
+/// and doesn't hang them up when it exits. Without a job spec, the current job is used.
+///
+/// ## Example
+///
+/// ```sh
+/// $ ./server &
+/// $ disown %1
+/// $ exit  # The server keeps running
+/// ```
+pub struct Disown;
+
+// Implement the `CommandInfo` trait for the `Disown` struct.
+impl super::CommandInfo for Disown {
+    /// Get the name of the command.
+    fn name(&self) -> String {
+        String::from("disown")
+    }
+
+    /// Get the description of the command.
+    fn description(&self) -> String {
+        String::from("Remove jobs from the job table")
+    }
+
+    /// Get the usage of the command.
+    fn usage(&self) -> String {
+        String::from("disown [-h] [-ar] [jobspec ...]")
+    }
+}
+
+// Implement the `ExecutableCommand` trait for the `Disown` struct.
+impl super::ExecutableCommand for Disown {
+    /// Execute the `disown` command.
+    /// - `-h` keeps the jobs in the table, and only makes sure they aren't hung up when the
+    ///   shell exits.
+    /// - `-a` is for all the jobs, and `-r` for all the running ones.
+    ///
+    /// The exit status is 1 if any of the job specs doesn't match a job.
+    /// ```sh
+    /// $ sleep 100 &
+    /// $ disown -h; jobs
+    /// ```
+    /// ```output
+    /// [1] 4242
+    /// [1]+  Running                 sleep 100 &
+    /// ```
+    fn execute<T>(
+        &self,
+        args: Vec<String>,
+        shell: &mut Shell,
+        _out_writer: &mut T,
+        err_writer: &mut T,
+    ) -> std::io::Result<i32>
+    where
+        T: std::io::Write,
+    {
+        let (mut keep, mut all, mut running) = (false, false, false);
+        let mut specs = Vec::new();
+        for arg in args.iter().skip(1) {
+            match arg.strip_prefix('-') {
+                Some(flags) if !flags.is_empty() => {
+                    for flag in flags.chars() {
+                        match flag {
+                            'h' => keep = true,
+                            'a' => all = true,
+                            'r' => running = true,
+                            _ => {
+                                writeln!(err_writer, "disown: -{}: invalid option", flag)?;
+                                writeln!(err_writer, "disown: usage: {}", self.usage())?;
+                                return Ok(2);
+                            }
+                        }
+                    }
+                }
+                _ => specs.push(arg.as_str()),
+            }
+        }
+
+        let jobs = shell.jobs();
+        let mut status = 0;
+        let mut ids = Vec::new();
+        if specs.is_empty() && (all || running) {
+            ids = jobs
+                .iter()
+                .filter(|job| !running || job.state() == State::Running)
+                .map(|job| job.id)
+                .collect();
+        } else if specs.is_empty() {
+            match jobs.find("%+") {
+                Ok(id) => ids.push(id),
+                Err(_) => {
+                    writeln!(err_writer, "disown: current: no such job")?;
+                    status = 1;
+                }
+            }
+        }
+        for spec in specs {
+            match jobs.find(spec) {
+                Ok(id) => ids.push(id),
+                Err(e) => {
+                    writeln!(err_writer, "disown: {}", e)?;
+                    status = 1;
+                }
+            }
+        }
+
+        for id in ids {
+            shell.disown_job(id, keep);
+        }
+        Ok(status)
+    }
+}
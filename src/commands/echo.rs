@@ -1,3 +1,6 @@
+// Library
+use crate::shell::Shell;
+
 // ----
 // ECHO
 // ----
@@ -46,9 +49,10 @@ impl super::ExecutableCommand for Echo {
     fn execute<T>(
         &self,
         args: Vec<String>,
+        _shell: &mut Shell,
         writer: &mut T,
         _err_writer: &mut T,
-    ) -> std::io::Result<()>
+    ) -> std::io::Result<i32>
     where
         T: std::io::Write,
     {
@@ -56,6 +60,6 @@ impl super::ExecutableCommand for Echo {
         let args = &args[1..];
         // Print the arguments to the screen
         writeln!(writer, "{}", args.join(" "))?;
-        Ok(())
+        Ok(0)
     }
 }
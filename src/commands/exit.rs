@@ -1,3 +1,6 @@
+// Library
+use crate::shell::Shell;
+
 // ----
 // EXIT
 // ----
@@ -33,26 +36,38 @@ impl super::CommandInfo for Exit {
 // Implement the `ExecutableCommand` trait for the `Exit` struct.
 impl super::ExecutableCommand for Exit {
     /// Execute the `exit` command.
-    /// Exits the shell and returns an error code.
+    /// Exits the shell and returns an error code. If there are stopped jobs, the shell warns
+    /// about them instead, and only exits if `exit` is run again right away.
+    /// Without an exit code, the shell exits with the status of the last command.
     /// ```sh
     /// $ exit 0 # Exit the shell with a status code of 0
     /// ```
     fn execute<T>(
         &self,
         args: Vec<String>,
+        shell: &mut Shell,
         _out_writer: &mut T,
-        _err_writer: &mut T,
-    ) -> std::io::Result<()>
+        err_writer: &mut T,
+    ) -> std::io::Result<i32>
     where
         T: std::io::Write,
     {
         // Parse the exit code from the arguments
         let exit_code = if args.len() > 1 {
-            args[1].parse::<i32>().unwrap_or(1) // Default to 1 if the exit code is invalid
+            // Default to 1 if the exit code is invalid. Only the lowest 8 bits make it to the exit
+            // status, so `exit 256` is `exit 0` and `exit -1` is `exit 255`.
+            args[1].parse::<i32>().unwrap_or(1) & 0xff
         } else {
-            0 // Default to 0 if no exit code is provided
+            shell.status() // Default to the status of the last command if none is provided
         };
-        // Exit the shell
-        std::process::exit(exit_code);
+        // Stopped jobs hold off the first attempt to exit
+        if !shell.can_exit() {
+            writeln!(err_writer, "There are stopped jobs.")?;
+            return Ok(1);
+        }
+        // The shell exits once the command line is done, after running the `EXIT` trap.
+        // The exit code becomes the exit status the shell exits with.
+        shell.request_exit();
+        Ok(exit_code)
     }
 }
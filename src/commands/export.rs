@@ -0,0 +1,122 @@
+// Library
+use super::CommandInfo;
+use crate::{helpers::vars, parser, shell::Shell};
+
+// ------
+// EXPORT
+// ------
+
+/// # The `export` command.
+/// This command will put variables in the environment of the programs the shell runs.
+/// Variables the shell sets itself stay in the shell unless they are exported, while the ones
+/// it was started with are exported already.
+///
+/// ## Example
+///
+/// ```sh
+/// $ GREETING=hello
+/// $ sh -c 'echo "[$GREETING]"'
+/// $ export GREETING
+/// $ sh -c 'echo "[$GREETING]"'
+/// ```
+/// ```output
+/// []
+/// [hello]
+/// ```
+pub struct Export;
+
+// Implement the `CommandInfo` trait for the `Export` struct.
+impl super::CommandInfo for Export {
+    /// Get the name of the command.
+    fn name(&self) -> String {
+        String::from("export")
+    }
+
+    /// Get the description of the command.
+    fn description(&self) -> String {
+        String::from("Export variables to the programs the shell runs")
+    }
+
+    /// Get the usage of the command.
+    fn usage(&self) -> String {
+        String::from("export [-n] [-p] [name[=value] ...]")
+    }
+}
+
+// Implement the `ExecutableCommand` trait for the `Export` struct.
+impl super::ExecutableCommand for Export {
+    /// Execute the `export` command.
+    /// - `export name` exports the variable, and `export name=value` sets it as well.
+    /// - `export -n name` stops exporting the variable, but keeps it in the shell.
+    /// - `export` alone, or `export -p`, lists the exported variables as commands that would
+    ///   export them again.
+    /// ```sh
+    /// $ export EDITOR=vim
+    /// $ export -p
+    /// ```
+    /// ```output
+    /// export EDITOR=vim
+    /// export HOME=/home/user
+    /// ```
+    fn execute<T>(
+        &self,
+        args: Vec<String>,
+        _shell: &mut Shell,
+        out_writer: &mut T,
+        err_writer: &mut T,
+    ) -> std::io::Result<i32>
+    where
+        T: std::io::Write,
+    {
+        // Skip the first argument (the command name)
+        let mut args = args.into_iter().skip(1).peekable();
+        let (mut exported, mut print) = (true, false);
+        while let Some(arg) = args.next_if(|arg| arg.starts_with('-')) {
+            if arg == "--" {
+                break;
+            }
+            for letter in arg.chars().skip(1) {
+                match letter {
+                    'n' => exported = false,
+                    'p' => print = true,
+                    _ => {
+                        writeln!(err_writer, "export: -{}: invalid option", letter)?;
+                        writeln!(err_writer, "export: usage: {}", self.usage())?;
+                        return Ok(2);
+                    }
+                }
+            }
+        }
+
+        let names: Vec<String> = args.collect();
+        if names.is_empty() || print {
+            for (name, value) in vars::exported() {
+                match value {
+                    Some(value) => {
+                        writeln!(out_writer, "export {}={}", name, parser::quote(&value))?
+                    }
+                    None => writeln!(out_writer, "export {}", name)?,
+                }
+            }
+            return Ok(0);
+        }
+
+        let mut status = 0;
+        for arg in names {
+            let (name, value) = match arg.split_once('=') {
+                Some((name, value)) => (name, Some(value)),
+                None => (arg.as_str(), None),
+            };
+            if !vars::is_name(name) {
+                writeln!(err_writer, "export: `{}': not a valid identifier", arg)?;
+                status = 1;
+                continue;
+            }
+            if let Some(value) = value {
+                vars::set(name, value);
+            }
+            vars::export(name, exported);
+        }
+        Ok(status)
+    }
+}
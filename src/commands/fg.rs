@@ -0,0 +1,81 @@
+// Library
+use super::CommandInfo;
+use crate::shell::Shell;
+
+// --
+// FG
+// --
+
+/// # The `fg` command.
+/// This command will bring a job to the foreground, continuing it if it was stopped,
+/// and wait for it. Without a job spec, the current job is used.
+///
+/// ## Example
+///
+/// ```sh
+/// $ vim notes.txt  # Ctrl+Z
+/// $ fg %vim
+/// ```
+pub struct Fg;
+
+// Implement the `CommandInfo` trait for the `Fg` struct.
+impl super::CommandInfo for Fg {
+    /// Get the name of the command.
+    fn name(&self) -> String {
+        String::from("fg")
+    }
+
+    /// Get the description of the command.
+    fn description(&self) -> String {
+        String::from("Move a job to the foreground")
+    }
+
+    /// Get the usage of the command.
+    fn usage(&self) -> String {
+        String::from("fg [job_spec]")
+    }
+}
+
+// Implement the `ExecutableCommand` trait for the `Fg` struct.
+impl super::ExecutableCommand for Fg {
+    /// Execute the `fg` command.
+    /// The job's command line is printed, and the exit status is the job's.
+    /// ```sh
+    /// $ sleep 100 &
+    /// $ fg
+    /// ```
+    /// ```output
+    /// [1] 4242
+    /// sleep 100
+    /// ```
+    fn execute<T>(
+        &self,
+        args: Vec<String>,
+        shell: &mut Shell,
+        out_writer: &mut T,
+        err_writer: &mut T,
+    ) -> std::io::Result<i32>
+    where
+        T: std::io::Write,
+    {
+        if args.len() > 2 {
+            writeln!(err_writer, "fg: usage: {}", self.usage())?;
+            return Ok(2);
+        }
+        let spec = args.get(1).map_or("%+", String::as_str);
+        let id = match shell.jobs().find(spec) {
+            Ok(id) => id,
+            Err(_) if args.len() == 1 => {
+                writeln!(err_writer, "fg: current: no such job")?;
+                return Ok(1);
+            }
+            Err(e) => {
+                writeln!(err_writer, "fg: {}", e)?;
+                return Ok(1);
+            }
+        };
+
+        shell.foreground_job(id, out_writer)?;
+        Ok(shell.status())
+    }
+}
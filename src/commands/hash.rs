@@ -0,0 +1,151 @@
+// Library
+use super::CommandInfo;
+use crate::{helpers, shell::Shell};
+
+// ----
+// HASH
+// ----
+
+/// # The `hash` command.
+/// This command will show and change the table of remembered command locations.
+/// Commands are remembered when they are run, so that `PATH` doesn't have to be searched again.
+///
+/// ## Example
+///
+/// ```sh
+/// $ ls > /dev/null
+/// $ hash
+/// ```
+/// ```output
+/// hits    command
+///    1    /usr/bin/ls
+/// ```
+pub struct Hash;
+
+// Implement the `CommandInfo` trait for the `Hash` struct.
+impl super::CommandInfo for Hash {
+    /// Get the name of the command.
+    fn name(&self) -> String {
+        String::from("hash")
+    }
+
+    /// Get the description of the command.
+    fn description(&self) -> String {
+        String::from("Remember or show the locations of commands")
+    }
+
+    /// Get the usage of the command.
+    fn usage(&self) -> String {
+        String::from("hash [-r] [-p pathname] [-dt] [name ...]")
+    }
+}
+
+// Implement the `ExecutableCommand` trait for the `Hash` struct.
+impl super::ExecutableCommand for Hash {
+    /// Execute the `hash` command.
+    /// - Without arguments, the remembered commands are listed.
+    /// - `-r` forgets all commands, and `-d` forgets the given ones.
+    /// - `-p pathname` remembers the given name as the pathname, without searching `PATH`.
+    /// - `-t` prints the remembered location of each name.
+    /// - Any other name is looked up in `PATH` and remembered.
+    /// ```sh
+    /// $ hash -p /usr/local/bin/python3 python
+    /// $ hash -t python
+    /// ```
+    /// ```output
+    /// /usr/local/bin/python3
+    /// ```
+    fn execute<T>(
+        &self,
+        args: Vec<String>,
+        _shell: &mut Shell,
+        out_writer: &mut T,
+        err_writer: &mut T,
+    ) -> std::io::Result<i32>
+    where
+        T: std::io::Write,
+    {
+        let (mut reset, mut forget, mut print) = (false, false, false);
+        let mut pathname = None;
+        let mut names = Vec::new();
+
+        // Skip the first argument (the command name)
+        let mut args = args.iter().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-r" => reset = true,
+                "-d" => forget = true,
+                "-t" => print = true,
+                "-p" => match args.next() {
+                    Some(path) => pathname = Some(path),
+                    None => {
+                        writeln!(err_writer, "hash: -p: option requires an argument")?;
+                        writeln!(err_writer, "hash: usage: {}", self.usage())?;
+                        return Ok(2);
+                    }
+                },
+                opt if opt.starts_with('-') => {
+                    writeln!(err_writer, "hash: {}: invalid option", opt)?;
+                    writeln!(err_writer, "hash: usage: {}", self.usage())?;
+                    return Ok(2);
+                }
+                name => names.push(name),
+            }
+        }
+
+        // Forget everything, before remembering any of the names
+        if reset {
+            helpers::path::forget(None);
+            if names.is_empty() {
+                return Ok(0);
+            }
+        }
+
+        // List the remembered commands
+        if names.is_empty() {
+            if pathname.is_some() || forget || print {
+                writeln!(err_writer, "hash: usage: {}", self.usage())?;
+                return Ok(2);
+            }
+            let hashed = helpers::path::hashed();
+            if hashed.is_empty() {
+                writeln!(out_writer, "hash: hash table empty")?;
+                return Ok(0);
+            }
+            writeln!(out_writer, "hits\tcommand")?;
+            for (_, entry) in hashed {
+                writeln!(out_writer, "{:>4}\t{}", entry.hits, entry.path.display())?;
+            }
+            return Ok(0);
+        }
+
+        let mut status = 0;
+        for name in &names {
+            if let Some(path) = pathname {
+                helpers::path::remember(name, path.into());
+            } else if forget {
+                if !helpers::path::forget(Some(name)) {
+                    writeln!(err_writer, "hash: {}: not found", name)?;
+                    status = 1;
+                }
+            } else if print {
+                let hashed = helpers::path::hashed();
+                match hashed.iter().find(|(n, _)| n == name) {
+                    // With several names, each location is labelled with its name
+                    Some((_, entry)) if names.len() > 1 => {
+                        writeln!(out_writer, "{}\t{}", name, entry.path.display())?
+                    }
+                    Some((_, entry)) => writeln!(out_writer, "{}", entry.path.display())?,
+                    None => {
+                        writeln!(err_writer, "hash: {}: not found", name)?;
+                        status = 1;
+                    }
+                }
+            } else if name.contains('/') || helpers::path::find_executable(name).is_none() {
+                writeln!(err_writer, "hash: {}: not found", name)?;
+                status = 1;
+            }
+        }
+        Ok(status)
+    }
+}
@@ -0,0 +1,110 @@
+// Library
+use super::CommandInfo;
+use crate::shell::Shell;
+
+// ----
+// JOBS
+// ----
+
+/// # The `jobs` command.
+/// This command will list the jobs that are running in the background or are stopped.
+/// The current job is marked with `+`, and the previous one with `-`.
+///
+/// ## Example
+///
+/// ```sh
+/// $ sleep 100 &
+/// $ jobs
+/// ```
+/// ```output
+/// [1]+  Running                 sleep 100 &
+/// ```
+pub struct Jobs;
+
+// Implement the `CommandInfo` trait for the `Jobs` struct.
+impl super::CommandInfo for Jobs {
+    /// Get the name of the command.
+    fn name(&self) -> String {
+        String::from("jobs")
+    }
+
+    /// Get the description of the command.
+    fn description(&self) -> String {
+        String::from("Display the status of jobs")
+    }
+
+    /// Get the usage of the command.
+    fn usage(&self) -> String {
+        String::from("jobs [-lp] [jobspec ...]")
+    }
+}
+
+// Implement the `ExecutableCommand` trait for the `Jobs` struct.
+impl super::ExecutableCommand for Jobs {
+    /// Execute the `jobs` command.
+    /// - `-l` lists the process group ID of each job as well.
+    /// - `-p` only lists the process group IDs.
+    /// - With job specs, only those jobs are listed.
+    /// ```sh
+    /// $ jobs -l %sleep
+    /// ```
+    /// ```output
+    /// [1]+ 4242 Running                 sleep 100 &
+    /// ```
+    fn execute<T>(
+        &self,
+        args: Vec<String>,
+        shell: &mut Shell,
+        out_writer: &mut T,
+        err_writer: &mut T,
+    ) -> std::io::Result<i32>
+    where
+        T: std::io::Write,
+    {
+        let (mut long, mut pids) = (false, false);
+        let mut specs = Vec::new();
+        for arg in args.iter().skip(1) {
+            match arg.as_str() {
+                "-l" => long = true,
+                "-p" => pids = true,
+                "-lp" | "-pl" => (long, pids) = (true, true),
+                opt if opt.starts_with('-') => {
+                    writeln!(err_writer, "jobs: {}: invalid option", opt)?;
+                    writeln!(err_writer, "jobs: usage: {}", self.usage())?;
+                    return Ok(2);
+                }
+                spec => specs.push(spec),
+            }
+        }
+
+        // Finished jobs are reported (and forgotten) first
+        shell.notify_jobs();
+        let jobs = shell.jobs();
+
+        let mut status = 0;
+        let ids: Vec<usize> = if specs.is_empty() {
+            jobs.iter().map(|job| job.id).collect()
+        } else {
+            let mut ids = Vec::new();
+            for spec in specs {
+                match jobs.find(spec) {
+                    Ok(id) => ids.push(id),
+                    Err(e) => {
+                        writeln!(err_writer, "jobs: {}", e)?;
+                        status = 1;
+                    }
+                }
+            }
+            ids
+        };
+
+        for job in ids.into_iter().filter_map(|id| jobs.get(id)) {
+            if pids {
+                writeln!(out_writer, "{}", job.pgid)?;
+            } else {
+                writeln!(out_writer, "{}", job.describe(jobs.marker(job.id), long))?;
+            }
+        }
+        Ok(status)
+    }
+}
@@ -0,0 +1,196 @@
+// Library
+use super::CommandInfo;
+use crate::{helpers::sys, shell::Shell};
+
+// ----
+// KILL
+// ----
+
+/// # The `kill` command.
+/// This command will send a signal to processes or jobs. The signal is `TERM` unless
+/// another one is named, and `kill -l` lists the signals.
+///
+/// ## Example
+///
+/// ```sh
+/// $ kill %1
+/// $ kill -s HUP 4242
+/// $ kill -9 %make
+/// ```
+pub struct Kill;
+
+// Implement the `CommandInfo` trait for the `Kill` struct.
+impl super::CommandInfo for Kill {
+    /// Get the name of the command.
+    fn name(&self) -> String {
+        String::from("kill")
+    }
+
+    /// Get the description of the command.
+    fn description(&self) -> String {
+        String::from("Send a signal to a job or process")
+    }
+
+    /// Get the usage of the command.
+    fn usage(&self) -> String {
+        String::from(
+            "kill [-s sigspec | -n signum | -sigspec] pid | jobspec ... or kill -l [sigspec]",
+        )
+    }
+}
+
+// Implement the `ExecutableCommand` trait for the `Kill` struct.
+impl super::ExecutableCommand for Kill {
+    /// Execute the `kill` command.
+    /// - `-s SIG`, `-n NUM`, `-SIG` and `-NUM` choose the signal, by name (with or without
+    ///   the `SIG` prefix) or by number.
+    /// - `-l` lists the signals, or with arguments, turns signal numbers into names and names
+    ///   into numbers. An exit status like 143 is taken as the signal that caused it.
+    ///
+    /// The exit status is 1 if any of the processes or jobs couldn't be signaled.
+    /// ```sh
+    /// $ kill -l 15 KILL
+    /// ```
+    /// ```output
+    /// TERM
+    /// 9
+    /// ```
+    fn execute<T>(
+        &self,
+        args: Vec<String>,
+        shell: &mut Shell,
+        out_writer: &mut T,
+        err_writer: &mut T,
+    ) -> std::io::Result<i32>
+    where
+        T: std::io::Write,
+    {
+        let mut signal = sys::SIGTERM;
+        let mut rest = args.iter().skip(1).map(String::as_str).peekable();
+
+        // The signal, if there is one, comes first
+        match rest.peek().copied() {
+            None => {
+                writeln!(err_writer, "kill: usage: {}", self.usage())?;
+                return Ok(2);
+            }
+            Some("-l" | "-L") => {
+                rest.next();
+                return list(rest.collect(), out_writer, err_writer);
+            }
+            Some(opt @ ("-s" | "-n")) => {
+                rest.next();
+                let Some(spec) = rest.next() else {
+                    writeln!(err_writer, "kill: {}: option requires an argument", opt)?;
+                    writeln!(err_writer, "kill: usage: {}", self.usage())?;
+                    return Ok(2);
+                };
+                let Some(number) = sys::signal_number(spec) else {
+                    writeln!(err_writer, "kill: {}: invalid signal specification", spec)?;
+                    return Ok(1);
+                };
+                signal = number;
+            }
+            Some("--") => {
+                rest.next();
+            }
+            Some(spec) if spec.starts_with('-') && spec.len() > 1 => {
+                rest.next();
+                let Some(number) = sys::signal_number(&spec[1..]) else {
+                    writeln!(
+                        err_writer,
+                        "kill: {}: invalid signal specification",
+                        &spec[1..]
+                    )?;
+                    return Ok(1);
+                };
+                signal = number;
+            }
+            Some(_) => {}
+        }
+
+        let targets: Vec<&str> = rest.collect();
+        if targets.is_empty() {
+            writeln!(err_writer, "kill: usage: {}", self.usage())?;
+            return Ok(2);
+        }
+
+        let mut status = 0;
+        for target in targets {
+            let result = if target.starts_with('%') {
+                match shell.jobs().find(target) {
+                    Ok(id) => shell.signal_job(id, signal),
+                    Err(e) => {
+                        writeln!(err_writer, "kill: {}", e)?;
+                        status = 1;
+                        continue;
+                    }
+                }
+            } else if let Ok(pid) = target.parse::<i32>() {
+                sys::kill(pid, signal)
+            } else {
+                writeln!(
+                    err_writer,
+                    "kill: {}: arguments must be process or job IDs",
+                    target
+                )?;
+                status = 1;
+                continue;
+            };
+
+            if let Err(e) = result {
+                // Like `No such process`, without the error number
+                let message = e.to_string();
+                let message = message.split(" (os error").next().unwrap_or_default();
+                writeln!(err_writer, "kill: ({}) - {}", target, message)?;
+                status = 1;
+            }
+        }
+        Ok(status)
+    }
+}
+
+/// Lists the signals for `kill -l`. Without arguments, all of them are listed by number. Otherwise each number is turned into a name, and each name into a number.
+fn list<T>(specs: Vec<&str>, out_writer: &mut T, err_writer: &mut T) -> std::io::Result<i32>
+where
+    T: std::io::Write,
+{
+    if specs.is_empty() {
+        print_signals(out_writer)?;
+        return Ok(0);
+    }
+
+    let mut status = 0;
+    for spec in specs {
+        let found = match spec.parse::<i32>() {
+            // An exit status like 143 is for the signal 15
+            Ok(number) => {
+                let number = if number > 128 { number - 128 } else { number };
+                sys::signal_name(number).map(String::from)
+            }
+            Err(_) => sys::signal_number(spec).map(|number| number.to_string()),
+        };
+        match found {
+            Some(found) => writeln!(out_writer, "{}", found)?,
+            None => {
+                writeln!(err_writer, "kill: {}: invalid signal specification", spec)?;
+                status = 1;
+            }
+        }
+    }
+    Ok(status)
+}
+
+/// Prints all the signals by number, five to a line, like `15) SIGTERM`
+pub(super) fn print_signals<T>(out_writer: &mut T) -> std::io::Result<()>
+where
+    T: std::io::Write,
+{
+    let signals: Vec<String> = sys::signals()
+        .map(|(number, name)| format!("{:>2}) SIG{}", number, name))
+        .collect();
+    for row in signals.chunks(5) {
+        writeln!(out_writer, "{}", row.join("\t"))?;
+    }
+    Ok(())
+}
@@ -1,11 +1,13 @@
 // Library
-use crate::helpers;
+use crate::{helpers, shell::Shell};
 
 // Modules
 mod echo;
 use echo::Echo;
 mod exit;
 use exit::Exit;
+mod export;
+use export::Export;
 mod unknown;
 use unknown::Unknown;
 mod r#type;
@@ -16,19 +18,51 @@ mod pwd;
 use pwd::PWD;
 mod cd;
 use cd::CD;
+mod bind;
+use bind::Bind;
+mod complete;
+use complete::Complete;
+mod compgen;
+use compgen::Compgen;
+mod shopt;
+use shopt::Shopt;
+mod hash;
+use hash::Hash;
+mod jobs;
+use jobs::Jobs;
+mod fg;
+use fg::Fg;
+mod bg;
+use bg::Bg;
+mod kill;
+use kill::Kill;
+mod wait;
+use wait::Wait;
+mod disown;
+use disown::Disown;
+mod trap;
+use trap::Trap;
+mod set;
+use set::Set;
+mod source;
+use source::Source;
+mod r#return;
+use r#return::Return;
 
 // --------
 // COMMANDS
 // --------
 
 /// A trait that defines a command that can be executed.
+/// Executing a command returns its exit status.
 pub trait ExecutableCommand {
     fn execute<T>(
         &self,
         args: Vec<String>,
+        shell: &mut Shell,
         out_writer: &mut T,
         err_writer: &mut T,
-    ) -> std::io::Result<()>
+    ) -> std::io::Result<i32>
     where
         T: std::io::Write;
 }
@@ -54,21 +88,29 @@ pub enum Command {
 // Provide an unified interface for executing commands.
 impl Command {
     /// Execute the command. This function will delegate the execution to the appropriate command.
+    /// Returns the exit status of the command.
     pub fn execute<T>(
         &self,
         args: Vec<String>,
+        shell: &mut Shell,
         out_writer: &mut T,
         err_writer: &mut T,
-    ) -> std::io::Result<()>
+    ) -> std::io::Result<i32>
     where
         T: std::io::Write,
     {
         match self {
-            Command::Builtin(builtin) => builtin.execute(args, out_writer, err_writer),
-            Command::Program(path) => path.execute(args, out_writer, err_writer),
-            Command::Unknown => Unknown.execute(args, out_writer, err_writer),
+            Command::Builtin(builtin) => builtin.execute(args, shell, out_writer, err_writer),
+            Command::Program(path) => path.execute(args, shell, out_writer, err_writer),
+            Command::Unknown => Unknown.execute(args, shell, out_writer, err_writer),
         }
     }
+
+    /// Whether the name resolves to a builtin or a program, like [`Command::from_str`](std::str::FromStr),
+    /// but without remembering the program in the hash table
+    pub fn exists(name: &str) -> bool {
+        name.parse::<Builtin>().is_ok() || helpers::path::lookup(name).is_some()
+    }
 }
 
 // Implement the FromStr trait for the Command enum to parse a string into a Command
@@ -90,13 +132,36 @@ impl std::str::FromStr for Command {
 // BUILTIN COMMANDS
 // ----------------
 
+/// The names of the built-in commands in the shell
+pub const BUILTINS: &[&str] = &[
+    ".", "bg", "bind", "cd", "compgen", "complete", "disown", "echo", "exit", "export", "fg",
+    "hash", "jobs", "kill", "pwd", "return", "set", "shopt", "source", "trap", "type", "wait",
+];
+
 /// The built-in commands in the shell
+#[allow(clippy::upper_case_acronyms)]
 pub enum Builtin {
     Echo(Echo),
     Exit(Exit),
+    Export(Export),
     Type(Type),
     PWD(PWD),
     CD(CD),
+    Bind(Bind),
+    Complete(Complete),
+    Compgen(Compgen),
+    Shopt(Shopt),
+    Hash(Hash),
+    Jobs(Jobs),
+    Fg(Fg),
+    Bg(Bg),
+    Kill(Kill),
+    Wait(Wait),
+    Disown(Disown),
+    Trap(Trap),
+    Set(Set),
+    Source(Source),
+    Return(Return),
 }
 
 // Implement the Command trait for the Builtin commands
@@ -105,18 +170,82 @@ impl ExecutableCommand for Builtin {
     fn execute<T>(
         &self,
         args: Vec<String>,
+        shell: &mut Shell,
         out_writer: &mut T,
         err_writer: &mut T,
-    ) -> std::io::Result<()>
+    ) -> std::io::Result<i32>
     where
         T: std::io::Write,
     {
         match self {
-            Builtin::Echo(cmd) => cmd.execute(args, out_writer, err_writer),
-            Builtin::Exit(cmd) => cmd.execute(args, out_writer, err_writer),
-            Builtin::Type(cmd) => cmd.execute(args, out_writer, err_writer),
-            Builtin::PWD(cmd) => cmd.execute(args, out_writer, err_writer),
-            Builtin::CD(cmd) => cmd.execute(args, out_writer, err_writer),
+            Builtin::Echo(cmd) => cmd.execute(args, shell, out_writer, err_writer),
+            Builtin::Exit(cmd) => cmd.execute(args, shell, out_writer, err_writer),
+            Builtin::Export(cmd) => cmd.execute(args, shell, out_writer, err_writer),
+            Builtin::Type(cmd) => cmd.execute(args, shell, out_writer, err_writer),
+            Builtin::PWD(cmd) => cmd.execute(args, shell, out_writer, err_writer),
+            Builtin::CD(cmd) => cmd.execute(args, shell, out_writer, err_writer),
+            Builtin::Bind(cmd) => cmd.execute(args, shell, out_writer, err_writer),
+            Builtin::Complete(cmd) => cmd.execute(args, shell, out_writer, err_writer),
+            Builtin::Compgen(cmd) => cmd.execute(args, shell, out_writer, err_writer),
+            Builtin::Shopt(cmd) => cmd.execute(args, shell, out_writer, err_writer),
+            Builtin::Hash(cmd) => cmd.execute(args, shell, out_writer, err_writer),
+            Builtin::Jobs(cmd) => cmd.execute(args, shell, out_writer, err_writer),
+            Builtin::Fg(cmd) => cmd.execute(args, shell, out_writer, err_writer),
+            Builtin::Bg(cmd) => cmd.execute(args, shell, out_writer, err_writer),
+            Builtin::Kill(cmd) => cmd.execute(args, shell, out_writer, err_writer),
+            Builtin::Wait(cmd) => cmd.execute(args, shell, out_writer, err_writer),
+            Builtin::Disown(cmd) => cmd.execute(args, shell, out_writer, err_writer),
+            Builtin::Trap(cmd) => cmd.execute(args, shell, out_writer, err_writer),
+            Builtin::Set(cmd) => cmd.execute(args, shell, out_writer, err_writer),
+            Builtin::Source(cmd) => cmd.execute(args, shell, out_writer, err_writer),
+            Builtin::Return(cmd) => cmd.execute(args, shell, out_writer, err_writer),
+        }
+    }
+}
+
+// Implement the CommandInfo trait for the Builtin commands
+impl CommandInfo for Builtin {
+    /// Get the name of the built-in command
+    fn name(&self) -> String {
+        self.info().name()
+    }
+
+    /// Get the description of the built-in command
+    fn description(&self) -> String {
+        self.info().description()
+    }
+
+    /// Get the usage of the built-in command
+    fn usage(&self) -> String {
+        self.info().usage()
+    }
+}
+
+impl Builtin {
+    /// The information about the built-in command
+    fn info(&self) -> &dyn CommandInfo {
+        match self {
+            Builtin::Echo(cmd) => cmd,
+            Builtin::Exit(cmd) => cmd,
+            Builtin::Export(cmd) => cmd,
+            Builtin::Type(cmd) => cmd,
+            Builtin::PWD(cmd) => cmd,
+            Builtin::CD(cmd) => cmd,
+            Builtin::Bind(cmd) => cmd,
+            Builtin::Complete(cmd) => cmd,
+            Builtin::Compgen(cmd) => cmd,
+            Builtin::Shopt(cmd) => cmd,
+            Builtin::Hash(cmd) => cmd,
+            Builtin::Jobs(cmd) => cmd,
+            Builtin::Fg(cmd) => cmd,
+            Builtin::Bg(cmd) => cmd,
+            Builtin::Kill(cmd) => cmd,
+            Builtin::Wait(cmd) => cmd,
+            Builtin::Disown(cmd) => cmd,
+            Builtin::Trap(cmd) => cmd,
+            Builtin::Set(cmd) => cmd,
+            Builtin::Source(cmd) => cmd,
+            Builtin::Return(cmd) => cmd,
         }
     }
 }
@@ -129,9 +258,25 @@ impl std::str::FromStr for Builtin {
         match s {
             "echo" => Ok(Builtin::Echo(Echo)),
             "exit" => Ok(Builtin::Exit(Exit)),
+            "export" => Ok(Builtin::Export(Export)),
             "type" => Ok(Builtin::Type(Type)),
             "pwd" => Ok(Builtin::PWD(PWD)),
             "cd" => Ok(Builtin::CD(CD)),
+            "bind" => Ok(Builtin::Bind(Bind)),
+            "complete" => Ok(Builtin::Complete(Complete)),
+            "compgen" => Ok(Builtin::Compgen(Compgen)),
+            "shopt" => Ok(Builtin::Shopt(Shopt)),
+            "hash" => Ok(Builtin::Hash(Hash)),
+            "jobs" => Ok(Builtin::Jobs(Jobs)),
+            "fg" => Ok(Builtin::Fg(Fg)),
+            "bg" => Ok(Builtin::Bg(Bg)),
+            "kill" => Ok(Builtin::Kill(Kill)),
+            "wait" => Ok(Builtin::Wait(Wait)),
+            "disown" => Ok(Builtin::Disown(Disown)),
+            "trap" => Ok(Builtin::Trap(Trap)),
+            "set" => Ok(Builtin::Set(Set)),
+            "source" | "." => Ok(Builtin::Source(Source)),
+            "return" => Ok(Builtin::Return(Return)),
             _ => Err(()),
         }
     }
@@ -1,3 +1,6 @@
+// Library
+use crate::{helpers::sys, shell::Shell};
+
 // -------
 // PROGRAM
 // -------
@@ -14,6 +17,11 @@ impl Program {
     pub fn new(path: String) -> Self {
         Program { path }
     }
+
+    /// The path to the program
+    pub fn path(&self) -> &str {
+        &self.path
+    }
 }
 
 impl std::fmt::Display for Program {
@@ -30,24 +38,44 @@ impl super::ExecutableCommand for Program {
     fn execute<T>(
         &self,
         args: Vec<String>,
+        _shell: &mut Shell,
         out_writer: &mut T,
         err_writer: &mut T,
-    ) -> std::io::Result<()>
+    ) -> std::io::Result<i32>
     where
         T: std::io::Write,
     {
-        // Execute the program with the given arguments
-        let base_path = std::path::PathBuf::from(&self.path);
-        let base_path = base_path.file_name().unwrap();
-        let output = std::process::Command::new(base_path)
-            .args(&args[1..])
-            .output()?;
+        // Execute the program with the given arguments. The path comes from the hash table,
+        // but the program still sees the name it was called by.
+        let mut command = std::process::Command::new(&self.path);
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::CommandExt;
+            command.arg0(&args[0]);
+            // The program gets back the signals the shell ignores.
+            // SAFETY: the closure only makes async-signal-safe calls
+            unsafe {
+                command.pre_exec(|| {
+                    for signal in sys::INTERACTIVE_SIGNALS {
+                        sys::ignore_signal(signal, false);
+                    }
+                    Ok(())
+                });
+            }
+        }
+        let output = command.args(&args[1..]).output()?;
 
         // Write the output to the standard output
         out_writer.write_all(&output.stdout)?;
         // Write the error output to the standard error
         err_writer.write_all(&output.stderr)?;
 
-        Ok(())
+        // Programs that were terminated by a signal don't have an exit code, and get 128 plus
+        // the signal number instead
+        #[cfg(unix)]
+        if let Some(signal) = std::os::unix::process::ExitStatusExt::signal(&output.status) {
+            return Ok(128 + signal);
+        }
+        Ok(output.status.code().unwrap_or(1))
     }
 }
@@ -1,3 +1,7 @@
+// Library
+use super::CommandInfo;
+use crate::{helpers::dirs, shell::Shell};
+
 // -----------------------
 // PRINT WORKING DIRECTORY
 // -----------------------
@@ -13,6 +17,7 @@
 /// ```output
 /// /path/to/current/directory
 /// ```
+#[allow(clippy::upper_case_acronyms)]
 pub struct PWD;
 
 impl super::CommandInfo for PWD {
@@ -28,7 +33,7 @@ impl super::CommandInfo for PWD {
 
     /// Get the usage of the command.
     fn usage(&self) -> String {
-        String::from("pwd")
+        String::from("pwd [-LP]")
     }
 }
 
@@ -36,6 +41,8 @@ impl super::CommandInfo for PWD {
 impl super::ExecutableCommand for PWD {
     /// Execute the `pwd` command.
     /// This command will print the current working directory to the screen.
+    /// With `-L` (the default), it is the directory as `cd` reached it, with any symbolic links
+    /// on the way. With `-P`, the links are resolved.
     /// ```sh
     /// $ pwd
     /// ```
@@ -44,19 +51,43 @@ impl super::ExecutableCommand for PWD {
     /// ```
     fn execute<T>(
         &self,
-        _args: Vec<String>,
+        args: Vec<String>,
+        _shell: &mut Shell,
         out_writer: &mut T,
-        _err_writer: &mut T,
-    ) -> std::io::Result<()>
+        err_writer: &mut T,
+    ) -> std::io::Result<i32>
     where
         T: std::io::Write,
     {
+        // Skip the first argument (the command name)
+        let mut physical = false;
+        for arg in args
+            .iter()
+            .skip(1)
+            .take_while(|arg| arg.starts_with('-') && *arg != "--")
+        {
+            for letter in arg.chars().skip(1) {
+                match letter {
+                    'L' => physical = false,
+                    'P' => physical = true,
+                    _ => {
+                        writeln!(err_writer, "pwd: -{}: invalid option", letter)?;
+                        writeln!(err_writer, "pwd: usage: {}", self.usage())?;
+                        return Ok(2);
+                    }
+                }
+            }
+        }
+
         // Get the current working directory
-        let cwd = std::env::current_dir()?;
+        let cwd = match physical {
+            true => std::env::current_dir()?,
+            false => dirs::logical().map_or_else(std::env::current_dir, Ok)?,
+        };
 
         // Print the current working directory
         writeln!(out_writer, "{}", cwd.display())?;
 
-        Ok(())
+        Ok(0)
     }
 }
@@ -0,0 +1,81 @@
+// Library
+use crate::shell::Shell;
+
+// ------
+// RETURN
+// ------
+
+/// # The `return` command.
+/// This command will end the function or sourced file that's running, with the given exit
+/// status, or the one of the last command.
+///
+/// ## Example
+///
+/// ```sh
+/// $ check() { echo checking; return 3; echo not here; }
+/// $ check
+/// ```
+/// ```output
+/// checking
+/// ```
+pub struct Return;
+
+// Implement the `CommandInfo` trait for the `Return` struct.
+impl super::CommandInfo for Return {
+    /// Get the name of the command.
+    fn name(&self) -> String {
+        String::from("return")
+    }
+
+    /// Get the description of the command.
+    fn description(&self) -> String {
+        String::from("Return from a function or sourced file")
+    }
+
+    /// Get the usage of the command.
+    fn usage(&self) -> String {
+        String::from("return [n]")
+    }
+}
+
+// Implement the `ExecutableCommand` trait for the `Return` struct.
+impl super::ExecutableCommand for Return {
+    /// Execute the `return` command.
+    /// The commands after it in the function or file are skipped. Outside of one, it fails.
+    /// ```sh
+    /// $ return
+    /// ```
+    /// ```output
+    /// return: can only `return' from a function or sourced script
+    /// ```
+    fn execute<T>(
+        &self,
+        args: Vec<String>,
+        shell: &mut Shell,
+        _out_writer: &mut T,
+        err_writer: &mut T,
+    ) -> std::io::Result<i32>
+    where
+        T: std::io::Write,
+    {
+        let status = match args.get(1) {
+            Some(arg) => match arg.parse::<i32>() {
+                // Like an exit status, it only keeps its lowest 8 bits
+                Ok(status) => status & 0xff,
+                Err(_) => {
+                    writeln!(err_writer, "return: {}: numeric argument required", arg)?;
+                    return Ok(2);
+                }
+            },
+            None => shell.status(),
+        };
+        if !shell.request_return() {
+            writeln!(
+                err_writer,
+                "return: can only `return' from a function or sourced script"
+            )?;
+            return Ok(1);
+        }
+        Ok(status)
+    }
+}
@@ -0,0 +1,171 @@
+// Library
+use super::CommandInfo;
+use crate::{
+    helpers::vars,
+    parser,
+    shell::{Options, Shell},
+};
+
+// ---
+// SET
+// ---
+
+/// # The `set` command.
+/// This command will turn the shell's `set` options on and off, set the positional parameters,
+/// and list the variables.
+///
+/// - `errexit` (`-e`) exits when a command fails, unless it is tested by `&&`, `||` or `!`
+/// - `noclobber` (`-C`) doesn't let `>` overwrite a file that exists, though `>|` still does
+/// - `noexec` (`-n`) reads commands without running them, in scripts
+/// - `nounset` (`-u`) treats expanding a variable that isn't set as an error
+/// - `pipefail` lets a pipeline fail if any of its commands fails
+/// - `xtrace` (`-x`) prints each command after `PS4` before it runs
+///
+/// ## Example
+///
+/// ```sh
+/// $ set -eu -o pipefail
+/// $ set -- one two
+/// $ echo $2
+/// ```
+/// ```output
+/// two
+/// ```
+pub struct Set;
+
+// Implement the `CommandInfo` trait for the `Set` struct.
+impl super::CommandInfo for Set {
+    /// Get the name of the command.
+    fn name(&self) -> String {
+        String::from("set")
+    }
+
+    /// Get the description of the command.
+    fn description(&self) -> String {
+        String::from("Set shell options and positional parameters")
+    }
+
+    /// Get the usage of the command.
+    fn usage(&self) -> String {
+        String::from("set [-eunxC] [-o option-name] [--] [arg ...]")
+    }
+}
+
+// Implement the `ExecutableCommand` trait for the `Set` struct.
+impl super::ExecutableCommand for Set {
+    /// Execute the `set` command.
+    /// - `set -x` turns an option on by its letter, and `set +x` turns it off.
+    /// - `set -o name` and `set +o name` do the same by its name.
+    /// - `set -o` lists the options, and `set +o` lists them as commands that set them again.
+    /// - `set -- arg ...` sets the positional parameters, as does any argument after the
+    ///   options. `set --` alone clears them.
+    /// - `set` alone lists the variables.
+    /// ```sh
+    /// $ set -o errexit
+    /// $ set +o
+    /// ```
+    /// ```output
+    /// set -o errexit
+    /// set +o noclobber
+    /// set +o noexec
+    /// set +o nounset
+    /// set +o pipefail
+    /// set +o xtrace
+    /// ```
+    fn execute<T>(
+        &self,
+        args: Vec<String>,
+        shell: &mut Shell,
+        out_writer: &mut T,
+        err_writer: &mut T,
+    ) -> std::io::Result<i32>
+    where
+        T: std::io::Write,
+    {
+        if args.len() == 1 {
+            print_variables(out_writer)?;
+            return Ok(0);
+        }
+
+        // Skip the first argument (the command name)
+        let mut args = args.into_iter().skip(1).peekable();
+        let mut positional = None;
+        while let Some(arg) = args.next() {
+            if arg == "--" {
+                positional = Some(args.by_ref().collect());
+                break;
+            }
+            let on = arg.starts_with('-');
+            if arg.len() < 2 || !(on || arg.starts_with('+')) {
+                positional = Some(std::iter::once(arg).chain(args.by_ref()).collect());
+                break;
+            }
+
+            for letter in arg.chars().skip(1) {
+                let name = match letter {
+                    // The option name is the next argument, and without one, the options are listed
+                    'o' => match args.next_if(|name| !name.starts_with(['-', '+'])) {
+                        Some(name) => name,
+                        None => {
+                            print_options(shell, on, out_writer)?;
+                            continue;
+                        }
+                    },
+                    letter => match Options::name_for(letter) {
+                        Some(name) => name.to_owned(),
+                        None => {
+                            let sign = if on { '-' } else { '+' };
+                            writeln!(err_writer, "set: {}{}: invalid option", sign, letter)?;
+                            writeln!(err_writer, "set: usage: {}", self.usage())?;
+                            return Ok(2);
+                        }
+                    },
+                };
+                if let Err(e) = shell.set_shell_option(&name, on) {
+                    writeln!(err_writer, "set: {}", e)?;
+                    return Ok(2);
+                }
+            }
+        }
+
+        if let Some(values) = positional {
+            vars::set_positional(values);
+        }
+        Ok(0)
+    }
+}
+
+/// Lists the options, either with whether they are on, or with `+o` as `set` commands
+fn print_options<T: std::io::Write>(
+    shell: &Shell,
+    on: bool,
+    out_writer: &mut T,
+) -> std::io::Result<()> {
+    for (name, value) in shell.shell_options() {
+        match (on, value) {
+            (true, true) => writeln!(out_writer, "{:<15}\ton", name)?,
+            (true, false) => writeln!(out_writer, "{:<15}\toff", name)?,
+            (false, true) => writeln!(out_writer, "set -o {}", name)?,
+            (false, false) => writeln!(out_writer, "set +o {}", name)?,
+        }
+    }
+    Ok(())
+}
+
+/// Lists the variables and arrays by name, with their values quoted so that they could be
+/// assigned again
+fn print_variables<T: std::io::Write>(out_writer: &mut T) -> std::io::Result<()> {
+    let mut variables: Vec<(String, String)> = vars::variables()
+        .into_iter()
+        .map(|(name, value)| (name, parser::quote(&value)))
+        .collect();
+    for (name, values) in vars::arrays() {
+        let values: Vec<String> = values.iter().map(|value| parser::quote(value)).collect();
+        variables.push((name, format!("({})", values.join(" "))));
+    }
+    variables.sort();
+    for (name, value) in variables {
+        writeln!(out_writer, "{}={}", name, value)?;
+    }
+    Ok(())
+}
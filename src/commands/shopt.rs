@@ -0,0 +1,146 @@
+// Library
+use super::CommandInfo;
+use crate::shell::Shell;
+
+// -----
+// SHOPT
+// -----
+
+/// # The `shopt` command.
+/// This command will turn shell options on and off, and show their settings.
+///
+/// ## Example
+///
+/// ```sh
+/// $ shopt -s complete_fuzzy complete_ignore_case
+/// $ shopt complete_fuzzy
+/// ```
+/// ```output
+/// complete_fuzzy  on
+/// ```
+pub struct Shopt;
+
+// Implement the `CommandInfo` trait for the `Shopt` struct.
+impl super::CommandInfo for Shopt {
+    /// Get the name of the command.
+    fn name(&self) -> String {
+        String::from("shopt")
+    }
+
+    /// Get the description of the command.
+    fn description(&self) -> String {
+        String::from("Turn shell options on and off")
+    }
+
+    /// Get the usage of the command.
+    fn usage(&self) -> String {
+        String::from("shopt [-pqsu] [optname ...]")
+    }
+}
+
+// Implement the `ExecutableCommand` trait for the `Shopt` struct.
+impl super::ExecutableCommand for Shopt {
+    /// Execute the `shopt` command.
+    /// With `-s` or `-u` the options are turned on or off. Otherwise they are listed (all of them
+    /// if none are given), and the exit status tells whether they are all on.
+    /// `-p` lists them in a form that can be read back in, and `-q` doesn't list them at all.
+    /// ```sh
+    /// $ shopt -p complete_substring
+    /// ```
+    /// ```output
+    /// shopt -u complete_substring
+    /// ```
+    fn execute<T>(
+        &self,
+        args: Vec<String>,
+        shell: &mut Shell,
+        out_writer: &mut T,
+        err_writer: &mut T,
+    ) -> std::io::Result<i32>
+    where
+        T: std::io::Write,
+    {
+        let (mut set, mut print, mut quiet) = (None, false, false);
+        let mut names = Vec::new();
+
+        // Skip the first argument (the command name)
+        for arg in args.iter().skip(1) {
+            match arg.as_str() {
+                "-s" => set = Some(true),
+                "-u" => set = Some(false),
+                "-p" => print = true,
+                "-q" => quiet = true,
+                opt if opt.starts_with('-') => {
+                    writeln!(err_writer, "shopt: {}: invalid option", opt)?;
+                    writeln!(err_writer, "shopt: usage: {}", self.usage())?;
+                    return Ok(2);
+                }
+                name => names.push(name),
+            }
+        }
+
+        let mut status = 0;
+
+        // Turn the options on or off
+        if let Some(on) = set {
+            for name in names {
+                if let Err(e) = shell.set_option(name, on) {
+                    writeln!(err_writer, "shopt: {}", e)?;
+                    status = 1;
+                }
+            }
+            return Ok(status);
+        }
+
+        // List the options
+        let options: Vec<(String, Option<bool>)> = match names.is_empty() {
+            true => shell
+                .options()
+                .map(|(n, on)| (n.to_owned(), Some(on)))
+                .collect(),
+            false => names
+                .iter()
+                .map(|n| (n.to_string(), shell.option(n)))
+                .collect(),
+        };
+        for (name, on) in options {
+            match on {
+                Some(on) => {
+                    if !on {
+                        status = 1;
+                    }
+                    if quiet {
+                        continue;
+                    }
+                    match print {
+                        true => writeln!(out_writer, "shopt {} {}", flag(on), name)?,
+                        false => writeln!(out_writer, "{:<15}\t{}", name, state(on))?,
+                    }
+                }
+                None => {
+                    writeln!(err_writer, "shopt: {}: invalid shell option name", name)?;
+                    status = 1;
+                }
+            }
+        }
+        Ok(status)
+    }
+}
+
+/// The `shopt` flag that sets an option to the given state
+fn flag(on: bool) -> &'static str {
+    if on {
+        "-s"
+    } else {
+        "-u"
+    }
+}
+
+/// The state of an option, as it is listed
+fn state(on: bool) -> &'static str {
+    if on {
+        "on"
+    } else {
+        "off"
+    }
+}
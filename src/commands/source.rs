@@ -0,0 +1,93 @@
+// Library
+use super::CommandInfo;
+use crate::{helpers, shell::Shell};
+use std::path::PathBuf;
+
+// ------
+// SOURCE
+// ------
+
+/// # The `source` command.
+/// This command will run the commands in a file in the current shell, so that the variables,
+/// functions and directory changes they make stay. It is also called `.`.
+///
+/// A name without a slash is looked up in the `PATH`, and then in the current directory.
+/// The file doesn't need to be executable.
+///
+/// ## Example
+///
+/// ```sh
+/// $ echo 'greeting="hello $1"' > setup.sh
+/// $ source ./setup.sh world
+/// $ echo $greeting
+/// ```
+/// ```output
+/// hello world
+/// ```
+pub struct Source;
+
+// Implement the `CommandInfo` trait for the `Source` struct.
+impl super::CommandInfo for Source {
+    /// Get the name of the command.
+    fn name(&self) -> String {
+        String::from("source")
+    }
+
+    /// Get the description of the command.
+    fn description(&self) -> String {
+        String::from("Run the commands in a file in the current shell")
+    }
+
+    /// Get the usage of the command.
+    fn usage(&self) -> String {
+        String::from("source filename [arguments]")
+    }
+}
+
+// Implement the `ExecutableCommand` trait for the `Source` struct.
+impl super::ExecutableCommand for Source {
+    /// Execute the `source` command.
+    /// The arguments after the filename are the positional parameters while the file runs, and
+    /// without any, the file sees the ones of the shell. A `return` in the file ends it early.
+    ///
+    /// The exit status is the one of the last command in the file, or 1 if it can't be read.
+    /// ```sh
+    /// $ printf 'echo in file\nreturn 3\necho not here\n' > early.sh
+    /// $ . ./early.sh
+    /// ```
+    /// ```output
+    /// in file
+    /// ```
+    fn execute<T>(
+        &self,
+        args: Vec<String>,
+        shell: &mut Shell,
+        _out_writer: &mut T,
+        err_writer: &mut T,
+    ) -> std::io::Result<i32>
+    where
+        T: std::io::Write,
+    {
+        let mut args = args.into_iter();
+        let name = args.next().unwrap_or_default();
+        let Some(filename) = args.next() else {
+            writeln!(err_writer, "{}: filename argument required", name)?;
+            writeln!(err_writer, "{}: usage: {}", name, self.usage())?;
+            return Ok(2);
+        };
+
+        let path = match filename.contains('/') {
+            true => PathBuf::from(&filename),
+            false => {
+                helpers::path::find_file(&filename).unwrap_or_else(|| PathBuf::from(&filename))
+            }
+        };
+        let args: Vec<String> = args.collect();
+        let args = (!args.is_empty()).then_some(args);
+        if let Err(e) = shell.source(&path, args) {
+            writeln!(err_writer, "{}: {}: {}", name, filename, e)?;
+            return Ok(1);
+        }
+        Ok(shell.status())
+    }
+}
@@ -0,0 +1,133 @@
+// Library
+use super::CommandInfo;
+use crate::shell::{Condition, Shell};
+
+// ----
+// TRAP
+// ----
+
+/// # The `trap` command.
+/// This command will set commands to run when the shell gets a signal, or on one of the
+/// shell's own events:
+/// - `EXIT` (or `0`) when the shell exits
+/// - `ERR` when a command fails
+/// - `DEBUG` before each command, with the command in `BASH_COMMAND`
+/// - `RETURN` when a function returns
+///
+/// ## Example
+///
+/// ```sh
+/// $ tmp=/tmp/work.$$
+/// $ trap 'rm -rf "$tmp"' EXIT
+/// $ trap 'echo interrupted' INT TERM
+/// ```
+pub struct Trap;
+
+// Implement the `CommandInfo` trait for the `Trap` struct.
+impl super::CommandInfo for Trap {
+    /// Get the name of the command.
+    fn name(&self) -> String {
+        String::from("trap")
+    }
+
+    /// Get the description of the command.
+    fn description(&self) -> String {
+        String::from("Run commands on signals and shell events")
+    }
+
+    /// Get the usage of the command.
+    fn usage(&self) -> String {
+        String::from("trap [-lp] [[action] sigspec ...]")
+    }
+}
+
+// Implement the `ExecutableCommand` trait for the `Trap` struct.
+impl super::ExecutableCommand for Trap {
+    /// Execute the `trap` command.
+    /// - `trap action sigspec ...` sets the action for each signal or event.
+    /// - `trap - sigspec ...` (or `trap sigspec`) resets them to what the shell does by default.
+    /// - `trap '' sigspec ...` ignores the signals, in the shell and in the programs it runs.
+    /// - `trap` and `trap -p` list the traps as commands that set them again, or with signal
+    ///   specs, only the ones for those signals.
+    /// - `trap -l` lists the signals.
+    ///
+    /// The exit status is 1 if any of the signal specs is invalid.
+    /// ```sh
+    /// $ trap 'echo bye' EXIT
+    /// $ trap -p
+    /// ```
+    /// ```output
+    /// trap -- 'echo bye' EXIT
+    /// ```
+    fn execute<T>(
+        &self,
+        args: Vec<String>,
+        shell: &mut Shell,
+        out_writer: &mut T,
+        err_writer: &mut T,
+    ) -> std::io::Result<i32>
+    where
+        T: std::io::Write,
+    {
+        let mut args: Vec<&str> = args.iter().skip(1).map(String::as_str).collect();
+        let mut print = args.is_empty();
+        match args.first().copied() {
+            Some("-l") => {
+                super::kill::print_signals(out_writer)?;
+                return Ok(0);
+            }
+            Some("-p") => {
+                args.remove(0);
+                print = true;
+            }
+            Some("--") => {
+                args.remove(0);
+            }
+            Some(opt) if opt.starts_with('-') && opt.len() > 1 => {
+                writeln!(err_writer, "trap: {}: invalid option", opt)?;
+                writeln!(err_writer, "trap: usage: {}", self.usage())?;
+                return Ok(2);
+            }
+            _ => {}
+        }
+
+        // The signal specs to print, or the action and the signal specs to set it on
+        let (action, specs) = match args.split_first() {
+            _ if print => (None, args.as_slice()),
+            // A lone signal spec is reset, like with `-`
+            Some((spec, [])) if spec.parse::<Condition>().is_ok() => (None, args.as_slice()),
+            Some((&"-", specs)) => (None, specs),
+            Some((action, specs)) if !specs.is_empty() => (Some(*action), specs),
+            _ => {
+                writeln!(err_writer, "trap: usage: {}", self.usage())?;
+                return Ok(2);
+            }
+        };
+
+        let mut status = 0;
+        let mut conditions = Vec::new();
+        for spec in specs {
+            match spec.parse::<Condition>() {
+                Ok(condition) => conditions.push(condition),
+                Err(e) => {
+                    writeln!(err_writer, "trap: {}", e)?;
+                    status = 1;
+                }
+            }
+        }
+
+        if print {
+            for (condition, action) in shell.traps() {
+                if specs.is_empty() || conditions.contains(&condition) {
+                    let action = action.replace('\'', "'\\''");
+                    writeln!(out_writer, "trap -- '{}' {}", action, condition)?;
+                }
+            }
+            return Ok(status);
+        }
+        for condition in conditions {
+            shell.set_trap(condition, action.map(String::from));
+        }
+        Ok(status)
+    }
+}
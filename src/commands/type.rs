@@ -1,3 +1,6 @@
+// Library
+use crate::shell::Shell;
+
 // ----
 // TYPE
 // ----
@@ -38,9 +41,10 @@ impl super::ExecutableCommand for Type {
     fn execute<T>(
         &self,
         args: Vec<String>,
+        shell: &mut Shell,
         out_writer: &mut T,
         _err_writer: &mut T,
-    ) -> std::io::Result<()>
+    ) -> std::io::Result<i32>
     where
         T: std::io::Write,
     {
@@ -49,6 +53,10 @@ impl super::ExecutableCommand for Type {
 
         // Get the first argument
         if let Some(arg) = args.first() {
+            if shell.has_function(arg) {
+                writeln!(out_writer, "{} is a function", arg)?;
+                return Ok(0);
+            }
             match arg.parse::<super::Command>() {
                 Ok(super::Command::Builtin(_)) => {
                     writeln!(out_writer, "{} is a shell builtin", arg)?;
@@ -58,9 +66,11 @@ impl super::ExecutableCommand for Type {
                 }
                 Ok(super::Command::Unknown) => {
                     writeln!(out_writer, "{}: not found", arg)?;
+                    return Ok(1);
                 }
                 Err(_) => {
                     writeln!(out_writer, "{} is not a valid command", arg)?;
+                    return Ok(1);
                 }
             }
         } else {
@@ -70,6 +80,6 @@ impl super::ExecutableCommand for Type {
             ))?;
         }
 
-        Ok(())
+        Ok(0)
     }
 }
@@ -1,3 +1,6 @@
+// Library
+use crate::shell::Shell;
+
 // -------
 // UNKNOWN
 // -------
@@ -14,15 +17,16 @@ impl super::ExecutableCommand for Unknown {
     fn execute<T>(
         &self,
         args: Vec<String>,
+        _shell: &mut Shell,
         _out_writer: &mut T,
         err_writer: &mut T,
-    ) -> std::io::Result<()>
+    ) -> std::io::Result<i32>
     where
         T: std::io::Write,
     {
-        if let Some(command) = args.get(0) {
+        if let Some(command) = args.first() {
             writeln!(err_writer, "{}: command not found", command)?;
         }
-        Ok(())
+        Ok(127)
     }
 }
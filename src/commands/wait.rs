@@ -0,0 +1,139 @@
+// Library
+use super::CommandInfo;
+use crate::{
+    helpers::sys,
+    shell::{Shell, State},
+};
+
+// ----
+// WAIT
+// ----
+
+/// # The `wait` command.
+/// This command will wait for jobs in the background to finish, and return the exit status
+/// of the last one. Without arguments, it waits for all of them.
+///
+/// ## Example
+///
+/// ```sh
+/// $ make > build.log &
+/// $ wait %make
+/// ```
+pub struct Wait;
+
+// Implement the `CommandInfo` trait for the `Wait` struct.
+impl super::CommandInfo for Wait {
+    /// Get the name of the command.
+    fn name(&self) -> String {
+        String::from("wait")
+    }
+
+    /// Get the description of the command.
+    fn description(&self) -> String {
+        String::from("Wait for jobs to finish")
+    }
+
+    /// Get the usage of the command.
+    fn usage(&self) -> String {
+        String::from("wait [-n] [id ...]")
+    }
+}
+
+// Implement the `ExecutableCommand` trait for the `Wait` struct.
+impl super::ExecutableCommand for Wait {
+    /// Execute the `wait` command.
+    /// - Each ID is a job spec like `%1`, or the process ID of a process in a job.
+    /// - `-n` waits for the first of the jobs to finish, rather than all of them.
+    ///
+    /// Without IDs, the exit status is 0 (or 127 with `-n`, if there are no jobs).
+    /// With IDs, it's the exit status of the last job or process, or 127 if it doesn't exist.
+    /// An interrupt, or a trapped signal, ends the wait with an exit status of 128 plus the
+    /// signal number.
+    /// ```sh
+    /// $ sleep 1 & false &
+    /// $ wait -n %false; echo $?
+    /// ```
+    /// ```output
+    /// [1] 4242
+    /// [2] 4243
+    /// 1
+    /// ```
+    fn execute<T>(
+        &self,
+        args: Vec<String>,
+        shell: &mut Shell,
+        _out_writer: &mut T,
+        err_writer: &mut T,
+    ) -> std::io::Result<i32>
+    where
+        T: std::io::Write,
+    {
+        let mut any = false;
+        let mut ids = Vec::new();
+        for arg in args.iter().skip(1) {
+            match arg.as_str() {
+                "-n" => any = true,
+                opt if opt.starts_with('-') => {
+                    writeln!(err_writer, "wait: {}: invalid option", opt)?;
+                    writeln!(err_writer, "wait: usage: {}", self.usage())?;
+                    return Ok(2);
+                }
+                id => ids.push(id),
+            }
+        }
+
+        // Waiting for all the jobs always succeeds
+        let wait_for_all = ids.is_empty() && !any;
+        let jobs = shell.jobs();
+        let mut status = 0;
+        let mut targets = Vec::new();
+        if ids.is_empty() {
+            // Stopped jobs would never finish on their own
+            targets = jobs
+                .iter()
+                .filter(|job| job.state() == State::Running)
+                .map(|job| (job.id, None))
+                .collect();
+            if any && targets.is_empty() {
+                return Ok(127);
+            }
+        }
+        for id in ids {
+            if id.starts_with('%') {
+                match jobs.find(id) {
+                    Ok(job) => targets.push((job, None)),
+                    Err(e) => {
+                        writeln!(err_writer, "wait: {}", e)?;
+                        status = 127;
+                    }
+                }
+            } else if let Ok(pid) = id.parse::<i32>() {
+                match jobs.find_pid(pid) {
+                    Some(job) => targets.push((job, Some(pid))),
+                    None => {
+                        writeln!(err_writer, "wait: pid {} is not a child of this shell", pid)?;
+                        status = 127;
+                    }
+                }
+            } else {
+                writeln!(err_writer, "wait: `{}': not a pid or valid job spec", id)?;
+                status = 2;
+            }
+        }
+        if targets.is_empty() {
+            return Ok(status);
+        }
+
+        match shell.wait_jobs(targets, any) {
+            Ok(_) if wait_for_all => Ok(0),
+            Ok(waited) => Ok(waited),
+            Err(signal) => {
+                // The terminal has shown `^C`, but not ended the line
+                if signal == sys::SIGINT {
+                    writeln!(err_writer)?;
+                }
+                Ok(128 + signal)
+            }
+        }
+    }
+}
@@ -1,95 +1,597 @@
-// ----------
-// ANSI CODES
-// ----------
+// Library
+use std::{
+    io::IsTerminal,
+    sync::atomic::{AtomicU8, Ordering},
+};
 
-/// ANSI escape codes for text formatting
-enum Color {
+use super::vars;
+
+// ------
+// COLORS
+// ------
+
+/// A terminal color: one of the 16 standard colors, an index into the 256-color palette,
+/// or a 24-bit RGB color
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    Black,
     Red,
-    // Green,
-    // Yellow,
-    // Blue,
-    // Magenta,
-    // Cyan,
-    // White,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    BrightBlack,
+    BrightRed,
+    BrightGreen,
+    BrightYellow,
+    BrightBlue,
+    BrightMagenta,
+    BrightCyan,
+    BrightWhite,
+    Ansi256(u8),
+    Rgb(u8, u8, u8),
 }
 
+/// The RGB values xterm uses for the 16 standard colors, to find the closest one to any other color
+const STANDARD: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+/// The 16 standard colors, in palette order
+const COLORS: [Color; 16] = [
+    Color::Black,
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+    Color::White,
+    Color::BrightBlack,
+    Color::BrightRed,
+    Color::BrightGreen,
+    Color::BrightYellow,
+    Color::BrightBlue,
+    Color::BrightMagenta,
+    Color::BrightCyan,
+    Color::BrightWhite,
+];
+
+/// The names of the first 8 standard colors. The other 8 are the same with `bright` in front.
+const NAMES: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+/// The levels of the red, green and blue channels in the 6x6x6 color cube of the 256-color palette
+const CUBE: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
 impl Color {
-    fn code(&self) -> u8 {
-        match self {
-            Color::Red => 31,
-            // Color::Green => 32,
-            // Color::Yellow => 33,
-            // Color::Blue => 34,
-            // Color::Magenta => 35,
-            // Color::Cyan => 36,
-            // Color::White => 37,
+    /// The SGR parameters that select this color as the foreground (or background), for a terminal
+    /// with the given level of color support. Colors the terminal can't show are approximated.
+    fn sgr(&self, level: Level, background: bool) -> String {
+        let (offset, extended) = match background {
+            false => (30, 38),
+            true => (40, 48),
+        };
+        match (*self, level) {
+            (Color::Rgb(r, g, b), Level::TrueColor) => format!("{};2;{};{};{}", extended, r, g, b),
+            (Color::Rgb(r, g, b), Level::Ansi256) => {
+                format!("{};5;{}", extended, rgb_to_256(r, g, b))
+            }
+            (Color::Ansi256(n), Level::Ansi256 | Level::TrueColor) if n >= 16 => {
+                format!("{};5;{}", extended, n)
+            }
+            (color, _) => {
+                let n = color.standard();
+                match n < 8 {
+                    true => (offset + n).to_string(),
+                    false => (offset + 60 + n - 8).to_string(),
+                }
+            }
+        }
+    }
+
+    /// The closest of the 16 standard colors, as an index into the palette
+    fn standard(&self) -> u8 {
+        match *self {
+            Color::Black => 0,
+            Color::Red => 1,
+            Color::Green => 2,
+            Color::Yellow => 3,
+            Color::Blue => 4,
+            Color::Magenta => 5,
+            Color::Cyan => 6,
+            Color::White => 7,
+            Color::BrightBlack => 8,
+            Color::BrightRed => 9,
+            Color::BrightGreen => 10,
+            Color::BrightYellow => 11,
+            Color::BrightBlue => 12,
+            Color::BrightMagenta => 13,
+            Color::BrightCyan => 14,
+            Color::BrightWhite => 15,
+            Color::Ansi256(n) if n < 16 => n,
+            Color::Ansi256(n) => {
+                let (r, g, b) = ansi256_to_rgb(n);
+                rgb_to_standard(r, g, b)
+            }
+            Color::Rgb(r, g, b) => rgb_to_standard(r, g, b),
+        }
+    }
+}
+
+impl std::str::FromStr for Color {
+    type Err = String;
+
+    /// Parse a color by name (`red`, `brightblue`), by its number in the 256-color palette
+    /// (`208`), or as a 24-bit `#rrggbb` value
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.to_lowercase();
+        let (bright, base) = match name.strip_prefix("bright") {
+            Some(base) => (8, base),
+            None => (0, name.as_str()),
+        };
+        if let Some(i) = NAMES.iter().position(|&n| n == base) {
+            return Ok(COLORS[bright + i]);
+        }
+        if let Ok(n) = s.parse::<u8>() {
+            return Ok(Color::Ansi256(n));
+        }
+        let channel = |i: usize| {
+            let hex = s.strip_prefix('#').filter(|hex| hex.len() == 6)?;
+            u8::from_str_radix(hex.get(i..i + 2)?, 16).ok()
+        };
+        match (channel(0), channel(2), channel(4)) {
+            (Some(r), Some(g), Some(b)) => Ok(Color::Rgb(r, g, b)),
+            _ => Err(format!("{}: invalid color", s)),
+        }
+    }
+}
+
+/// The closest color in the 256-color palette, from the color cube or the grayscale ramp
+fn rgb_to_256(r: u8, g: u8, b: u8) -> u8 {
+    if r == g && g == b {
+        return match r {
+            0..8 => 16,
+            249.. => 231,
+            _ => 232 + ((r - 8 + 5) / 10).min(23),
+        };
+    }
+    let level = |v: u8| match v {
+        0..48 => 0,
+        48..115 => 1,
+        _ => (v - 35) / 40,
+    };
+    16 + 36 * level(r) + 6 * level(g) + level(b)
+}
+
+/// The RGB value of a color in the 256-color palette
+fn ansi256_to_rgb(n: u8) -> (u8, u8, u8) {
+    match n {
+        0..16 => STANDARD[n as usize],
+        16..232 => {
+            let i = n - 16;
+            (
+                CUBE[(i / 36) as usize],
+                CUBE[(i / 6 % 6) as usize],
+                CUBE[(i % 6) as usize],
+            )
+        }
+        _ => {
+            let v = 8 + 10 * (n - 232);
+            (v, v, v)
+        }
+    }
+}
+
+/// The closest of the 16 standard colors
+fn rgb_to_standard(r: u8, g: u8, b: u8) -> u8 {
+    let distance = |&(sr, sg, sb): &(u8, u8, u8)| {
+        let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+        d(r, sr) + d(g, sg) + d(b, sb)
+    };
+    (0..16)
+        .min_by_key(|&i| distance(&STANDARD[i]))
+        .unwrap_or_default() as u8
+}
+
+// ------
+// STYLES
+// ------
+
+/// How text looks: its colors and attributes. Styles are built up from [`Style::new`]:
+/// ```ignore
+/// Style::new().fg(Color::Green).bold().paint("ok")
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Style {
+    foreground: Option<Color>,
+    background: Option<Color>,
+    bold: bool,
+    dim: bool,
+    italic: bool,
+    underline: bool,
+    reverse: bool,
+}
+
+impl Style {
+    /// A style that leaves text as it is
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn fg(mut self, color: Color) -> Self {
+        self.foreground = Some(color);
+        self
+    }
+
+    pub fn bg(mut self, color: Color) -> Self {
+        self.background = Some(color);
+        self
+    }
+
+    pub fn bold(mut self) -> Self {
+        self.bold = true;
+        self
+    }
+
+    pub fn dim(mut self) -> Self {
+        self.dim = true;
+        self
+    }
+
+    pub fn italic(mut self) -> Self {
+        self.italic = true;
+        self
+    }
+
+    pub fn underline(mut self) -> Self {
+        self.underline = true;
+        self
+    }
+
+    pub fn reverse(mut self) -> Self {
+        self.reverse = true;
+        self
+    }
+
+    /// Style text that is written to stdout, as far as the terminal supports it
+    pub fn paint(&self, text: &str) -> String {
+        self.paint_at(level(Stream::Stdout), text)
+    }
+
+    /// Style text that is written to stderr, as far as the terminal supports it
+    pub fn paint_stderr(&self, text: &str) -> String {
+        self.paint_at(level(Stream::Stderr), text)
+    }
+
+    /// Style text for a terminal with the given level of color support.
+    /// Without any color support, the text is left as it is.
+    pub fn paint_at(&self, level: Level, text: &str) -> String {
+        if level == Level::None || *self == Style::default() {
+            return text.to_owned();
+        }
+
+        let attributes = [
+            (self.bold, "1"),
+            (self.dim, "2"),
+            (self.italic, "3"),
+            (self.underline, "4"),
+            (self.reverse, "7"),
+        ];
+        let mut parameters: Vec<String> = attributes
+            .iter()
+            .filter(|(on, _)| *on)
+            .map(|(_, code)| code.to_string())
+            .collect();
+        if let Some(color) = self.foreground {
+            parameters.push(color.sgr(level, false));
+        }
+        if let Some(color) = self.background {
+            parameters.push(color.sgr(level, true));
+        }
+        format!("\u{001b}[{}m{}\u{001b}[0m", parameters.join(";"), text)
+    }
+}
+
+impl std::str::FromStr for Style {
+    type Err = String;
+
+    /// Parse a style the way git writes colors: any of the attributes `bold`, `dim`, `italic`,
+    /// `ul` (or `underline`) and `reverse`, then the foreground color and the background color.
+    /// `normal` leaves a color as it is, as in `normal blue` for only a blue background.
+    /// ```ignore
+    /// "bold green".parse::<Style>()
+    /// "ul #ff8700 236".parse::<Style>()
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut style = Style::new();
+        let mut colors = 0;
+        for word in s.split_whitespace() {
+            style = match word {
+                "bold" => style.bold(),
+                "dim" => style.dim(),
+                "italic" => style.italic(),
+                "ul" | "underline" => style.underline(),
+                "reverse" => style.reverse(),
+                "normal" if colors < 2 => style,
+                _ if colors == 0 => style.fg(word.parse()?),
+                _ if colors == 1 => style.bg(word.parse()?),
+                _ => return Err(format!("{}: too many colors", s)),
+            };
+            if !matches!(
+                word,
+                "bold" | "dim" | "italic" | "ul" | "underline" | "reverse"
+            ) {
+                colors += 1;
+            }
         }
+        Ok(style)
+    }
+}
+
+// ----------------
+// COLOR DETECTION
+// ----------------
+
+/// How many colors a terminal can show
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    /// No colors or attributes at all
+    None,
+    /// The 16 standard colors
+    Basic,
+    /// The 256-color palette
+    Ansi256,
+    /// 24-bit RGB colors
+    TrueColor,
+}
+
+/// When to use colors, as set with `--color`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Only when writing to a terminal, and `NO_COLOR` isn't set
+    Auto,
+    Always,
+    Never,
+}
+
+impl std::str::FromStr for ColorChoice {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            _ => Err(format!(
+                "{}: invalid color setting (expected auto, always or never)",
+                s
+            )),
+        }
+    }
+}
+
+/// The output streams that can be colored
+#[derive(Debug, Clone, Copy)]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+/// The color choice, stored as the index of the [`ColorChoice`] variant
+static CHOICE: AtomicU8 = AtomicU8::new(0);
+
+/// Set when to use colors
+pub fn set_choice(choice: ColorChoice) {
+    let index = match choice {
+        ColorChoice::Auto => 0,
+        ColorChoice::Always => 1,
+        ColorChoice::Never => 2,
+    };
+    CHOICE.store(index, Ordering::Relaxed);
+}
+
+/// The colors to use for the given stream, from the color choice and the environment
+pub fn level(stream: Stream) -> Level {
+    let is_terminal = match stream {
+        Stream::Stdout => std::io::stdout().is_terminal(),
+        Stream::Stderr => std::io::stderr().is_terminal(),
+    };
+    let var = |name| vars::get(name).unwrap_or_default();
+    match CHOICE.load(Ordering::Relaxed) {
+        1 => detect(&var("COLORTERM"), &var("TERM")).max(Level::Basic),
+        2 => Level::None,
+        // `NO_COLOR` turns colors off when it is set to anything
+        _ if !var("NO_COLOR").is_empty() || !is_terminal => Level::None,
+        _ => detect(&var("COLORTERM"), &var("TERM")),
+    }
+}
+
+/// Work out how many colors the terminal supports from `COLORTERM` and `TERM`
+fn detect(colorterm: &str, term: &str) -> Level {
+    if matches!(colorterm, "truecolor" | "24bit") || term.ends_with("-direct") {
+        Level::TrueColor
+    } else if term.contains("256color") {
+        Level::Ansi256
+    } else if term == "dumb" {
+        Level::None
+    } else {
+        Level::Basic
     }
+}
+
+// -------------
+// DISPLAY WIDTH
+// -------------
+
+/// Marks the start of text that takes up no room on the screen, like `\[` in a prompt
+pub const START_IGNORE: char = '\u{1}';
+/// Marks the end of text that takes up no room on the screen, like `\]` in a prompt
+pub const END_IGNORE: char = '\u{2}';
 
-    fn wrap(&self, text: &str) -> String {
-        format!("\u{001b}[{}m{}\u{001b}[0m", self.code(), text)
+/// The number of columns the text takes up on the screen. Escape sequences, and anything between
+/// [`START_IGNORE`] and [`END_IGNORE`], take up none.
+pub fn width(text: &str) -> usize {
+    let mut width = 0;
+    let mut ignoring = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            START_IGNORE => ignoring = true,
+            END_IGNORE => ignoring = false,
+            _ if ignoring => {}
+            '\u{1b}' => match chars.next() {
+                // Control sequences end with a letter (or another final byte)
+                Some('[') => {
+                    for c in chars.by_ref() {
+                        if ('\u{40}'..='\u{7e}').contains(&c) {
+                            break;
+                        }
+                    }
+                }
+                // Operating system commands end with BEL or `ESC \`
+                Some(']') => {
+                    while let Some(c) = chars.next() {
+                        if c == '\u{7}' || (c == '\u{1b}' && chars.next_if_eq(&'\\').is_some()) {
+                            break;
+                        }
+                    }
+                }
+                _ => {}
+            },
+            c if c.is_control() => {}
+            _ => width += 1,
+        }
     }
+    width
+}
+
+/// Remove the [`START_IGNORE`] and [`END_IGNORE`] markers, before the text is written out
+pub fn strip_markers(text: &str) -> String {
+    text.replace([START_IGNORE, END_IGNORE], "")
 }
 
 // ---------------
 // COLORABLE TRAIT
 // ---------------
 
-/// A trait for coloring text with ANSI escape codes
+/// A trait for styling text with ANSI escape codes
 pub trait Colorable {
-    fn red(&self) -> String;
-    // fn green(&self) -> String;
-    // fn yellow(&self) -> String;
-    // fn blue(&self) -> String;
-    // fn magenta(&self) -> String;
-    // fn cyan(&self) -> String;
-    // fn white(&self) -> String;
-}
+    /// Style the text, as far as the terminal it is written to supports it
+    fn styled(&self, style: Style) -> String;
 
-impl Colorable for String {
     fn red(&self) -> String {
-        Color::Red.wrap(self)
-    }
-    // fn green(&self) -> String {
-    //     Color::Green.wrap(self)
-    // }
-    // fn yellow(&self) -> String {
-    //     Color::Yellow.wrap(self)
-    // }
-    // fn blue(&self) -> String {
-    //     Color::Blue.wrap(self)
-    // }
-    // fn magenta(&self) -> String {
-    //     Color::Magenta.wrap(self)
-    // }
-    // fn cyan(&self) -> String {
-    //     Color::Cyan.wrap(self)
-    // }
-    // fn white(&self) -> String {
-    //     Color::White.wrap(self)
-    // }
+        self.styled(Style::new().fg(Color::Red))
+    }
 }
 
+impl Colorable for str {
+    fn styled(&self, style: Style) -> String {
+        style.paint(self)
+    }
+}
+
+/// Errors are reported on stderr, so they are styled for it
 impl Colorable for std::io::Error {
-    fn red(&self) -> String {
-        Color::Red.wrap(&self.to_string())
-    }
-    // fn green(&self) -> String {
-    //     Color::Green.wrap(&self.to_string())
-    // }
-    // fn yellow(&self) -> String {
-    //     Color::Yellow.wrap(&self.to_string())
-    // }
-    // fn blue(&self) -> String {
-    //     Color::Blue.wrap(&self.to_string())
-    // }
-    // fn magenta(&self) -> String {
-    //     Color::Magenta.wrap(&self.to_string())
-    // }
-    // fn cyan(&self) -> String {
-    //     Color::Cyan.wrap(&self.to_string())
-    // }
-    // fn white(&self) -> String {
-    //     Color::White.wrap(&self.to_string())
-    // }
+    fn styled(&self, style: Style) -> String {
+        style.paint_stderr(&self.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_styles_compose() {
+        let style = Style::new().fg(Color::Red).bold().underline();
+        assert_eq!(
+            style.paint_at(Level::Basic, "x"),
+            "\u{1b}[1;4;31mx\u{1b}[0m"
+        );
+        assert_eq!(style.paint_at(Level::None, "x"), "x");
+        assert_eq!(Style::new().paint_at(Level::TrueColor, "x"), "x");
+        let bright = Style::new().fg(Color::BrightBlue).bg(Color::Black);
+        assert_eq!(
+            bright.paint_at(Level::Basic, "x"),
+            "\u{1b}[94;40mx\u{1b}[0m"
+        );
+    }
+
+    #[test]
+    fn test_colors_are_approximated() {
+        let orange = Style::new().fg(Color::Rgb(255, 135, 0));
+        assert_eq!(
+            orange.paint_at(Level::TrueColor, "x"),
+            "\u{1b}[38;2;255;135;0mx\u{1b}[0m"
+        );
+        assert_eq!(
+            orange.paint_at(Level::Ansi256, "x"),
+            "\u{1b}[38;5;208mx\u{1b}[0m"
+        );
+        assert_eq!(orange.paint_at(Level::Basic, "x"), "\u{1b}[33mx\u{1b}[0m");
+        assert_eq!(rgb_to_256(128, 128, 128), 244);
+        assert_eq!(Color::Ansi256(196).standard(), 9);
+    }
+
+    #[test]
+    fn test_parse_styles() {
+        assert_eq!("brightblue".parse(), Ok(Color::BrightBlue));
+        assert_eq!("208".parse(), Ok(Color::Ansi256(208)));
+        assert_eq!("#FF8700".parse(), Ok(Color::Rgb(255, 135, 0)));
+        assert!("#ff87".parse::<Color>().is_err());
+        assert!("purple".parse::<Color>().is_err());
+
+        assert_eq!(
+            "bold green".parse(),
+            Ok(Style::new().bold().fg(Color::Green))
+        );
+        assert_eq!(
+            "ul italic dim reverse white 236".parse(),
+            Ok(Style::new()
+                .underline()
+                .italic()
+                .dim()
+                .reverse()
+                .fg(Color::White)
+                .bg(Color::Ansi256(236)))
+        );
+        assert_eq!("normal blue".parse(), Ok(Style::new().bg(Color::Blue)));
+        assert_eq!("".parse(), Ok(Style::new()));
+        assert!("red blue green".parse::<Style>().is_err());
+    }
+
+    #[test]
+    fn test_width() {
+        let prompt = "\u{1b}[32muser\u{1b}[0m \u{1}\u{1b}]0;title\u{7}\u{2}$ ";
+        assert_eq!(width(prompt), 7);
+        assert_eq!(width("\u{1}hidden\u{2}ab"), 2);
+        assert_eq!(strip_markers("\u{1}x\u{2}y"), "xy");
+    }
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(detect("truecolor", "xterm"), Level::TrueColor);
+        assert_eq!(detect("", "xterm-256color"), Level::Ansi256);
+        assert_eq!(detect("", "xterm"), Level::Basic);
+        assert_eq!(detect("", "dumb"), Level::None);
+    }
 }
@@ -0,0 +1,57 @@
+// Library
+use super::vars;
+use std::{
+    env,
+    path::{Component, Path, PathBuf},
+};
+
+// -----------------
+// WORKING DIRECTORY
+// -----------------
+
+/// The current directory the way it was reached, with the symbolic links on the way kept, as
+/// `PWD` has it. If `PWD` doesn't lead to the current directory, this is the physical path.
+pub fn logical() -> Option<PathBuf> {
+    let physical = env::current_dir().ok()?;
+    let logical = vars::get("PWD").map(PathBuf::from);
+    match logical {
+        Some(logical) if logical.is_absolute() && same_file(&logical, &physical) => Some(logical),
+        _ => Some(physical),
+    }
+}
+
+/// Resolves the `.` and `..` parts of a path without looking at the file system, so that
+/// `..` goes back out of a symbolic link rather than to the directory the link points into
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// Whether the two paths lead to the same file
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize(Path::new("/a/./b/../c")), PathBuf::from("/a/c"));
+        assert_eq!(normalize(Path::new("/a/link/..")), PathBuf::from("/a"));
+        assert_eq!(normalize(Path::new("/../..")), PathBuf::from("/"));
+    }
+}
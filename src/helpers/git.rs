@@ -0,0 +1,369 @@
+// Library
+use std::{
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+// ----------
+// REPOSITORY
+// ----------
+
+/// The parts of a git repository that the prompt needs, found without running `git`
+#[derive(Debug, PartialEq)]
+pub struct Repository {
+    /// The directory with `HEAD`, like `.git`, or `.git/worktrees/<name>` for a linked worktree
+    git_dir: PathBuf,
+    /// The directory with the refs, shared by all the worktrees of the repository
+    common_dir: PathBuf,
+    /// The top-level directory of the checkout
+    work_tree: PathBuf,
+}
+
+/// What `HEAD` points at
+#[derive(Debug, PartialEq)]
+pub enum Head {
+    /// A branch, which may not have any commits yet
+    Branch(String),
+    /// A commit, described by a tag that points at it or by its abbreviated hash
+    Detached(String),
+}
+
+impl Repository {
+    /// Find the repository the directory is in, by walking up to the first `.git`.
+    /// A `.git` file (in a linked worktree or a submodule) points to the real git directory.
+    pub fn discover(start: &Path) -> Option<Self> {
+        for dir in start.ancestors() {
+            let dot_git = dir.join(".git");
+            let git_dir = if dot_git.is_dir() {
+                dot_git
+            } else if dot_git.is_file() {
+                let contents = fs::read_to_string(&dot_git).ok()?;
+                let target = contents.strip_prefix("gitdir:")?.trim();
+                dir.join(target)
+            } else {
+                continue;
+            };
+
+            // Linked worktrees keep their refs in the main repository
+            let common_dir = match fs::read_to_string(git_dir.join("commondir")) {
+                Ok(common) => git_dir.join(common.trim()),
+                Err(_) => git_dir.clone(),
+            };
+            return Some(Repository {
+                git_dir,
+                common_dir,
+                work_tree: dir.to_owned(),
+            });
+        }
+        None
+    }
+
+    /// What `HEAD` points at
+    pub fn head(&self) -> Option<Head> {
+        let head = fs::read_to_string(self.git_dir.join("HEAD")).ok()?;
+        let head = head.trim();
+        if let Some(reference) = head.strip_prefix("ref:") {
+            let reference = reference.trim();
+            let name = reference.strip_prefix("refs/heads/").unwrap_or(reference);
+            return Some(Head::Branch(name.to_owned()));
+        }
+
+        let tag = self.refs().into_iter().find_map(|(name, hash)| {
+            let tag = name.strip_prefix("refs/tags/")?;
+            (hash == head).then(|| tag.to_owned())
+        });
+        Some(Head::Detached(tag.unwrap_or_else(|| {
+            format!("{}...", &head[..head.len().min(7)])
+        })))
+    }
+
+    /// All the refs, as (name, hash), from the loose ref files and `packed-refs`.
+    /// Loose refs come first, since they take precedence over packed ones.
+    fn refs(&self) -> Vec<(String, String)> {
+        let mut refs = Vec::new();
+        loose_refs(&self.common_dir, &self.common_dir.join("refs"), &mut refs);
+
+        if let Ok(packed) = fs::read_to_string(self.common_dir.join("packed-refs")) {
+            let mut lines = packed.lines().peekable();
+            while let Some(line) = lines.next() {
+                let Some((hash, name)) = line.split_once(' ') else {
+                    continue;
+                };
+                if line.starts_with('#') || line.starts_with('^') {
+                    continue;
+                }
+                // An annotated tag is followed by the commit it points at
+                let hash = match lines.next_if(|next| next.starts_with('^')) {
+                    Some(peeled) => &peeled[1..],
+                    None => hash,
+                };
+                refs.push((name.to_owned(), hash.to_owned()));
+            }
+        }
+        refs
+    }
+
+    /// The operation in progress, like a merge or a rebase
+    fn state(&self) -> Option<&'static str> {
+        let exists = |name: &str| self.git_dir.join(name).exists();
+        if exists("rebase-merge") || exists("rebase-apply") {
+            Some("REBASE")
+        } else if exists("MERGE_HEAD") {
+            Some("MERGING")
+        } else if exists("CHERRY_PICK_HEAD") {
+            Some("CHERRY-PICKING")
+        } else if exists("REVERT_HEAD") {
+            Some("REVERTING")
+        } else if exists("BISECT_LOG") {
+            Some("BISECTING")
+        } else {
+            None
+        }
+    }
+}
+
+/// Collect the loose refs in the directory and the ones below it
+fn loose_refs(common_dir: &Path, dir: &Path, refs: &mut Vec<(String, String)>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.filter_map(Result::ok) {
+        let path = entry.path();
+        if path.is_dir() {
+            loose_refs(common_dir, &path, refs);
+        } else if let Ok(hash) = fs::read_to_string(&path) {
+            let name = path.strip_prefix(common_dir).unwrap_or(&path);
+            refs.push((name.to_string_lossy().into_owned(), hash.trim().to_owned()));
+        }
+    }
+}
+
+// ------
+// STATUS
+// ------
+
+/// The state of the working tree, compared to `HEAD` and the upstream branch
+#[derive(Debug, Default, PartialEq)]
+pub struct Status {
+    pub staged: bool,
+    pub unstaged: bool,
+    pub untracked: bool,
+    pub ahead: u32,
+    pub behind: u32,
+}
+
+impl Status {
+    /// Read the output of `git status --porcelain=v2 --branch`
+    fn parse(output: &str) -> Self {
+        let mut status = Status::default();
+        for line in output.lines() {
+            let mut fields = line.split(' ');
+            match fields.next() {
+                Some("#") if line.starts_with("# branch.ab ") => {
+                    for field in fields.skip(1) {
+                        if let Some(ahead) = field.strip_prefix('+') {
+                            status.ahead = ahead.parse().unwrap_or_default();
+                        } else if let Some(behind) = field.strip_prefix('-') {
+                            status.behind = behind.parse().unwrap_or_default();
+                        }
+                    }
+                }
+                // Changed and renamed entries, with the staged and unstaged state as `XY`
+                Some("1" | "2") => {
+                    let xy: Vec<char> = fields.next().unwrap_or_default().chars().collect();
+                    status.staged |= xy.first().is_some_and(|&c| c != '.');
+                    status.unstaged |= xy.get(1).is_some_and(|&c| c != '.');
+                }
+                // Merge conflicts need the user's attention, like unstaged changes
+                Some("u") => status.unstaged = true,
+                Some("?") => status.untracked = true,
+                _ => {}
+            }
+        }
+        status
+    }
+
+    /// Run `git status` in the working tree, and give up once the timeout is reached
+    fn read(work_tree: &Path, timeout: Duration) -> Option<Self> {
+        let mut child = Command::new("git")
+            .args([
+                "--no-optional-locks",
+                "status",
+                "--porcelain=v2",
+                "--branch",
+            ])
+            .current_dir(work_tree)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .ok()?;
+
+        // Read the output on another thread, so that a full pipe can't stall `git`
+        let mut stdout = child.stdout.take()?;
+        let reader = thread::spawn(move || {
+            let mut output = String::new();
+            stdout.read_to_string(&mut output).map(|_| output)
+        });
+
+        let deadline = Instant::now() + timeout;
+        loop {
+            match child.try_wait() {
+                Ok(Some(status)) if status.success() => break,
+                Ok(Some(_)) | Err(_) => return None,
+                Ok(None) if Instant::now() >= deadline => {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return None;
+                }
+                Ok(None) => thread::sleep(Duration::from_millis(5)),
+            }
+        }
+        let output = reader.join().ok()?.ok()?;
+        Some(Status::parse(&output))
+    }
+}
+
+// -------------
+// PROMPT STRING
+// -------------
+
+/// The git segment of the prompt for the directory, like `main *+% ↑1 ↓2`, or nothing outside
+/// of a repository. The markers are for unstaged (`*`), staged (`+`) and untracked (`%`) changes,
+/// and the arrows count the commits ahead of and behind the upstream branch. They are left out
+/// if `git status` takes longer than the timeout.
+pub fn prompt(dir: &Path, timeout: Duration) -> String {
+    let Some(repository) = Repository::discover(dir) else {
+        return String::new();
+    };
+    let mut prompt = match repository.head() {
+        Some(Head::Branch(name)) => name,
+        Some(Head::Detached(name)) => format!("({})", name),
+        None => return String::new(),
+    };
+    if let Some(state) = repository.state() {
+        prompt.push('|');
+        prompt.push_str(state);
+    }
+
+    // Inside the git directory itself, there is no working tree to compare
+    if dir.starts_with(&repository.git_dir) {
+        return prompt;
+    }
+    if let Some(status) = Status::read(&repository.work_tree, timeout) {
+        let markers: String = [
+            (status.unstaged, '*'),
+            (status.staged, '+'),
+            (status.untracked, '%'),
+        ]
+        .iter()
+        .filter(|(on, _)| *on)
+        .map(|(_, marker)| *marker)
+        .collect();
+        if !markers.is_empty() {
+            prompt.push(' ');
+            prompt.push_str(&markers);
+        }
+        if status.ahead > 0 {
+            prompt.push_str(&format!(" ↑{}", status.ahead));
+        }
+        if status.behind > 0 {
+            prompt.push_str(&format!(" ↓{}", status.behind));
+        }
+    }
+    prompt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A directory for a test, removed when the test is done
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let path =
+                std::env::temp_dir().join(format!("shell-git-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            TempDir(path)
+        }
+
+        fn write(&self, file: &str, contents: &str) {
+            let path = self.0.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_branch_from_a_subdirectory() {
+        let repo = TempDir::new("branch");
+        repo.write(".git/HEAD", "ref: refs/heads/feature/x\n");
+        repo.write("src/lib.rs", "");
+        let found = Repository::discover(&repo.0.join("src")).unwrap();
+        assert_eq!(found.work_tree, repo.0);
+        assert_eq!(found.head(), Some(Head::Branch(String::from("feature/x"))));
+    }
+
+    #[test]
+    fn test_detached_head_and_tags() {
+        let repo = TempDir::new("detached");
+        let hash = "0123456789abcdef0123456789abcdef01234567";
+        repo.write(".git/HEAD", &format!("{}\n", hash));
+        let found = Repository::discover(&repo.0).unwrap();
+        assert_eq!(
+            found.head(),
+            Some(Head::Detached(String::from("0123456...")))
+        );
+
+        // An annotated tag in packed-refs is matched by the commit it points at
+        repo.write(
+            ".git/packed-refs",
+            &format!("# pack-refs with: peeled\nffff refs/tags/v1.0\n^{}\n", hash),
+        );
+        assert_eq!(found.head(), Some(Head::Detached(String::from("v1.0"))));
+    }
+
+    #[test]
+    fn test_linked_worktree() {
+        let repo = TempDir::new("worktree");
+        repo.write("main/.git/HEAD", "ref: refs/heads/main\n");
+        repo.write("main/.git/worktrees/wt/HEAD", "ref: refs/heads/topic\n");
+        repo.write("main/.git/worktrees/wt/commondir", "../..\n");
+        repo.write("wt/.git", "gitdir: ../main/.git/worktrees/wt\n");
+        let found = Repository::discover(&repo.0.join("wt")).unwrap();
+        assert_eq!(found.head(), Some(Head::Branch(String::from("topic"))));
+        assert_eq!(
+            found.common_dir,
+            repo.0.join("wt/../main/.git/worktrees/wt/../..")
+        );
+    }
+
+    #[test]
+    fn test_parse_status() {
+        let output = "# branch.oid abc\n# branch.head main\n# branch.ab +2 -1\n\
+                      1 M. N... 100644 100644 100644 a b file\n? new\n";
+        assert_eq!(
+            Status::parse(output),
+            Status {
+                staged: true,
+                unstaged: false,
+                untracked: true,
+                ahead: 2,
+                behind: 1
+            }
+        );
+    }
+}
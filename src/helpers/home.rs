@@ -1,5 +1,6 @@
 // Library
-use std::{env, path::PathBuf};
+use super::vars;
+use std::path::PathBuf;
 
 // ---------
 // HOME PATH
@@ -8,7 +9,7 @@ use std::{env, path::PathBuf};
 // Get the home path from the `HOME` or `HOMEPATH` environment variable
 pub fn get() -> Option<PathBuf> {
     // Get the home path from the `HOME` environment variable
-    let env_home_path = env::var("HOME").ok();
+    let env_home_path = vars::get("HOME");
 
     // If the `HOME` environment variable is set, return it
     if let Some(home_path) = env_home_path {
@@ -16,7 +17,7 @@ pub fn get() -> Option<PathBuf> {
     }
 
     // If the `HOME` environment variable is not set, check the `HOMEPATH` variable
-    let env_home_path = env::var("HOMEPATH").ok();
+    let env_home_path = vars::get("HOMEPATH");
 
     // If the `HOMEPATH` environment variable is set, return it
     if let Some(home_path) = env_home_path {
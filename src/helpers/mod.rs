@@ -3,5 +3,9 @@
 // ----------------
 
 pub mod ansi;
+pub mod dirs;
+pub mod git;
 pub mod home;
 pub mod path;
+pub mod sys;
+pub mod vars;
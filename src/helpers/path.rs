@@ -1,50 +1,194 @@
 // Library
-use std::{env, path::PathBuf};
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    env,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use super::vars;
 
 // -------------------------
 // PATH ENVIRONMENT VARIABLE
 // -------------------------
 
 /// Find an executable in the `PATH` environment variable.
+/// Executables that are found are remembered in the hash table, for the `hash` builtin.
 pub fn find_executable(name: &str) -> Option<String> {
-    // Get the `PATH` environment variable
-    let path = env::var("PATH").expect("Failed to retrieve the PATH environment variable");
-
-    // Split the `PATH` environment variable into a list of paths
-    let paths: Vec<PathBuf> = env::split_paths(&path).collect();
-
-    // Iterate over the paths and return the first executable found
-    for path in paths {
-        // Check for name executable in each path
-        let executable = path.join(name);
-        if executable.exists() {
-            return Some(executable.to_str()?.to_owned());
-        }
-        // Check name.exe (for windows) while we are at it
-        let executable = path.join(format!("{}.exe", name));
-        if executable.exists() {
-            return Some(executable.to_str()?.to_owned());
+    // Commands given with a path aren't looked up
+    if name.contains('/') {
+        return PathBuf::from(name).exists().then(|| name.to_owned());
+    }
+
+    TABLE.with(|table| {
+        let mut table = table.borrow_mut();
+        table.refresh();
+
+        // A remembered path is used for as long as the file is there
+        if let Some(entry) = table.hashed.get_mut(name) {
+            if entry.path.exists() {
+                entry.hits += 1;
+                return Some(entry.path.to_str()?.to_owned());
+            }
         }
+
+        let path = table.executables.get(name)?.clone();
+        table.hashed.insert(
+            name.to_owned(),
+            Hashed {
+                path: path.clone(),
+                hits: 1,
+            },
+        );
+        Some(path.to_str()?.to_owned())
+    })
+}
+
+/// Find an executable the same way as [`find_executable`], without remembering it in the hash table
+pub fn lookup(name: &str) -> Option<PathBuf> {
+    if name.contains('/') {
+        return PathBuf::from(name).exists().then(|| PathBuf::from(name));
     }
 
-    // Return `None` if no executable was found
-    None
+    TABLE.with(|table| {
+        let mut table = table.borrow_mut();
+        table.refresh();
+        match table.hashed.get(name) {
+            Some(entry) if entry.path.exists() => Some(entry.path.clone()),
+            _ => table.executables.get(name).cloned(),
+        }
+    })
+}
+
+/// Find a file in the `PATH` environment variable, like a script for `source`.
+/// Unlike a command, the file doesn't need to be executable.
+pub fn find_file(name: &str) -> Option<PathBuf> {
+    let path = vars::get("PATH")?;
+    env::split_paths(&path)
+        .map(|dir| dir.join(name))
+        .find(|file| file.is_file())
 }
 
-pub fn get_executables() -> Vec<PathBuf> {
-    let mut collection = Vec::new();
+/// The names of all executables in the `PATH`, sorted and without duplicates
+pub fn get_executables() -> Vec<String> {
+    TABLE.with(|table| {
+        let mut table = table.borrow_mut();
+        table.refresh();
+        table.executables.keys().cloned().collect()
+    })
+}
+
+// ----------
+// HASH TABLE
+// ----------
 
-    // Get the `PATH` environment variable
-    let path = env::var("PATH").expect("Failed to retrieve the PATH environment variable");
+/// A command remembered in the hash table
+#[derive(Debug, Clone)]
+pub struct Hashed {
+    /// The path to the executable
+    pub path: PathBuf,
+    /// The number of times the command was looked up
+    pub hits: u32,
+}
 
-    // Split the `PATH` environment variable into a list of paths
-    for dir in env::split_paths(&path) {
-        if let Ok(entries) = std::fs::read_dir(&dir) {
+/// An index of the executables in the `PATH`, shared by command lookup and completion.
+/// The index is rebuilt when `PATH` changes or when a directory in it is modified,
+/// so that newly installed programs are picked up.
+#[derive(Default)]
+struct HashTable {
+    /// The value of `PATH` the index was built for
+    path: String,
+    /// The directories in the `PATH`, and when they were last modified
+    dirs: Vec<(PathBuf, Option<SystemTime>)>,
+    /// The path of every executable, by name. The first one in the `PATH` wins
+    executables: BTreeMap<String, PathBuf>,
+    /// The commands that were looked up, or added with `hash -p`
+    hashed: HashMap<String, Hashed>,
+}
+
+thread_local! {
+    static TABLE: RefCell<HashTable> = RefCell::new(HashTable::default());
+}
+
+impl HashTable {
+    /// Rebuild the index if it is out of date. A changed `PATH` also forgets the hashed commands.
+    fn refresh(&mut self) {
+        let path = vars::get("PATH").unwrap_or_default();
+        if path != self.path {
+            self.hashed.clear();
+        } else if self.dirs.iter().all(|(dir, mtime)| modified(dir) == *mtime) {
+            return;
+        }
+
+        self.dirs = env::split_paths(&path)
+            .map(|dir| {
+                let mtime = modified(&dir);
+                (dir, mtime)
+            })
+            .collect();
+        self.path = path;
+
+        self.executables.clear();
+        for (dir, _) in &self.dirs {
+            let Ok(entries) = std::fs::read_dir(dir) else {
+                continue;
+            };
             for entry in entries.filter_map(Result::ok) {
-                collection.push(entry.path())
+                let name = entry.file_name().to_string_lossy().into_owned();
+                // Executables on windows are found by their name without `.exe`, too
+                if let Some(stem) = name.strip_suffix(".exe") {
+                    self.executables
+                        .entry(stem.to_owned())
+                        .or_insert(entry.path());
+                }
+                self.executables.entry(name).or_insert(entry.path());
             }
         }
     }
+}
 
-    collection
+/// When the directory was last modified
+fn modified(dir: &Path) -> Option<SystemTime> {
+    std::fs::metadata(dir).and_then(|m| m.modified()).ok()
+}
+
+/// The commands in the hash table, sorted by name
+pub fn hashed() -> Vec<(String, Hashed)> {
+    TABLE.with(|table| {
+        let mut table = table.borrow_mut();
+        table.refresh();
+        let mut hashed: Vec<_> = table
+            .hashed
+            .iter()
+            .map(|(name, entry)| (name.clone(), entry.clone()))
+            .collect();
+        hashed.sort_by(|(a, _), (b, _)| a.cmp(b));
+        hashed
+    })
+}
+
+/// Remember the path of a command, without looking it up
+pub fn remember(name: &str, path: PathBuf) {
+    TABLE.with(|table| {
+        let mut table = table.borrow_mut();
+        table.refresh();
+        table
+            .hashed
+            .insert(name.to_owned(), Hashed { path, hits: 0 });
+    })
+}
+
+/// Forget a command, or all of them. Returns whether the command was in the table.
+pub fn forget(name: Option<&str>) -> bool {
+    TABLE.with(|table| {
+        let mut table = table.borrow_mut();
+        match name {
+            Some(name) => table.hashed.remove(name).is_some(),
+            None => {
+                table.hashed.clear();
+                true
+            }
+        }
+    })
 }
@@ -0,0 +1,565 @@
+// Library
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use super::vars;
+
+// ---------------
+// SYSTEM QUERIES
+// ---------------
+
+/// Declarations for the few C library functions the standard library doesn't wrap
+#[cfg(unix)]
+mod ffi {
+    use std::ffi::{c_char, c_int, c_long};
+
+    /// The broken-down time from `localtime_r`
+    #[repr(C)]
+    pub struct Tm {
+        pub tm_sec: c_int,
+        pub tm_min: c_int,
+        pub tm_hour: c_int,
+        pub tm_mday: c_int,
+        pub tm_mon: c_int,
+        pub tm_year: c_int,
+        pub tm_wday: c_int,
+        pub tm_yday: c_int,
+        pub tm_isdst: c_int,
+        pub tm_gmtoff: c_long,
+        pub tm_zone: *const c_char,
+    }
+
+    /// A time interval from `getrusage`
+    #[repr(C)]
+    pub struct Timeval {
+        pub tv_sec: c_long,
+        pub tv_usec: c_long,
+    }
+
+    /// The resource usage from `getrusage`. Only the CPU times are read.
+    #[repr(C)]
+    pub struct Rusage {
+        pub ru_utime: Timeval,
+        pub ru_stime: Timeval,
+        pub ru_rest: [c_long; 14],
+    }
+
+    /// The children of the process that have terminated and been waited for
+    pub const RUSAGE_CHILDREN: c_int = -1;
+
+    extern "C" {
+        pub fn getuid() -> u32;
+        pub fn gethostname(name: *mut c_char, len: usize) -> c_int;
+        pub fn localtime_r(time: *const i64, tm: *mut Tm) -> *mut Tm;
+        pub fn getrusage(who: c_int, usage: *mut Rusage) -> c_int;
+        pub fn getpgrp() -> c_int;
+        pub fn setpgid(pid: c_int, pgid: c_int) -> c_int;
+        pub fn tcgetpgrp(fd: c_int) -> c_int;
+        pub fn tcsetpgrp(fd: c_int, pgrp: c_int) -> c_int;
+        pub fn waitpid(pid: c_int, status: *mut c_int, options: c_int) -> c_int;
+        pub fn kill(pid: c_int, sig: c_int) -> c_int;
+        pub fn signal(signum: c_int, handler: usize) -> usize;
+    }
+
+    pub const WNOHANG: c_int = 1;
+    pub const WUNTRACED: c_int = 2;
+    #[cfg(target_os = "linux")]
+    pub const WCONTINUED: c_int = 8;
+    #[cfg(not(target_os = "linux"))]
+    pub const WCONTINUED: c_int = 0x10;
+
+    pub const SIG_DFL: usize = 0;
+    pub const SIG_IGN: usize = 1;
+}
+
+/// The user ID of the shell, if the platform has them
+pub fn uid() -> Option<u32> {
+    #[cfg(unix)]
+    {
+        // SAFETY: `getuid` has no preconditions and can't fail
+        Some(unsafe { ffi::getuid() })
+    }
+    #[cfg(not(unix))]
+    {
+        None
+    }
+}
+
+/// The name of the user: `$USER` or `$LOGNAME`, or the entry for the user ID in `/etc/passwd`
+pub fn username() -> Option<String> {
+    if let Some(name) = ["USER", "LOGNAME"]
+        .iter()
+        .find_map(|name| vars::get(name).filter(|v| !v.is_empty()))
+    {
+        return Some(name);
+    }
+    let uid = uid()?.to_string();
+    let passwd = std::fs::read_to_string("/etc/passwd").ok()?;
+    passwd.lines().find_map(|line| {
+        let fields: Vec<&str> = line.split(':').collect();
+        (fields.get(2) == Some(&uid.as_str())).then(|| fields[0].to_owned())
+    })
+}
+
+/// The host name of the machine
+pub fn hostname() -> Option<String> {
+    #[cfg(unix)]
+    {
+        let mut buffer = [0u8; 256];
+        // SAFETY: the buffer is valid for its whole length, and one byte is kept for the terminator
+        let result = unsafe { ffi::gethostname(buffer.as_mut_ptr().cast(), buffer.len() - 1) };
+        if result == 0 {
+            let end = buffer.iter().position(|&b| b == 0).unwrap_or(buffer.len());
+            return Some(String::from_utf8_lossy(&buffer[..end]).into_owned());
+        }
+    }
+    vars::get("HOSTNAME").or_else(|| vars::get("COMPUTERNAME"))
+}
+
+/// The CPU time used by the children of the shell that have finished, as (user, system)
+pub fn children_cpu_time() -> Option<(Duration, Duration)> {
+    #[cfg(unix)]
+    {
+        // SAFETY: `Rusage` is plain data, and `getrusage` fills it in or returns an error
+        let mut usage: ffi::Rusage = unsafe { std::mem::zeroed() };
+        if unsafe { ffi::getrusage(ffi::RUSAGE_CHILDREN, &mut usage) } == 0 {
+            let duration = |t: &ffi::Timeval| {
+                Duration::from_secs(t.tv_sec as u64) + Duration::from_micros(t.tv_usec as u64)
+            };
+            return Some((duration(&usage.ru_utime), duration(&usage.ru_stime)));
+        }
+    }
+    None
+}
+
+// ---------
+// PROCESSES
+// ---------
+
+/// The signals the shell handles itself
+pub const SIGHUP: i32 = 1;
+pub const SIGINT: i32 = 2;
+pub const SIGQUIT: i32 = 3;
+pub const SIGPIPE: i32 = 13;
+pub const SIGTERM: i32 = 15;
+#[cfg(target_os = "linux")]
+pub const SIGCONT: i32 = 18;
+#[cfg(not(target_os = "linux"))]
+pub const SIGCONT: i32 = 19;
+#[cfg(target_os = "linux")]
+pub const SIGTSTP: i32 = 20;
+#[cfg(not(target_os = "linux"))]
+pub const SIGTSTP: i32 = 18;
+pub const SIGTTIN: i32 = 21;
+pub const SIGTTOU: i32 = 22;
+
+/// The signals an interactive shell ignores, so that the keys that interrupt, quit and suspend
+/// only reach the job in the foreground
+pub const INTERACTIVE_SIGNALS: [i32; 5] = [SIGINT, SIGQUIT, SIGTSTP, SIGTTIN, SIGTTOU];
+
+/// The signals, by number, with their names and the way they are reported
+#[cfg(target_os = "linux")]
+const SIGNALS: [(i32, &str, &str); 31] = [
+    (1, "HUP", "Hangup"),
+    (2, "INT", "Interrupt"),
+    (3, "QUIT", "Quit"),
+    (4, "ILL", "Illegal instruction"),
+    (5, "TRAP", "Trace/breakpoint trap"),
+    (6, "ABRT", "Aborted"),
+    (7, "BUS", "Bus error"),
+    (8, "FPE", "Floating point exception"),
+    (9, "KILL", "Killed"),
+    (10, "USR1", "User defined signal 1"),
+    (11, "SEGV", "Segmentation fault"),
+    (12, "USR2", "User defined signal 2"),
+    (13, "PIPE", "Broken pipe"),
+    (14, "ALRM", "Alarm clock"),
+    (15, "TERM", "Terminated"),
+    (16, "STKFLT", "Stack fault"),
+    (17, "CHLD", "Child exited"),
+    (18, "CONT", "Continued"),
+    (19, "STOP", "Stopped (signal)"),
+    (20, "TSTP", "Stopped"),
+    (21, "TTIN", "Stopped (tty input)"),
+    (22, "TTOU", "Stopped (tty output)"),
+    (23, "URG", "Urgent I/O condition"),
+    (24, "XCPU", "CPU time limit exceeded"),
+    (25, "XFSZ", "File size limit exceeded"),
+    (26, "VTALRM", "Virtual timer expired"),
+    (27, "PROF", "Profiling timer expired"),
+    (28, "WINCH", "Window changed"),
+    (29, "IO", "I/O possible"),
+    (30, "PWR", "Power failure"),
+    (31, "SYS", "Bad system call"),
+];
+#[cfg(not(target_os = "linux"))]
+const SIGNALS: [(i32, &str, &str); 31] = [
+    (1, "HUP", "Hangup"),
+    (2, "INT", "Interrupt"),
+    (3, "QUIT", "Quit"),
+    (4, "ILL", "Illegal instruction"),
+    (5, "TRAP", "Trace/BPT trap"),
+    (6, "ABRT", "Abort trap"),
+    (7, "EMT", "EMT trap"),
+    (8, "FPE", "Floating point exception"),
+    (9, "KILL", "Killed"),
+    (10, "BUS", "Bus error"),
+    (11, "SEGV", "Segmentation fault"),
+    (12, "SYS", "Bad system call"),
+    (13, "PIPE", "Broken pipe"),
+    (14, "ALRM", "Alarm clock"),
+    (15, "TERM", "Terminated"),
+    (16, "URG", "Urgent I/O condition"),
+    (17, "STOP", "Stopped (signal)"),
+    (18, "TSTP", "Stopped"),
+    (19, "CONT", "Continued"),
+    (20, "CHLD", "Child exited"),
+    (21, "TTIN", "Stopped (tty input)"),
+    (22, "TTOU", "Stopped (tty output)"),
+    (23, "IO", "I/O possible"),
+    (24, "XCPU", "CPU time limit exceeded"),
+    (25, "XFSZ", "File size limit exceeded"),
+    (26, "VTALRM", "Virtual timer expired"),
+    (27, "PROF", "Profiling timer expired"),
+    (28, "WINCH", "Window size changes"),
+    (29, "INFO", "Information request"),
+    (30, "USR1", "User defined signal 1"),
+    (31, "USR2", "User defined signal 2"),
+];
+
+/// The way a signal is reported, like `Terminated` or `Segmentation fault`
+pub fn signal_description(signal: i32) -> String {
+    SIGNALS
+        .iter()
+        .find(|(number, _, _)| *number == signal)
+        .map(|(_, _, description)| description.to_string())
+        .unwrap_or_else(|| format!("Signal {}", signal))
+}
+
+/// The signals, by number, with their names without the `SIG` prefix
+pub fn signals() -> impl Iterator<Item = (i32, &'static str)> {
+    SIGNALS.iter().map(|(number, name, _)| (*number, *name))
+}
+
+/// The name of a signal, without the `SIG` prefix, like `TERM`
+pub fn signal_name(signal: i32) -> Option<&'static str> {
+    signals()
+        .find(|(number, _)| *number == signal)
+        .map(|(_, name)| name)
+}
+
+/// The signal for a name like `TERM`, `SIGTERM` or `term`, or a number like `15`.
+/// Signal 0 only checks that a process exists.
+pub fn signal_number(name: &str) -> Option<i32> {
+    if let Ok(number) = name.parse::<i32>() {
+        let known = number == 0 || signals().any(|(signal, _)| signal == number);
+        return known.then_some(number);
+    }
+    let name = name.to_ascii_uppercase();
+    let name = name.strip_prefix("SIG").unwrap_or(&name);
+    signals()
+        .find(|(_, other)| *other == name)
+        .map(|(number, _)| number)
+}
+
+/// The file descriptor of the terminal the shell reads from
+const TERMINAL: i32 = 0;
+
+/// How a child process changed, as reported by `waitpid`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WaitStatus {
+    /// The process exited with the status
+    Exited(i32),
+    /// The process was killed by the signal, and whether it dumped core
+    Signaled(i32, bool),
+    /// The process was stopped by the signal
+    Stopped(i32),
+    /// The process was continued after being stopped
+    Continued,
+}
+
+impl WaitStatus {
+    /// Decode the status from `waitpid`
+    fn decode(status: i32) -> Self {
+        match status & 0x7f {
+            0 => WaitStatus::Exited((status >> 8) & 0xff),
+            0x7f if status == 0xffff => WaitStatus::Continued,
+            0x7f => WaitStatus::Stopped((status >> 8) & 0xff),
+            signal => WaitStatus::Signaled(signal, status & 0x80 != 0),
+        }
+    }
+}
+
+/// Wait for the child process to exit or stop. Without `block`, this only checks for a
+/// change (including being continued), and returns `None` if there isn't one.
+/// Also returns `None` if the process doesn't exist, or isn't a child of the shell.
+pub fn wait(pid: i32, block: bool) -> Option<WaitStatus> {
+    #[cfg(unix)]
+    {
+        let options = if block {
+            ffi::WUNTRACED
+        } else {
+            ffi::WUNTRACED | ffi::WNOHANG | ffi::WCONTINUED
+        };
+        let mut status = 0;
+        loop {
+            // SAFETY: the status is a valid place for `waitpid` to write to
+            let result = unsafe { ffi::waitpid(pid, &mut status, options) };
+            if result > 0 {
+                return Some(WaitStatus::decode(status));
+            }
+            if result == 0
+                || std::io::Error::last_os_error().kind() != std::io::ErrorKind::Interrupted
+            {
+                return None;
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (pid, block);
+        None
+    }
+}
+
+/// Send the signal to the process, or to the process group if the ID is negative
+pub fn kill(pid: i32, signal: i32) -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        // SAFETY: `kill` has no memory-safety preconditions
+        if unsafe { ffi::kill(pid, signal) } == 0 {
+            return Ok(());
+        }
+        Err(std::io::Error::last_os_error())
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (pid, signal);
+        Err(std::io::ErrorKind::Unsupported.into())
+    }
+}
+
+/// Move the process into the process group. A process group ID of 0 makes a new group,
+/// led by the process itself.
+pub fn set_process_group(pid: i32, pgid: i32) {
+    #[cfg(unix)]
+    // SAFETY: `setpgid` has no memory-safety preconditions. It can fail if the process has
+    // already moved itself (or has already exec'd), which is fine.
+    unsafe {
+        ffi::setpgid(pid, pgid);
+    }
+    #[cfg(not(unix))]
+    let _ = (pid, pgid);
+}
+
+/// The ID of the shell's process group
+pub fn process_group() -> i32 {
+    #[cfg(unix)]
+    {
+        // SAFETY: `getpgrp` has no preconditions and can't fail
+        unsafe { ffi::getpgrp() }
+    }
+    #[cfg(not(unix))]
+    {
+        std::process::id() as i32
+    }
+}
+
+/// Give the terminal to the process group, so that it can read from it and gets the signals
+/// from its special keys. Returns whether it worked.
+pub fn set_foreground(pgid: i32) -> bool {
+    #[cfg(unix)]
+    {
+        // SAFETY: `tcsetpgrp` has no memory-safety preconditions
+        unsafe { ffi::tcsetpgrp(TERMINAL, pgid) == 0 }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = pgid;
+        false
+    }
+}
+
+/// The process group the terminal belongs to, if the shell has a terminal
+pub fn foreground() -> Option<i32> {
+    #[cfg(unix)]
+    {
+        // SAFETY: `tcgetpgrp` has no memory-safety preconditions
+        let pgid = unsafe { ffi::tcgetpgrp(TERMINAL) };
+        (pgid > 0).then_some(pgid)
+    }
+    #[cfg(not(unix))]
+    {
+        None
+    }
+}
+
+/// The signals caught by [`catch_signal`] that haven't been taken yet, one bit per signal
+static PENDING_SIGNALS: AtomicU64 = AtomicU64::new(0);
+
+/// The handler for the signals the shell catches, which only notes that the signal arrived
+#[cfg(unix)]
+extern "C" fn record_signal(signal: std::ffi::c_int) {
+    PENDING_SIGNALS.fetch_or(1 << signal, Ordering::SeqCst);
+}
+
+/// Catch the signal, so that it doesn't stop or kill the shell, but can be checked for with
+/// [`take_signal`]. Programs the shell runs get back what it does by default.
+pub fn catch_signal(signal: i32) {
+    #[cfg(unix)]
+    // SAFETY: the handler only touches an atomic, which is async-signal-safe
+    unsafe {
+        let handler: extern "C" fn(std::ffi::c_int) = record_signal;
+        ffi::signal(signal, handler as usize);
+    }
+    #[cfg(not(unix))]
+    let _ = signal;
+}
+
+/// Whether the signal arrived since it was last checked for, and forget it if it did
+pub fn take_signal(signal: i32) -> bool {
+    let bit = 1 << signal;
+    PENDING_SIGNALS.fetch_and(!bit, Ordering::SeqCst) & bit != 0
+}
+
+/// A caught signal that hasn't been taken yet, the lowest one if there are several
+pub fn pending_signal() -> Option<i32> {
+    let pending = PENDING_SIGNALS.load(Ordering::SeqCst);
+    (pending != 0).then(|| pending.trailing_zeros() as i32)
+}
+
+/// Ignore the signal, or restore what it does by default. Ignored signals stay ignored in
+/// the programs the shell runs, so they have to be restored in each child before it runs.
+pub fn ignore_signal(signal: i32, ignore: bool) {
+    #[cfg(unix)]
+    // SAFETY: only the standard dispositions are installed, and `signal` is async-signal-safe,
+    // so this can be called between `fork` and `exec`
+    unsafe {
+        ffi::signal(signal, if ignore { ffi::SIG_IGN } else { ffi::SIG_DFL });
+    }
+    #[cfg(not(unix))]
+    let _ = (signal, ignore);
+}
+
+// ----------
+// LOCAL TIME
+// ----------
+
+/// A point in time, in the local time zone
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LocalTime {
+    pub year: i32,
+    /// The month, from 1 to 12
+    pub month: u32,
+    pub day: u32,
+    /// The day of the week, where 0 is Sunday
+    pub weekday: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+}
+
+impl LocalTime {
+    /// The current time. Platforms without `localtime_r` get UTC instead.
+    pub fn now() -> Self {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() as i64);
+
+        #[cfg(unix)]
+        {
+            // SAFETY: `Tm` is plain data, and `localtime_r` fills it in or returns null
+            let mut tm: ffi::Tm = unsafe { std::mem::zeroed() };
+            if !unsafe { ffi::localtime_r(&seconds, &mut tm) }.is_null() {
+                return LocalTime {
+                    year: tm.tm_year + 1900,
+                    month: tm.tm_mon as u32 + 1,
+                    day: tm.tm_mday as u32,
+                    weekday: tm.tm_wday as u32,
+                    hour: tm.tm_hour as u32,
+                    minute: tm.tm_min as u32,
+                    second: tm.tm_sec as u32,
+                };
+            }
+        }
+        LocalTime::utc(seconds)
+    }
+
+    /// The time in UTC, from the seconds since the Unix epoch
+    fn utc(seconds: i64) -> Self {
+        let (days, rest) = (seconds.div_euclid(86400), seconds.rem_euclid(86400));
+
+        // Convert days to a civil date (Howard Hinnant's algorithm)
+        let z = days + 719468;
+        let era = z.div_euclid(146097);
+        let doe = z - era * 146097;
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = (yoe + era * 400 + i64::from(month <= 2)) as i32;
+
+        LocalTime {
+            year,
+            month,
+            day,
+            // The epoch was a Thursday
+            weekday: (days + 4).rem_euclid(7) as u32,
+            hour: (rest / 3600) as u32,
+            minute: (rest / 60 % 60) as u32,
+            second: (rest % 60) as u32,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wait_status() {
+        assert_eq!(WaitStatus::decode(0x0100), WaitStatus::Exited(1));
+        assert_eq!(WaitStatus::decode(0x008b), WaitStatus::Signaled(11, true));
+        assert_eq!(WaitStatus::decode(0x147f), WaitStatus::Stopped(20));
+        assert_eq!(WaitStatus::decode(0xffff), WaitStatus::Continued);
+    }
+
+    #[test]
+    fn test_signal_description() {
+        assert_eq!(signal_description(15), "Terminated");
+        assert_eq!(signal_description(11), "Segmentation fault");
+        assert_eq!(signal_description(SIGTSTP), "Stopped");
+        assert_eq!(signal_description(64), "Signal 64");
+    }
+
+    #[test]
+    fn test_signal_number() {
+        assert_eq!(signal_number("TERM"), Some(SIGTERM));
+        assert_eq!(signal_number("sigint"), Some(SIGINT));
+        assert_eq!(signal_number("9"), Some(9));
+        assert_eq!(signal_number("99"), None);
+        assert_eq!(signal_number("FOO"), None);
+        assert_eq!(signal_name(SIGHUP), Some("HUP"));
+    }
+
+    #[test]
+    fn test_utc() {
+        // 2024-02-29 13:45:30 UTC, a Thursday
+        let time = LocalTime::utc(1709214330);
+        assert_eq!(
+            time,
+            LocalTime {
+                year: 2024,
+                month: 2,
+                day: 29,
+                weekday: 4,
+                hour: 13,
+                minute: 45,
+                second: 30
+            }
+        );
+    }
+}
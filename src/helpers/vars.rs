@@ -0,0 +1,331 @@
+// Library
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    env,
+};
+
+// ---------
+// VARIABLES
+// ---------
+
+/// A plain variable of the shell
+struct Variable {
+    /// The value, or `None` for a name that was exported before it was given a value
+    value: Option<String>,
+    /// Whether the programs the shell runs get the variable in their environment
+    exported: bool,
+}
+
+// The shell keeps its variables to itself, and only the exported ones go in the environment that
+// programs inherit. Arrays and the positional parameters can't be exported at all.
+thread_local! {
+    static VARIABLES: RefCell<HashMap<String, Variable>> = RefCell::new(inherited());
+    static ARRAYS: RefCell<HashMap<String, Vec<String>>> = RefCell::new(HashMap::new());
+    static POSITIONAL: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+    static SCRIPT_NAME: RefCell<String> = RefCell::new(String::from("shell"));
+    static STATUS: Cell<i32> = const { Cell::new(0) };
+    static LAST_BACKGROUND: Cell<Option<i32>> = const { Cell::new(None) };
+    static FLAGS: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+/// The variables the shell starts out with, which are the environment it was given. They stay
+/// exported.
+fn inherited() -> HashMap<String, Variable> {
+    env::vars_os()
+        .filter_map(|(name, value)| Some((name.into_string().ok()?, value.into_string().ok()?)))
+        .map(|(name, value)| {
+            let variable = Variable {
+                value: Some(value),
+                exported: true,
+            };
+            (name, variable)
+        })
+        .collect()
+}
+
+/// Get the value of a variable, as it would be expanded by `$NAME` or `${NAME}`.
+///
+/// Besides plain variables this understands the positional parameters (`1`, `2`, ...), the name
+/// of the shell or script (`0`), all of the positional parameters (`@` and `*`) and how many there
+/// are (`#`), the exit status of the last command (`?`), the process ID of the shell (`$`) and of
+/// the last background job (`!`), the letters of the options that are on (`-`), and array elements
+/// (`NAME[1]`, `NAME[$i]`, `NAME[@]`). An array without an index expands to its first element.
+pub fn get(name: &str) -> Option<String> {
+    if let Some((array, index)) = name.strip_suffix(']').and_then(|n| n.split_once('[')) {
+        let values = get_array(array)?;
+        return match index {
+            "@" | "*" => Some(values.join(" ")),
+            _ => values.get(resolve_index(index)?).cloned(),
+        };
+    }
+    match name {
+        "0" => return Some(SCRIPT_NAME.with(|n| n.borrow().clone())),
+        "@" | "*" => return Some(positional().join(" ")),
+        "#" => return Some(positional().len().to_string()),
+        "?" => return Some(STATUS.with(Cell::get).to_string()),
+        "$" => return Some(std::process::id().to_string()),
+        "!" => return LAST_BACKGROUND.with(Cell::get).map(|pid| pid.to_string()),
+        "-" => return Some(FLAGS.with(|f| f.borrow().clone())),
+        _ => {}
+    }
+    if let Ok(n) = name.parse::<usize>() {
+        return POSITIONAL.with(|p| p.borrow().get(n.checked_sub(1)?).cloned());
+    }
+    if let Some(values) = ARRAYS.with(|a| a.borrow().get(name).cloned()) {
+        return values.into_iter().next();
+    }
+    value(name)
+}
+
+/// The value of a plain variable
+fn value(name: &str) -> Option<String> {
+    VARIABLES.with(|v| v.borrow().get(name).and_then(|v| v.value.clone()))
+}
+
+/// Get the elements of an array. A plain variable counts as an array with a single element.
+pub fn get_array(name: &str) -> Option<Vec<String>> {
+    ARRAYS
+        .with(|a| a.borrow().get(name).cloned())
+        .or_else(|| value(name).map(|value| vec![value]))
+}
+
+/// Set a plain variable, replacing any array of the same name. A new variable isn't exported,
+/// and one that is exported stays that way.
+pub fn set(name: &str, value: &str) {
+    ARRAYS.with(|a| a.borrow_mut().remove(name));
+    VARIABLES.with(|v| {
+        let mut variables = v.borrow_mut();
+        let variable = variables.entry(name.to_owned()).or_insert(Variable {
+            value: None,
+            exported: false,
+        });
+        variable.value = Some(value.to_owned());
+        if variable.exported {
+            env::set_var(name, value);
+        }
+    });
+}
+
+/// Set an array, replacing any plain variable of the same name
+pub fn set_array(name: &str, values: Vec<String>) {
+    remove(name);
+    ARRAYS.with(|a| a.borrow_mut().insert(name.to_owned(), values));
+}
+
+/// Remove a variable or array
+pub fn unset(name: &str) {
+    ARRAYS.with(|a| a.borrow_mut().remove(name));
+    remove(name);
+}
+
+/// Remove a plain variable, along with its place in the environment
+fn remove(name: &str) {
+    if let Some(variable) = VARIABLES.with(|v| v.borrow_mut().remove(name)) {
+        if variable.exported {
+            env::remove_var(name);
+        }
+    }
+}
+
+/// Export a variable to the programs the shell runs, or stop exporting it. A name without a
+/// value is exported once it is given one.
+pub fn export(name: &str, exported: bool) {
+    VARIABLES.with(|v| {
+        let mut variables = v.borrow_mut();
+        if !exported && !variables.contains_key(name) {
+            return;
+        }
+        let variable = variables.entry(name.to_owned()).or_insert(Variable {
+            value: None,
+            exported,
+        });
+        variable.exported = exported;
+        match (&variable.value, exported) {
+            (Some(value), true) => env::set_var(name, value),
+            _ => env::remove_var(name),
+        }
+    });
+}
+
+/// Whether the variable is exported to the programs the shell runs
+pub fn is_exported(name: &str) -> bool {
+    VARIABLES.with(|v| v.borrow().get(name).is_some_and(|v| v.exported))
+}
+
+/// The plain variables that are set, with their values, sorted by name
+pub fn variables() -> Vec<(String, String)> {
+    let mut variables: Vec<(String, String)> = VARIABLES.with(|v| {
+        v.borrow()
+            .iter()
+            .filter_map(|(name, v)| Some((name.clone(), v.value.clone()?)))
+            .collect()
+    });
+    variables.sort();
+    variables
+}
+
+/// The exported variables, with their values if they have one, sorted by name
+pub fn exported() -> Vec<(String, Option<String>)> {
+    let mut exported: Vec<(String, Option<String>)> = VARIABLES.with(|v| {
+        v.borrow()
+            .iter()
+            .filter(|(_, v)| v.exported)
+            .map(|(name, v)| (name.clone(), v.value.clone()))
+            .collect()
+    });
+    exported.sort();
+    exported
+}
+
+/// The arrays that are set, with their elements
+pub fn arrays() -> Vec<(String, Vec<String>)> {
+    ARRAYS.with(|a| {
+        a.borrow()
+            .iter()
+            .map(|(name, values)| (name.clone(), values.clone()))
+            .collect()
+    })
+}
+
+/// Replace the positional parameters, returning the previous ones so that they can be restored
+pub fn set_positional(values: Vec<String>) -> Vec<String> {
+    POSITIONAL.with(|p| std::mem::replace(&mut *p.borrow_mut(), values))
+}
+
+/// The positional parameters, `$1` onwards
+pub fn positional() -> Vec<String> {
+    POSITIONAL.with(|p| p.borrow().clone())
+}
+
+/// Set the name of the shell or script, which `$0` expands to
+pub fn set_script_name(name: &str) {
+    SCRIPT_NAME.with(|n| *n.borrow_mut() = name.to_owned());
+}
+
+/// Set the exit status that `$?` expands to
+pub fn set_status(status: i32) {
+    STATUS.with(|s| s.set(status));
+}
+
+/// Set the process ID of the last background job, which `$!` expands to
+pub fn set_last_background(pid: i32) {
+    LAST_BACKGROUND.with(|p| p.set(Some(pid)));
+}
+
+/// Set the letters of the options that are on, which `$-` expands to
+pub fn set_flags(flags: &str) {
+    FLAGS.with(|f| *f.borrow_mut() = flags.to_owned());
+}
+
+/// Whether the text is a valid variable name
+pub fn is_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Expand the parameters in the text, like `$NAME`, `${NAME}` and `$1`, and nothing else. Quotes,
+/// backslashes and tildes are left as they are, and the text isn't split into words.
+pub fn expand_parameters(text: &str) -> String {
+    let mut expanded = String::new();
+    let mut chars = text.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if ch != '$' {
+            expanded.push(ch);
+            continue;
+        }
+        let name = match chars.peek() {
+            Some('{') => {
+                chars.next();
+                chars.by_ref().take_while(|&c| c != '}').collect()
+            }
+            Some(&c) if c.is_alphabetic() || c == '_' => {
+                let mut name = String::new();
+                while let Some(c) = chars.next_if(|&c| c.is_alphanumeric() || c == '_') {
+                    name.push(c);
+                }
+                name
+            }
+            Some(&c)
+                if c.is_ascii_digit() || matches!(c, '@' | '*' | '#' | '?' | '$' | '!' | '-') =>
+            {
+                chars.next();
+                c.to_string()
+            }
+            _ => {
+                expanded.push('$');
+                continue;
+            }
+        };
+        expanded.push_str(&get(&name).unwrap_or_default());
+    }
+    expanded
+}
+
+/// Work out an array index, which is either a number or the name of a variable holding one
+fn resolve_index(index: &str) -> Option<usize> {
+    let index = index.trim().trim_start_matches('$');
+    match index.parse() {
+        Ok(n) => Some(n),
+        Err(_) => get(index)?.trim().parse().ok(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_array_elements() {
+        set_array("TEST_WORDS", vec!["svc".into(), "st".into()]);
+        set("TEST_CWORD", "1");
+        assert_eq!(get("TEST_WORDS"), Some("svc".into()));
+        assert_eq!(get("TEST_WORDS[1]"), Some("st".into()));
+        assert_eq!(get("TEST_WORDS[TEST_CWORD]"), Some("st".into()));
+        assert_eq!(get("TEST_WORDS[$TEST_CWORD]"), Some("st".into()));
+        assert_eq!(get("TEST_WORDS[@]"), Some("svc st".into()));
+        assert_eq!(get("TEST_WORDS[2]"), None);
+        unset("TEST_WORDS");
+        assert_eq!(get("TEST_WORDS"), None);
+    }
+
+    #[test]
+    fn test_positional_parameters() {
+        let previous = set_positional(vec!["a".into(), "b".into()]);
+        assert_eq!(get("1"), Some("a".into()));
+        assert_eq!(get("2"), Some("b".into()));
+        assert_eq!(get("3"), None);
+        set_positional(previous);
+    }
+
+    #[test]
+    fn test_expand_parameters() {
+        set("TEST_DIR", "/home/me");
+        let previous = set_positional(vec!["a".into()]);
+        assert_eq!(
+            expand_parameters("$TEST_DIR/.env ${TEST_DIR}rc $1 $UNSET_NAME. $ ~ '$(x)'"),
+            "/home/me/.env /home/merc a . $ ~ '$(x)'"
+        );
+        set_positional(previous);
+        unset("TEST_DIR");
+    }
+
+    #[test]
+    fn test_shell_variables_are_not_exported() {
+        set("TEST_SHELL_ONLY", "value");
+        assert_eq!(get("TEST_SHELL_ONLY"), Some("value".into()));
+        assert!(!is_exported("TEST_SHELL_ONLY"));
+        assert!(env::var("TEST_SHELL_ONLY").is_err());
+        unset("TEST_SHELL_ONLY");
+
+        // A name exported before it has a value is exported along with it
+        export("TEST_EXPORTED_LATER", true);
+        assert_eq!(get("TEST_EXPORTED_LATER"), None);
+        set("TEST_EXPORTED_LATER", "value");
+        assert_eq!(env::var("TEST_EXPORTED_LATER"), Ok("value".into()));
+        export("TEST_EXPORTED_LATER", false);
+        assert!(env::var("TEST_EXPORTED_LATER").is_err());
+        assert_eq!(get("TEST_EXPORTED_LATER"), Some("value".into()));
+        unset("TEST_EXPORTED_LATER");
+    }
+}
@@ -5,20 +5,143 @@ mod parser;
 mod shell;
 
 // Library
-use helpers::ansi::Colorable;
-use shell::Shell;
+use helpers::ansi::{self, ColorChoice, Colorable};
+use shell::{Options, Shell, Startup};
+use std::{io::IsTerminal, path::PathBuf};
 
 // ----
 // MAIN
 // ----
 
-/// The main entry point of the application
+/// The usage of the shell, for when its options don't make sense
+const USAGE: &str = "\
+Usage: shell [option ...] [script [arg ...]]
+       shell [option ...] -c command [name [arg ...]]
+       shell [option ...] -s [arg ...]
+Options: --color=auto|always|never, -l (--login), --norc, --noprofile, --rcfile file,
+         and the letters of `set` (-eunxC)";
+
+/// Where the shell reads its commands from
+enum Input {
+    /// A command string, from `-c`
+    Command(String),
+    /// A script file
+    File(String),
+    /// Stdin, with the line editor, since it's a terminal
+    Terminal,
+    /// Stdin, as it is
+    Stdin,
+}
+
+/// The main entry point of the application.
+///
+/// With a script, the shell runs it with the arguments after it as the positional parameters.
+/// With `-c`, it runs the command string instead, with the name after it as `$0`. Otherwise
+/// it reads commands from stdin, with the line editor if stdin is a terminal. `-s` reads from
+/// stdin even when there are arguments, which become the positional parameters.
+///
+/// The startup files are read first (see [`Shell::read_startup_files`]). A shell started with
+/// `-l`, or with a name starting with `-`, is a login shell.
 fn main() {
+    let mut args = std::env::args().peekable();
+    let name = args.next().unwrap_or_else(|| String::from("shell"));
+
     // Initialize the Shell
     let mut shell = Shell::default();
+    let mut startup = Startup {
+        login: name.starts_with('-'),
+        ..Startup::default()
+    };
+
+    // Apply the command-line options, which come before the script and its arguments
+    let (mut command, mut stdin) = (false, false);
+    while let Some(arg) = args.next_if(|arg| arg.starts_with('-') && arg != "-") {
+        match arg.as_str() {
+            "--" => break,
+            "--login" => startup.login = true,
+            "--norc" => startup.rc = false,
+            "--noprofile" => startup.profile = false,
+            "--rcfile" => match args.next() {
+                Some(file) => startup.rcfile = Some(PathBuf::from(file)),
+                None => {
+                    eprintln!("shell: --rcfile: option requires an argument");
+                    std::process::exit(2);
+                }
+            },
+            _ if arg.starts_with("--color=") => {
+                let choice = &arg["--color=".len()..];
+                if let Err(e) = choice.parse::<ColorChoice>().map(ansi::set_choice) {
+                    eprintln!("shell: --color={}", e);
+                    std::process::exit(2);
+                }
+            }
+            _ if arg.starts_with("--") => invalid_option(&arg),
+            // Single-letter options can be grouped, like `-ex`, and include those of `set`
+            _ => {
+                for letter in arg.chars().skip(1) {
+                    match letter {
+                        'c' => command = true,
+                        's' => stdin = true,
+                        'l' => startup.login = true,
+                        letter => match Options::name_for(letter) {
+                            Some(name) => shell.set_shell_option(name, true).unwrap_or_default(),
+                            None => invalid_option(&arg),
+                        },
+                    }
+                }
+            }
+        }
+    }
+    let mut args: Vec<String> = args.collect();
 
-    // Start the Shell's Read-Eval-Print Loop (REPL)
-    if let Err(e) = shell.run() {
+    // Work out where the commands come from, along with `$0` and the positional parameters
+    let (input, name) = if command {
+        if args.is_empty() {
+            eprintln!("shell: -c: option requires an argument");
+            std::process::exit(2);
+        }
+        let command = args.remove(0);
+        let name = if args.is_empty() {
+            name
+        } else {
+            args.remove(0)
+        };
+        (Input::Command(command), name)
+    } else if !stdin && !args.is_empty() {
+        let script = args.remove(0);
+        (Input::File(script.clone()), script)
+    } else if std::io::stdin().is_terminal() {
+        (Input::Terminal, name)
+    } else {
+        (Input::Stdin, name)
+    };
+    shell.set_arguments(&name, args);
+    startup.interactive = matches!(input, Input::Terminal);
+    // The interactive shell runs the startup files with job control, like the commands typed later
+    if startup.interactive {
+        shell.start_job_control();
+    }
+    shell.read_startup_files(&startup);
+
+    // Run the commands
+    let result = match input {
+        Input::Command(command) => shell.run_command(&command),
+        Input::File(script) => shell.run_file(&script),
+        // Start the Shell's Read-Eval-Print Loop (REPL)
+        Input::Terminal => shell.run(),
+        Input::Stdin => shell.run_stdin(),
+    };
+    if let Err(e) = result {
         eprintln!("Error: {}", e.red());
     }
+
+    // Exit with the status of the last command, like other shells do on end-of-file
+    std::process::exit(shell.status());
+}
+
+/// Reports an option the shell doesn't know, and exits
+fn invalid_option(arg: &str) -> ! {
+    eprintln!("shell: {}: invalid option", arg);
+    eprintln!("{}", USAGE);
+    std::process::exit(2);
 }
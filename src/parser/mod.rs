@@ -1,6 +1,9 @@
 // Library
 use std::iter::Peekable;
-use std::str::Chars;
+use std::ops::Range;
+use std::str::CharIndices;
+
+use crate::helpers;
 
 /// Represents the various states the parser can be in
 #[derive(Debug)]
@@ -10,38 +13,137 @@ enum ParseState {
     InDoubleQuote,
 }
 
+/// How a redirection opens its file
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OpenMode {
+    /// `>` empties the file first, unless `set -C` forbids overwriting it
+    Truncate,
+    /// `>>` adds to the end of the file
+    Append,
+    /// `>|` empties the file first, even with `set -C`
+    Clobber,
+}
+
+/// A redirection target: the filename, and how to open it
+pub type Target = (String, OpenMode);
+
+/// The arguments of a command, and the targets its stdout and stderr are redirected to
+pub type Parsed = (Vec<String>, Option<Target>, Option<Target>);
+
+/// A word in the input, along with where it was found
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    /// The value of the word, with quotes and escapes removed and variables expanded
+    pub value: String,
+    /// The byte offsets of the word in the input, including any quotes
+    pub span: Range<usize>,
+    /// Whether any part of the word was quoted or escaped
+    pub quoted: bool,
+    /// The quote that was still open at the end of the input, if any.
+    /// This only happens for the last word of a partial input.
+    pub open_quote: Option<char>,
+}
+
+impl Token {
+    /// The word, if it is written out plainly in the input rather than quoted, escaped or
+    /// expanded from a variable. Only such a word can be an operator, so that `X='|'; echo $X`
+    /// prints a `|`. The newline between two commands counts as a `;`.
+    pub fn plain(&self, input: &str) -> Option<&str> {
+        let written = &input[self.span.clone()];
+        (written == self.value || (written == "\n" && self.value == ";"))
+            .then_some(self.value.as_str())
+    }
+}
+
+/// What a [`Fragment`] of the input is, for syntax highlighting
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FragmentKind {
+    /// A single- or double-quoted string, including the quotes
+    Quoted,
+    /// A character escaped with a backslash
+    Escape,
+    /// A variable, like `$NAME`, `${NAME}` or `$1`
+    Variable,
+    /// A comment, from `#` to the end of the line
+    Comment,
+}
+
+/// A part of the input that is more than plain text, along with where it was found.
+/// Fragments can be nested, like a variable inside double quotes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fragment {
+    pub kind: FragmentKind,
+    /// The byte offsets of the fragment in the input
+    pub span: Range<usize>,
+}
+
+/// The shell options that change how the input is parsed
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ParseOptions {
+    /// Whether expanding a variable that isn't set is an error, for `set -u`
+    pub nounset: bool,
+}
+
 #[derive(Debug)]
 /// A `Parser` struct that holds the state and context for parsing operations.
 pub struct Parser<'a> {
     /// A collection to store the resulting arguments
-    args: Vec<String>,
+    args: Vec<Token>,
     /// A string representing the current token being processed
     current: String,
+    /// The byte offset where the current token started, if one has been started
+    start: Option<usize>,
+    /// Whether any part of the current token was quoted or escaped
+    quoted: bool,
+    /// The byte offset just past the last character that was read
+    position: usize,
     /// The current state of the parser, represented by the `ParseState` enum
     state: ParseState,
+    /// The quotes, escapes, variables and comments found so far
+    fragments: Vec<Fragment>,
+    /// The byte offset of the quote that was opened last
+    quote_start: usize,
+    /// Whether an unset variable is an error, for `set -u`
+    nounset: bool,
+    /// The first unset variable that was expanded, if `nounset` is on
+    unbound: Option<String>,
+    /// The input string, for the words as they are written in it
+    input: &'a str,
     /// An iterator over the characters of the input string, allowing for peeking at the next character
-    chars: Peekable<Chars<'a>>,
+    chars: Peekable<CharIndices<'a>>,
 }
 
 impl<'a> Parser<'a> {
     /// Instantiate a new Parser with the initial conditions
-    fn new(input: &str) -> Parser {
+    fn new(input: &str) -> Parser<'_> {
         Parser {
             args: Vec::new(),
             current: String::new(),
+            start: None,
+            quoted: false,
+            position: 0,
             state: ParseState::Normal,
-            chars: input.trim().chars().peekable(),
+            fragments: Vec::new(),
+            quote_start: 0,
+            nounset: false,
+            unbound: None,
+            input,
+            chars: input.char_indices().peekable(),
         }
     }
 
     /// Parses an input string into a vector of arguments, handling quotes and escapes.
-    pub fn parse(
-        input: &str,
-    ) -> Result<(Vec<String>, Option<(String, bool)>, Option<(String, bool)>), String> {
+    pub fn parse(input: &str) -> Result<Parsed, String> {
+        Parser::parse_with_options(input, ParseOptions::default())
+    }
+
+    /// Parses an input string like [`Parser::parse`], with the shell options that affect parsing
+    pub fn parse_with_options(input: &str, options: ParseOptions) -> Result<Parsed, String> {
         let mut parser = Parser::new(input); // Initialize the parser
+        parser.nounset = options.nounset;
 
         // Iterate over the characters...
-        while let Some(ch) = parser.chars.next() {
+        while let Some(ch) = parser.next_char() {
             parser.state = match parser.state {
                 ParseState::Normal => parser.handle_normal(ch)?,
                 ParseState::InSingleQuote => parser.handle_in_single_quote(ch),
@@ -51,35 +153,349 @@ impl<'a> Parser<'a> {
         }
 
         // Once the iteration is complete, put any remaining tokens in current as the final argument
-        if !parser.current.is_empty() {
-            parser.args.push(parser.current);
+        parser.finish_token(parser.position, None);
+        if let Some(name) = parser.unbound {
+            return Err(format!("{}: unbound variable", name));
         }
 
-        let (args, stdout_target, stderr_target) = extract_redirection(parser.args);
+        let (args, stdout_target, stderr_target) = extract_redirection(input, parser.args);
 
         // Return the resulting vector of arguments
         Ok((args, stdout_target, stderr_target))
     }
 
+    /// Splits a (possibly incomplete) input string into words, keeping track of where each word
+    /// is in the input. Unlike [`Parser::parse`], this never fails: a trailing backslash is
+    /// ignored and an unterminated quote is reported through [`Token::open_quote`].
+    pub fn tokenize(input: &str) -> Vec<Token> {
+        Parser::scan(input).args
+    }
+
+    /// Finds the quotes, escapes, variables and comments in a (possibly incomplete) input string,
+    /// ordered by where they start. An unterminated quote runs to the end of the input.
+    pub fn fragments(input: &str) -> Vec<Fragment> {
+        let mut fragments = Parser::scan(input).fragments;
+        fragments.sort_by_key(|fragment| fragment.span.start);
+        fragments
+    }
+
+    /// Runs the parser over the whole input without failing, for [`Parser::tokenize`] and [`Parser::fragments`]
+    fn scan(input: &str) -> Parser<'_> {
+        let mut parser = Parser::new(input);
+
+        while let Some(ch) = parser.next_char() {
+            let state = match parser.state {
+                ParseState::Normal => parser.handle_normal(ch),
+                ParseState::InSingleQuote => Ok(parser.handle_in_single_quote(ch)),
+                ParseState::InDoubleQuote => parser.handle_in_double_quote(ch),
+            };
+            match state {
+                Ok(state) => parser.state = state,
+                Err(_) => break, // Only a trailing backslash can fail, and it's the end anyway
+            }
+        }
+
+        let open_quote = match parser.state {
+            ParseState::Normal => None,
+            ParseState::InSingleQuote => Some('\''),
+            ParseState::InDoubleQuote => Some('"'),
+        };
+        if open_quote.is_some() {
+            parser.add_fragment(FragmentKind::Quoted, parser.quote_start);
+        }
+        parser.finish_token(parser.position, open_quote);
+        parser
+    }
+
+    /// Splits the input into the commands separated by `;` or `&`. A command that ends with `&`
+    /// keeps it, so that it can be run in the background (see [`Parser::background`]).
+    /// Each command is an and-or list (see [`Parser::split_list`]).
+    /// Separators inside the braces of a function definition don't split the input.
+    pub fn split_commands(input: &str) -> Vec<&str> {
+        let mut commands = Vec::new();
+        let mut start = 0;
+        let mut depth = 0;
+        // Braces are only special as the first word of a command
+        let mut command_start = true;
+
+        for token in Parser::tokenize(input) {
+            let operator = token.plain(input).unwrap_or_default();
+            match operator {
+                ";" if depth == 0 => {
+                    commands.push(&input[start..token.span.start]);
+                    start = token.span.end;
+                }
+                "&" if depth == 0 => {
+                    commands.push(&input[start..token.span.end]);
+                    start = token.span.end;
+                }
+                "{" if command_start => depth += 1,
+                "}" if command_start && depth > 0 => depth -= 1,
+                _ => {}
+            }
+            command_start = matches!(operator, ";" | "&" | "|" | "&&" | "||" | "{")
+                || is_function_header(operator);
+        }
+        commands.push(&input[start..]);
+
+        commands.retain(|command| !command.trim().is_empty());
+        commands
+    }
+
+    /// Whether the input is complete, rather than going on in the next line. It goes on if it
+    /// ends inside quotes, inside the braces of a function definition, after a backslash, or
+    /// after a `|`, `&&` or `||`.
+    pub fn is_complete(input: &str) -> bool {
+        let line = input.strip_suffix('\n').unwrap_or(input);
+        if (line.len() - line.trim_end_matches('\\').len()) % 2 == 1 {
+            return false;
+        }
+
+        let parser = Parser::scan(input);
+        let mut depth = 0;
+        let mut command_start = true;
+        for token in &parser.args {
+            let operator = token.plain(input).unwrap_or_default();
+            match operator {
+                "{" if command_start => depth += 1,
+                "}" if command_start && depth > 0 => depth -= 1,
+                _ => {}
+            }
+            command_start = matches!(operator, ";" | "&" | "|" | "&&" | "||" | "{")
+                || is_function_header(operator);
+        }
+        let open_quote = parser
+            .args
+            .last()
+            .is_some_and(|token| token.open_quote.is_some());
+        depth == 0 && !open_quote && !parser.continues()
+    }
+
+    /// Splits off the `&` at the end of a command from [`Parser::split_commands`].
+    /// Returns the rest of the command, and whether it is to run in the background.
+    pub fn background(command: &str) -> (&str, bool) {
+        match Parser::tokenize(command).last() {
+            Some(token) if token.plain(command) == Some("&") => {
+                (&command[..token.span.start], true)
+            }
+            _ => (command, false),
+        }
+    }
+
+    /// Splits off the `!` in front of a pipeline, which inverts its exit status.
+    /// Returns the rest of the pipeline, and whether it is negated.
+    pub fn negation(pipeline: &str) -> (&str, bool) {
+        match Parser::tokenize(pipeline).first() {
+            Some(token) if token.plain(pipeline) == Some("!") => {
+                (&pipeline[token.span.end..], true)
+            }
+            _ => (pipeline, false),
+        }
+    }
+
+    /// Splits a command into the pipelines of an and-or list, like `make && make install || exit`.
+    /// Each pipeline comes with the `&&` or `||` in front of it, except for the first one.
+    pub fn split_list(command: &str) -> Vec<(Option<&str>, &str)> {
+        let mut pipelines = Vec::new();
+        let mut start = 0;
+        let mut operator = None;
+        for token in Parser::tokenize(command) {
+            if matches!(token.plain(command), Some("&&" | "||")) {
+                pipelines.push((operator, &command[start..token.span.start]));
+                operator = Some(&command[token.span.clone()]);
+                start = token.span.end;
+            }
+        }
+        pipelines.push((operator, &command[start..]));
+        pipelines
+    }
+
+    /// Splits a command into the stages of a pipeline, separated by `|`.
+    /// A command without any pipes is a pipeline of one stage.
+    pub fn split_pipeline(command: &str) -> Vec<&str> {
+        let mut stages = Vec::new();
+        let mut start = 0;
+        for token in Parser::tokenize(command) {
+            if token.plain(command) == Some("|") {
+                stages.push(&command[start..token.span.start]);
+                start = token.span.end;
+            }
+        }
+        stages.push(&command[start..]);
+        stages
+    }
+
+    /// Parses a function definition of the form `name() { commands; }`.
+    /// Returns the name of the function and its body, or `None` if the input isn't a function definition.
+    pub fn parse_function(input: &str) -> Option<(String, String)> {
+        let tokens = Parser::tokenize(input);
+        let [header, open, .., close] = tokens.as_slice() else {
+            return None;
+        };
+        let header = header.plain(input).unwrap_or_default();
+        let plain = |token: &Token, value: &str| token.plain(input) == Some(value);
+        if !is_function_header(header) || !plain(open, "{") || !plain(close, "}") {
+            return None;
+        }
+
+        let name = header.trim_end_matches("()").to_owned();
+        let body = input[open.span.end..close.span.start].trim().to_owned();
+        Some((name, body))
+    }
+
+    /// Advances to the next character, keeping track of the position in the input
+    fn next_char(&mut self) -> Option<char> {
+        let (i, ch) = self.chars.next()?;
+        self.position = i + ch.len_utf8();
+        Some(ch)
+    }
+
+    /// Peeks at the next character without consuming it
+    fn peek_char(&mut self) -> Option<char> {
+        self.chars.peek().map(|&(_, ch)| ch)
+    }
+
+    /// The current token as it is written in the input, up to the character that was just read
+    fn written(&self) -> &str {
+        self.start
+            .map_or("", |start| &self.input[start..self.position - 1])
+    }
+
+    /// Marks the start of a token at the character that was just read, if none has started yet
+    fn start_token(&mut self, ch: char) {
+        if self.start.is_none() {
+            self.start = Some(self.position - ch.len_utf8());
+        }
+    }
+
+    /// Records a fragment that starts at the given byte offset and ends at the current position
+    fn add_fragment(&mut self, kind: FragmentKind, start: usize) {
+        self.fragments.push(Fragment {
+            kind,
+            span: start..self.position,
+        });
+    }
+
+    /// Pushes the current token (if any), which ends at the given byte offset, onto the list of arguments.
+    /// Words that are empty because of an unset variable are dropped, but quoted empty words are kept.
+    fn finish_token(&mut self, end: usize, open_quote: Option<char>) {
+        if let Some(start) = self.start.take() {
+            if !self.current.is_empty() || self.quoted {
+                self.args.push(Token {
+                    value: std::mem::take(&mut self.current),
+                    span: start..end,
+                    quoted: self.quoted,
+                    open_quote,
+                });
+            }
+        }
+        self.quoted = false;
+    }
+
     /// Handles a character in the Normal state.
     /// Returns the new state after processing the character.
     fn handle_normal(&mut self, ch: char) -> Result<ParseState, String> {
+        // A backslash at the end of a line joins it with the next one
+        if ch == '\\' && self.peek_char() == Some('\n') {
+            self.next_char();
+            return Ok(ParseState::Normal);
+        }
+        if !ch.is_whitespace() {
+            self.start_token(ch);
+        }
         match ch {
             '\\' => {
                 // Escape the next character if present.
-                if let Some(escaped) = self.chars.next() {
+                self.quoted = true;
+                if let Some(escaped) = self.next_char() {
                     self.current.push(escaped);
+                    self.add_fragment(FragmentKind::Escape, self.position - 1 - escaped.len_utf8());
                 } else {
                     return Err("Trailing backslash".into());
                 }
                 Ok(ParseState::Normal)
             }
-            '\'' => Ok(ParseState::InSingleQuote),
-            '"' => Ok(ParseState::InDoubleQuote),
-            c if c.is_whitespace() => {
-                if !self.current.is_empty() {
-                    self.args.push(std::mem::take(&mut self.current));
+            '\'' => {
+                self.quoted = true;
+                self.quote_start = self.position - 1;
+                Ok(ParseState::InSingleQuote)
+            }
+            '"' => {
+                self.quoted = true;
+                self.quote_start = self.position - 1;
+                Ok(ParseState::InDoubleQuote)
+            }
+            // A `#` at the start of a word comments out the rest of the line
+            '#' if self.start == Some(self.position - 1) => {
+                self.start = None;
+                let start = self.position - 1;
+                while self.peek_char().is_some_and(|c| c != '\n') {
+                    self.next_char();
                 }
+                self.add_fragment(FragmentKind::Comment, start);
+                Ok(ParseState::Normal)
+            }
+            '$' => {
+                self.expand_parameter();
+                Ok(ParseState::Normal)
+            }
+            // A tilde at the start of a word is short for the home directory
+            '~' if self.current.is_empty()
+                && !self.quoted
+                && self
+                    .peek_char()
+                    .is_none_or(|c| c == '/' || c.is_whitespace()) =>
+            {
+                match helpers::home::get() {
+                    Some(home) => self.current.push_str(&home.to_string_lossy()),
+                    None => self.current.push(ch),
+                }
+                Ok(ParseState::Normal)
+            }
+            // The `|` of `>|` is part of the redirection
+            '|' if matches!(self.written(), ">" | "1>" | "2>") => {
+                self.current.push(ch);
+                Ok(ParseState::Normal)
+            }
+            // The `&` of `2>&1`, `>&2` or `<&0` is part of the redirection
+            '&' if self.written().ends_with(['>', '<']) => {
+                self.current.push(ch);
+                Ok(ParseState::Normal)
+            }
+            // A semicolon or a newline separates commands, an ampersand runs the command before it
+            // in the background, and a pipe connects two commands. `&&` and `||` run the command
+            // after them depending on how the one before went. Each is a token of its own.
+            // A newline before the command, or after a `|`, `&&` or `||`, only goes on to the
+            // rest of it
+            '\n' if self.current.is_empty()
+                && !self.quoted
+                && (self.args.is_empty() || self.continues()) =>
+            {
+                self.start = None;
+                Ok(ParseState::Normal)
+            }
+            ';' | '\n' | '&' | '|' => {
+                let start = self.position - 1;
+                if self.current.is_empty() && !self.quoted {
+                    self.start = None;
+                } else {
+                    self.finish_token(start, None);
+                }
+                let mut operator = String::from(if ch == '\n' { ';' } else { ch });
+                if matches!(ch, '&' | '|') && self.peek_char() == Some(ch) {
+                    self.next_char();
+                    operator.push(ch);
+                }
+                self.args.push(Token {
+                    value: operator,
+                    span: start..self.position,
+                    quoted: false,
+                    open_quote: None,
+                });
+                Ok(ParseState::Normal)
+            }
+            c if c.is_whitespace() => {
+                self.finish_token(self.position - c.len_utf8(), None);
                 Ok(ParseState::Normal)
             }
             _ => {
@@ -89,10 +505,18 @@ impl<'a> Parser<'a> {
         }
     }
 
+    /// Whether the last word is an operator that needs a command after it
+    fn continues(&self) -> bool {
+        self.args
+            .last()
+            .is_some_and(|token| matches!(token.plain(self.input), Some("|" | "&&" | "||")))
+    }
+
     /// Handles a character in the InSingleQuote state.
     /// Returns the new state after processing the character.
     fn handle_in_single_quote(&mut self, ch: char) -> ParseState {
         if ch == '\'' {
+            self.add_fragment(FragmentKind::Quoted, self.quote_start);
             ParseState::Normal
         } else {
             self.current.push(ch);
@@ -104,13 +528,17 @@ impl<'a> Parser<'a> {
     /// Returns the new state after processing the character.
     fn handle_in_double_quote(&mut self, ch: char) -> Result<ParseState, String> {
         match ch {
-            '"' => Ok(ParseState::Normal),
+            '"' => {
+                self.add_fragment(FragmentKind::Quoted, self.quote_start);
+                Ok(ParseState::Normal)
+            }
             '\\' => {
                 // Only escape certain characters within double quotes.
-                if let Some(&next_ch) = self.chars.peek() {
+                if let Some(next_ch) = self.peek_char() {
                     match next_ch {
                         '\\' | '"' | '$' | '\n' => {
-                            self.current.push(self.chars.next().unwrap());
+                            let escaped = self.next_char().unwrap();
+                            self.current.push(escaped);
                         }
                         _ => {
                             self.current.push('\\');
@@ -121,39 +549,129 @@ impl<'a> Parser<'a> {
                     Err("Trailing backslash in double quotes".into())
                 }
             }
+            '$' => {
+                self.expand_parameter();
+                Ok(ParseState::InDoubleQuote)
+            }
             _ => {
                 self.current.push(ch);
                 Ok(ParseState::InDoubleQuote)
             }
         }
     }
+
+    /// Expands the variable following a `$` (either `$NAME`, `$1` or `${NAME}`) into the current token.
+    /// A `$` that isn't followed by a variable name is kept as-is.
+    fn expand_parameter(&mut self) {
+        let start = self.position - 1;
+        let name = match self.peek_char() {
+            Some('{') => {
+                self.next_char();
+                let mut name = String::new();
+                while let Some(ch) = self.next_char() {
+                    if ch == '}' {
+                        break;
+                    }
+                    name.push(ch);
+                }
+                name
+            }
+            Some(c) if c.is_alphabetic() || c == '_' => {
+                let mut name = String::new();
+                while let Some(c) = self.peek_char() {
+                    if !(c.is_alphanumeric() || c == '_') {
+                        break;
+                    }
+                    name.push(c);
+                    self.next_char();
+                }
+                name
+            }
+            // Positional parameters above 9 need braces, as do other names of more than one
+            // character
+            Some(c)
+                if c.is_ascii_digit() || matches!(c, '@' | '*' | '#' | '?' | '$' | '!' | '-') =>
+            {
+                self.next_char();
+                c.to_string()
+            }
+            _ => {
+                self.current.push('$');
+                return;
+            }
+        };
+        self.add_fragment(FragmentKind::Variable, start);
+        if name == "@" {
+            self.expand_positional(start);
+            return;
+        }
+        match helpers::vars::get(&name) {
+            Some(value) => self.current.push_str(&value),
+            None if self.nounset && self.unbound.is_none() => self.unbound = Some(name),
+            None => {}
+        }
+    }
+
+    /// Expands `$@` into the positional parameters, each one a word of its own, even in double
+    /// quotes. Without any positional parameters, `"$@"` makes no word at all.
+    fn expand_positional(&mut self, start: usize) {
+        let values = helpers::vars::positional();
+        if values.is_empty() && self.current.is_empty() {
+            self.quoted = false;
+            return;
+        }
+        let quoted = self.quoted;
+        for (i, value) in values.iter().enumerate() {
+            if i > 0 {
+                self.finish_token(self.position, None);
+                self.start = Some(start);
+                self.quoted = quoted;
+            }
+            self.current.push_str(value);
+        }
+    }
 }
 
 // ----------------
 // HELPER FUNCTIONS
 // ----------------
 
+/// Whether the word starts a function definition, like `name()`
+fn is_function_header(word: &str) -> bool {
+    word.strip_suffix("()").is_some_and(|name| {
+        !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | ':'))
+    })
+}
+
 /// Given a vector of tokens, extracts redirection targets and returns a tuple:
-/// (remaining arguments, stdout target, stderr target)
-fn extract_redirection(
-    tokens: Vec<String>,
-) -> (Vec<String>, Option<(String, bool)>, Option<(String, bool)>) {
+/// (remaining arguments, stdout target, stderr target).
+/// Only plain tokens are treated as redirection operators, not quoted or expanded ones.
+fn extract_redirection(input: &str, tokens: Vec<Token>) -> Parsed {
     let mut args = Vec::new();
-    let mut stdout_target: Option<(String, bool)> = None;
-    let mut stderr_target: Option<(String, bool)> = None;
+    let mut stdout_target: Option<Target> = None;
+    let mut stderr_target: Option<Target> = None;
 
-    let mut iter = tokens.iter();
-    while let Some(token) = iter.next() {
+    let mut iter = tokens
+        .into_iter()
+        .map(|t| (t.plain(input).is_some(), t.value));
+    while let Some((plain, token)) = iter.next() {
         match token.as_str() {
-            ">" | ">>" | "1>" | "1>>" | "2>" | "2>>" => {
-                let append = token.ends_with(">>"); // Check if we're appending
-                if let Some(filename) = iter.next() {
-                    match token.as_str() {
-                        ">" | ">>" | "1>" | "1>>" => {
-                            stdout_target = Some((filename.clone(), append))
-                        }
-                        "2>" | "2>>" => stderr_target = Some((filename.clone(), append)),
-                        _ => {}
+            ">" | ">>" | ">|" | "1>" | "1>>" | "1>|" | "2>" | "2>>" | "2>|" if plain => {
+                let mode = if token.ends_with(">>") {
+                    OpenMode::Append
+                } else if token.ends_with(">|") {
+                    OpenMode::Clobber
+                } else {
+                    OpenMode::Truncate
+                };
+                if let Some((_, filename)) = iter.next() {
+                    if token.starts_with('2') {
+                        stderr_target = Some((filename, mode));
+                    } else {
+                        stdout_target = Some((filename, mode));
                     }
                 } else {
                     // Handle error: No filename provided after redirection operator.
@@ -164,13 +682,22 @@ fn extract_redirection(
                     break;
                 }
             }
-            _ => args.push(token.clone()),
+            _ => args.push(token),
         }
     }
 
     (args, stdout_target, stderr_target)
 }
 
+/// Quotes a word for the shell to read back as the same word, if it needs to be
+pub fn quote(word: &str) -> String {
+    let plain = |c: char| c.is_ascii_alphanumeric() || "_-+=./:,@%^".contains(c);
+    if !word.is_empty() && word.chars().all(plain) {
+        return word.to_owned();
+    }
+    format!("'{}'", word.replace('\'', "'\\''"))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -288,6 +815,324 @@ mod tests {
         assert_eq!(actual.0, expected);
     }
 
+    #[test]
+    fn test_parse_input_with_empty_quotes() {
+        let input = "command '' arg2";
+        let actual = Parser::parse(input).unwrap();
+        let expected = vec!["command", "", "arg2"];
+        assert_eq!(actual.0, expected);
+    }
+
+    #[test]
+    fn test_parse_input_with_variables() {
+        std::env::set_var("PARSER_TEST_VAR", "value");
+        let input = "command $PARSER_TEST_VAR \"${PARSER_TEST_VAR}s\" '$PARSER_TEST_VAR' \\$PARSER_TEST_VAR";
+        let actual = Parser::parse(input).unwrap();
+        let expected = vec![
+            "command",
+            "value",
+            "values",
+            "$PARSER_TEST_VAR",
+            "$PARSER_TEST_VAR",
+        ];
+        assert_eq!(actual.0, expected);
+    }
+
+    #[test]
+    fn test_parse_input_with_unset_variable() {
+        let input = "command $PARSER_TEST_UNSET arg2";
+        let actual = Parser::parse(input).unwrap();
+        let expected = vec!["command", "arg2"];
+        assert_eq!(actual.0, expected);
+    }
+
+    #[test]
+    fn test_parse_input_with_quoted_redirection() {
+        let input = "echo '>' file";
+        let actual = Parser::parse(input).unwrap();
+        assert_eq!(actual.0, vec!["echo", ">", "file"]);
+        assert_eq!(actual.1, None);
+    }
+
+    #[test]
+    fn test_tokenize_spans() {
+        let input = "cat  'my file' src/ma";
+        let tokens = Parser::tokenize(input);
+        let spans: Vec<_> = tokens.iter().map(|t| t.span.clone()).collect();
+        assert_eq!(spans, vec![0..3, 5..14, 15..21]);
+        assert_eq!(tokens[1].value, "my file");
+    }
+
+    #[test]
+    fn test_tokenize_partial_input() {
+        let tokens = Parser::tokenize("cat \"my fi");
+        assert_eq!(tokens[1].value, "my fi");
+        assert_eq!(tokens[1].open_quote, Some('"'));
+        assert_eq!(tokens[1].span, 4..10);
+
+        let tokens = Parser::tokenize("cat my\\");
+        assert_eq!(tokens[1].value, "my");
+    }
+
+    #[test]
+    fn test_tokenize_semicolons() {
+        let tokens = Parser::tokenize("a;b ';' c\\;");
+        let values: Vec<_> = tokens.iter().map(|t| t.value.as_str()).collect();
+        assert_eq!(values, vec!["a", ";", "b", ";", "c;"]);
+        assert!(tokens[3].quoted);
+    }
+
+    #[test]
+    fn test_split_commands() {
+        let input = "a 1; b 'x;y'; f() { c; d; }; e";
+        let commands = Parser::split_commands(input);
+        assert_eq!(commands, vec!["a 1", " b 'x;y'", " f() { c; d; }", " e"]);
+    }
+
+    #[test]
+    fn test_expanded_separators() {
+        helpers::vars::set("PARSER_TEST_SEPARATOR", ";");
+        assert_eq!(
+            Parser::split_commands("echo a $PARSER_TEST_SEPARATOR echo b; echo c"),
+            vec!["echo a $PARSER_TEST_SEPARATOR echo b", " echo c"]
+        );
+        helpers::vars::set("PARSER_TEST_SEPARATOR", "{");
+        assert_eq!(
+            Parser::parse_function("f() $PARSER_TEST_SEPARATOR echo; }"),
+            None
+        );
+        helpers::vars::unset("PARSER_TEST_SEPARATOR");
+    }
+
+    #[test]
+    fn test_parse_function() {
+        let (name, body) = Parser::parse_function("_svc() { echo hi; echo there; }").unwrap();
+        assert_eq!(name, "_svc");
+        assert_eq!(body, "echo hi; echo there;");
+        assert_eq!(Parser::parse_function("echo { hi }"), None);
+    }
+
+    #[test]
+    fn test_parse_positional_parameters() {
+        let previous = helpers::vars::set_positional(vec!["first".into()]);
+        let actual = Parser::parse("echo $1 ${1} $2").unwrap();
+        assert_eq!(actual.0, vec!["echo", "first", "first"]);
+        helpers::vars::set_positional(previous);
+    }
+
+    #[test]
+    fn test_parse_comments() {
+        let actual = Parser::parse("echo a#b # the rest; echo c").unwrap();
+        assert_eq!(actual.0, vec!["echo", "a#b"]);
+        assert_eq!(
+            Parser::split_commands("echo a # b; c"),
+            vec!["echo a # b; c"]
+        );
+    }
+
+    #[test]
+    fn test_split_lines() {
+        assert_eq!(
+            Parser::split_commands("echo a # b\necho c \\\n  d\n"),
+            vec!["echo a # b", "echo c \\\n  d"]
+        );
+        assert_eq!(
+            Parser::parse("echo c \\\n  d").unwrap().0,
+            vec!["echo", "c", "d"]
+        );
+        assert_eq!(
+            Parser::parse("echo 'a\nb'").unwrap().0,
+            vec!["echo", "a\nb"]
+        );
+    }
+
+    #[test]
+    fn test_background_and_pipelines() {
+        let commands = Parser::split_commands("sleep 5& echo a | tr a b;echo '&'");
+        assert_eq!(commands, vec!["sleep 5&", " echo a | tr a b", "echo '&'"]);
+        assert_eq!(Parser::background(commands[0]), ("sleep 5", true));
+        assert_eq!(Parser::background(commands[2]), ("echo '&'", false));
+        assert_eq!(
+            Parser::split_pipeline(commands[1]),
+            vec![" echo a ", " tr a b"]
+        );
+        assert_eq!(Parser::split_pipeline("echo 'a|b'"), vec!["echo 'a|b'"]);
+    }
+
+    #[test]
+    fn test_duplicating_redirections() {
+        let commands = Parser::split_commands("echo hi 2>&1");
+        assert_eq!(commands, vec!["echo hi 2>&1"]);
+        assert_eq!(Parser::background(commands[0]), ("echo hi 2>&1", false));
+        let words: Vec<String> = Parser::tokenize(commands[0])
+            .into_iter()
+            .map(|token| token.value)
+            .collect();
+        assert_eq!(words, vec!["echo", "hi", "2>&1"]);
+
+        let commands = Parser::split_commands("cmd >&2; cmd <&0 &");
+        assert_eq!(commands, vec!["cmd >&2", " cmd <&0 &"]);
+        assert_eq!(Parser::background(commands[1]), (" cmd <&0 ", true));
+        assert_eq!(Parser::background("cmd '>'&"), ("cmd '>'", true));
+    }
+
+    #[test]
+    fn test_negation() {
+        assert_eq!(Parser::negation(" ! grep -q a"), (" grep -q a", true));
+        assert_eq!(Parser::negation("!true"), ("!true", false));
+        assert_eq!(Parser::negation("'!' true"), ("'!' true", false));
+        assert_eq!(Parser::negation("echo !"), ("echo !", false));
+    }
+
+    #[test]
+    fn test_expanded_pipes_and_ampersands() {
+        helpers::vars::set("PARSER_TEST_PIPE", "|");
+        assert_eq!(
+            Parser::split_pipeline("echo c $PARSER_TEST_PIPE tr c d | cat"),
+            vec!["echo c $PARSER_TEST_PIPE tr c d ", " cat"]
+        );
+        helpers::vars::set("PARSER_TEST_PIPE", "&");
+        assert_eq!(
+            Parser::background("sleep 1 $PARSER_TEST_PIPE"),
+            ("sleep 1 $PARSER_TEST_PIPE", false)
+        );
+        helpers::vars::set("PARSER_TEST_PIPE", ">");
+        assert_eq!(
+            Parser::split_commands("echo $PARSER_TEST_PIPE& echo b"),
+            vec!["echo $PARSER_TEST_PIPE&", " echo b"]
+        );
+        helpers::vars::unset("PARSER_TEST_PIPE");
+    }
+
+    #[test]
+    fn test_and_or_lists() {
+        assert_eq!(
+            Parser::split_commands("make &&\n  make install\necho done"),
+            vec!["make &&\n  make install", "echo done"]
+        );
+        let commands = Parser::split_commands("make && make install || echo failed & ls");
+        assert_eq!(
+            commands,
+            vec!["make && make install || echo failed &", " ls"]
+        );
+        assert_eq!(
+            Parser::split_list("a|b&&c||'d||e'"),
+            vec![(None, "a|b"), (Some("&&"), "c"), (Some("||"), "'d||e'")]
+        );
+    }
+
+    #[test]
+    fn test_expanded_and_or_operators() {
+        helpers::vars::set("PARSER_TEST_AND", "&&");
+        assert_eq!(
+            Parser::split_list("echo 1 $PARSER_TEST_AND echo 2 || echo 3"),
+            vec![
+                (None, "echo 1 $PARSER_TEST_AND echo 2 "),
+                (Some("||"), " echo 3")
+            ]
+        );
+        helpers::vars::set("PARSER_TEST_AND", "!");
+        assert_eq!(
+            Parser::negation("$PARSER_TEST_AND true"),
+            ("$PARSER_TEST_AND true", false)
+        );
+        helpers::vars::set("PARSER_TEST_AND", ">");
+        let words: Vec<String> = Parser::tokenize("echo $PARSER_TEST_AND| cat")
+            .into_iter()
+            .map(|token| token.value)
+            .collect();
+        assert_eq!(words, vec!["echo", ">", "|", "cat"]);
+        helpers::vars::unset("PARSER_TEST_AND");
+    }
+
+    #[test]
+    fn test_parse_redirection_modes() {
+        let (args, out, err) = Parser::parse("echo a >| out 2>> err").unwrap();
+        assert_eq!(args, vec!["echo", "a"]);
+        assert_eq!(out, Some((String::from("out"), OpenMode::Clobber)));
+        assert_eq!(err, Some((String::from("err"), OpenMode::Append)));
+    }
+
+    #[test]
+    fn test_expanded_operators_are_words() {
+        helpers::vars::set("PARSER_TEST_OPERATOR", ">");
+        let (args, out, _) = Parser::parse("echo $PARSER_TEST_OPERATOR out1").unwrap();
+        assert_eq!(args, vec!["echo", ">", "out1"]);
+        assert_eq!(out, None);
+        let (args, out, _) = Parser::parse("echo '>' out1 > \"$PARSER_TEST_OPERATOR\"").unwrap();
+        assert_eq!(args, vec!["echo", ">", "out1"]);
+        assert_eq!(out, Some((String::from(">"), OpenMode::Truncate)));
+        helpers::vars::unset("PARSER_TEST_OPERATOR");
+    }
+
+    #[test]
+    fn test_parse_special_parameters() {
+        let input = "echo \"$@\" $# x$*";
+        helpers::vars::set_positional(vec![String::from("a"), String::from("b c")]);
+        let expected = vec!["echo", "a", "b c", "2", "xa b c"];
+        assert_eq!(Parser::parse(input).unwrap().0, expected);
+
+        helpers::vars::set_positional(Vec::new());
+        let expected = vec!["echo", "0", "x"];
+        assert_eq!(Parser::parse(input).unwrap().0, expected);
+    }
+
+    #[test]
+    fn test_is_complete() {
+        assert!(Parser::is_complete("echo a\n"));
+        assert!(Parser::is_complete("echo a \\\\\n"));
+        assert!(!Parser::is_complete("echo a \\\n"));
+        assert!(!Parser::is_complete("echo 'a\n"));
+        assert!(!Parser::is_complete("f() {\n  echo a\n"));
+        assert!(Parser::is_complete("f() {\n  echo a\n}\n"));
+        assert!(!Parser::is_complete("make &&\n"));
+        assert!(!Parser::is_complete("ls |\n"));
+        assert!(Parser::is_complete("ls |\n  wc -l\n"));
+        helpers::vars::set("PARSER_TEST_PIPE", "|");
+        assert!(Parser::is_complete("echo $PARSER_TEST_PIPE\n"));
+    }
+
+    #[test]
+    fn test_parse_nounset() {
+        let input = "echo $PARSER_TEST_UNSET";
+        assert!(Parser::parse(input).is_ok());
+        assert_eq!(
+            Parser::parse_with_options(input, ParseOptions { nounset: true }),
+            Err(String::from("PARSER_TEST_UNSET: unbound variable"))
+        );
+    }
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(quote("echo"), "echo");
+        assert_eq!(quote("a b"), "'a b'");
+        assert_eq!(quote(""), "''");
+        assert_eq!(quote("it's"), "'it'\\''s'");
+        assert_eq!(quote("$HOME"), "'$HOME'");
+    }
+
+    #[test]
+    fn test_fragments() {
+        use FragmentKind::*;
+        let kinds = |input| {
+            Parser::fragments(input)
+                .into_iter()
+                .map(|f| (f.kind, f.span))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            kinds(r#"echo "a $HOME" \x # c"#),
+            vec![
+                (Quoted, 5..14),
+                (Variable, 8..13),
+                (Escape, 15..17),
+                (Comment, 18..21)
+            ]
+        );
+        // An unterminated quote runs to the end of the input
+        assert_eq!(kinds("echo 'abc"), vec![(Quoted, 5..9)]);
+    }
+
     // #[test]
     // fn test_parse_redirection_with_both() {
     //     let input = "ls -l > out.txt 2> err.txt";
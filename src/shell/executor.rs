@@ -1,47 +1,120 @@
-use crate::commands::Command;
+use super::Condition;
+use crate::{
+    commands::Command,
+    helpers::vars,
+    parser::{self, OpenMode, Target},
+};
 
-use std::io;
+use std::{cell::RefCell, fs::File, io, rc::Rc};
 
 impl super::Shell {
-    /// Handles command execution
+    /// Handles command execution, and records the exit status of the command
     pub(super) fn execute_command(
         &mut self,
         args: Vec<String>,
-        out_target: Option<(String, bool)>,
-        err_target: Option<(String, bool)>,
+        out_target: Option<Target>,
+        err_target: Option<Target>,
     ) -> io::Result<()> {
+        // Variable assignments in front of the command
+        let (assignments, args) = parse_assignments(args);
+        if !assignments.is_empty() {
+            if args.is_empty() {
+                for (name, value) in assignments {
+                    match value {
+                        Value::Scalar(value) => vars::set(&name, &value),
+                        Value::Array(values) => vars::set_array(&name, values),
+                    }
+                }
+                self.status = 0;
+                return Ok(());
+            }
+
+            // Assignments only last for the command they are in front of, which gets them in
+            // its environment
+            let mut saved = Vec::new();
+            for (name, value) in assignments {
+                if let Value::Scalar(value) = value {
+                    saved.push((name.clone(), vars::get(&name), vars::is_exported(&name)));
+                    vars::set(&name, &value);
+                    vars::export(&name, true);
+                }
+            }
+            let result = self.execute_command(args, out_target, err_target);
+            for (name, value, exported) in saved {
+                match value {
+                    Some(value) => vars::set(&name, &value),
+                    None => vars::unset(&name),
+                }
+                vars::export(&name, exported);
+            }
+            return result;
+        }
+
+        // Functions take precedence over builtins and programs.
+        // Their output isn't redirected, only the output of the commands inside.
+        if let Some(body) = args.first().and_then(|name| self.functions.get(name)) {
+            return self.call_function(&body.clone(), args);
+        }
+
+        // Outside of a command substitution, programs run as jobs with the terminal to themselves
+        let command = args.first().map(|name| name.parse::<Command>());
+        if let (Some(Ok(Command::Program(_))), None) = (&command, &self.capture) {
+            let text = args.join(" ");
+            return self.run_job(vec![(args, out_target, err_target)], &text, false);
+        }
+
+        // Open the redirections first, since a command whose file can't be opened doesn't run
+        let (out_file, err_file) = match (
+            out_target
+                .as_ref()
+                .map(|target| self.open_target(target))
+                .transpose(),
+            err_target
+                .as_ref()
+                .map(|target| self.open_target(target))
+                .transpose(),
+        ) {
+            (Ok(out_file), Ok(err_file)) => (out_file, err_file),
+            (Err(e), _) | (_, Err(e)) => {
+                eprintln!("{}{}", self.error_prefix(), e);
+                self.status = 1;
+                return Ok(());
+            }
+        };
+
         // Decide the writer for stdout.
-        let mut out_writer: Box<dyn io::Write> = if let Some((filename, append)) = out_target {
-            let file = std::fs::OpenOptions::new()
-                .write(true)
-                .append(append)
-                .create(true)
-                .open(filename)?;
+        let mut out_writer: Box<dyn io::Write> = if let Some(file) = out_file {
             Box::new(io::BufWriter::new(file))
+        } else if let Some(buffer) = &self.capture {
+            Box::new(Capture(buffer.clone()))
         } else {
             Box::new(io::BufWriter::new(io::stdout()))
         };
 
         // Decide the writer for stderr.
-        let mut err_writer: Box<dyn io::Write> = if let Some((filename, append)) = err_target {
-            let file = std::fs::OpenOptions::new()
-                .write(true)
-                .append(append)
-                .create(true)
-                .open(filename)?;
+        let mut err_writer: Box<dyn io::Write> = if let Some(file) = err_file {
             Box::new(io::BufWriter::new(file))
+        } else if self.location.is_some() {
+            // Errors say which file and line they come from
+            Box::new(Prefixed {
+                prefix: self.error_prefix(),
+                inner: io::BufWriter::new(io::stderr()),
+                line_start: true,
+            })
         } else {
             Box::new(io::BufWriter::new(io::stderr()))
         };
 
-        // Extract the command name from the vector
-        if let Some(command) = args.get(0) {
-            // Try to parse the command into a Command enum
-            return match command.parse::<Command>() {
-                Ok(cmd) => Ok(cmd.execute(args, &mut out_writer, &mut err_writer)?),
+        // Act on the command, which was parsed into a Command enum above
+        if let Some(command) = command {
+            return match command {
+                Ok(cmd) => {
+                    self.status = cmd.execute(args, self, &mut out_writer, &mut err_writer)?;
+                    Ok(())
+                }
                 Err(_) => Err(io::Error::new(
                     io::ErrorKind::InvalidInput,
-                    format!("Unexpected command! {command}"),
+                    format!("Unexpected command! {}", args[0]),
                 )),
             };
         }
@@ -50,3 +123,355 @@ impl super::Shell {
         Ok(()) // Return and continue on
     }
 }
+
+impl super::Shell {
+    /// Runs the body of a function, with the arguments as the positional parameters
+    pub(super) fn call_function(&mut self, body: &str, args: Vec<String>) -> io::Result<()> {
+        let previous = vars::set_positional(args.into_iter().skip(1).collect());
+        let result = self.run_call(|shell| shell.eval(body));
+        vars::set_positional(previous);
+        result
+    }
+
+    /// Runs a function or a sourced file, which `return` ends early. The `RETURN` trap runs
+    /// once it's done, unless it ran `exit`.
+    pub(super) fn run_call(
+        &mut self,
+        run: impl FnOnce(&mut Self) -> io::Result<()>,
+    ) -> io::Result<()> {
+        self.calls += 1;
+        let result = run(self);
+        self.calls -= 1;
+        self.returning = false;
+        if !self.exiting {
+            self.run_trap(Condition::Return)?;
+        }
+        result
+    }
+
+    /// Asks the function or sourced file that's running to return once the command is done.
+    /// Returns `false` if there is none.
+    pub fn request_return(&mut self) -> bool {
+        self.returning = self.calls > 0;
+        self.returning
+    }
+
+    /// Runs the commands and returns what they wrote to stdout without the trailing newlines,
+    /// like a command substitution `$(commands)`
+    pub(super) fn capture_output(&mut self, input: &str) -> io::Result<String> {
+        let output = self.capture(|shell| shell.eval(input))?;
+        let output = String::from_utf8_lossy(&output).into_owned();
+        Ok(output.trim_end_matches('\n').to_owned())
+    }
+
+    /// Runs a command and returns what it wrote to stdout, for a stage of a pipeline that runs
+    /// in the shell itself
+    pub(super) fn capture_command(
+        &mut self,
+        args: Vec<String>,
+        out_target: Option<Target>,
+        err_target: Option<Target>,
+    ) -> io::Result<Vec<u8>> {
+        self.capture(|shell| shell.execute_command(args, out_target, err_target))
+    }
+
+    /// Runs the closure with stdout going to a buffer, and returns what was written to it.
+    /// Like a subshell, an `exit` in it only ends the closure, not the shell.
+    pub(super) fn capture(
+        &mut self,
+        run: impl FnOnce(&mut Self) -> io::Result<()>,
+    ) -> io::Result<Vec<u8>> {
+        let buffer = Rc::new(RefCell::new(Vec::new()));
+        let previous = self.capture.replace(buffer.clone());
+        let exiting = self.exiting;
+        let result = run(self);
+        self.capture = previous;
+        self.exiting = exiting;
+        result?;
+        Ok(buffer.take())
+    }
+
+    /// Opens the file a redirection writes to. With `set -C`, `>` won't overwrite a regular file
+    /// that already exists, though `>|` will.
+    pub(super) fn open_target(&self, (filename, mode): &Target) -> Result<File, String> {
+        let exists = std::fs::metadata(filename).is_ok_and(|metadata| metadata.is_file());
+        if *mode == OpenMode::Truncate && exists && self.is_set("noclobber") {
+            return Err(format!("{}: cannot overwrite existing file", filename));
+        }
+        let append = *mode == OpenMode::Append;
+        std::fs::OpenOptions::new()
+            .write(true)
+            .append(append)
+            .truncate(!append)
+            .create(true)
+            .open(filename)
+            .map_err(|e| format!("{}: {}", filename, e))
+    }
+
+    /// Prints a command as it runs, for `set -x`. It comes after the expanded `PS4`, with the
+    /// words quoted so that they could be run again.
+    pub(super) fn trace(&mut self, args: &[String]) {
+        let ps4 = vars::get("PS4").unwrap_or_else(|| String::from("+ "));
+        let words: Vec<String> = args
+            .iter()
+            .map(|arg| match arg.split_once('=') {
+                Some((name, value)) if is_assignment(arg) => {
+                    format!("{}={}", name, parser::quote(value))
+                }
+                _ => parser::quote(arg),
+            })
+            .collect();
+        eprintln!("{}{}", self.expand_prompt(&ps4), words.join(" "));
+    }
+
+    /// The program a command runs, if it runs one rather than a function or builtin.
+    /// Otherwise the arguments are handed back.
+    pub(super) fn resolve_program(&self, args: Vec<String>) -> Result<ProgramCall, Vec<String>> {
+        let words = args.iter().skip_while(|arg| is_assignment(arg));
+        let path = match words.clone().next() {
+            Some(name) if !self.functions.contains_key(name) => match name.parse::<Command>() {
+                Ok(Command::Program(program)) => program.path().to_owned(),
+                _ => return Err(args),
+            },
+            _ => return Err(args),
+        };
+        let (assignments, args) = parse_assignments(args);
+        let env = assignments
+            .into_iter()
+            .filter_map(|(name, value)| match value {
+                Value::Scalar(value) => Some((name, value)),
+                Value::Array(_) => None,
+            })
+            .collect();
+        Ok(ProgramCall { path, env, args })
+    }
+}
+
+/// A command that runs a program, from [`resolve_program`](super::Shell::resolve_program)
+pub(super) struct ProgramCall {
+    /// The path to the program
+    pub path: String,
+    /// The variables assigned in front of the command, for the program's environment
+    pub env: Vec<(String, String)>,
+    /// The arguments, starting with the name the program was called by
+    pub args: Vec<String>,
+}
+
+/// A writer that collects the output of a command substitution
+struct Capture(Rc<RefCell<Vec<u8>>>);
+
+impl io::Write for Capture {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A writer that starts every line with a prefix, like the file and line of the command
+struct Prefixed<W> {
+    prefix: String,
+    inner: W,
+    /// Whether the next byte written starts a line
+    line_start: bool,
+}
+
+impl<W: io::Write> io::Write for Prefixed<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for line in buf.split_inclusive(|byte| *byte == b'\n') {
+            if self.line_start {
+                self.inner.write_all(self.prefix.as_bytes())?;
+            }
+            self.inner.write_all(line)?;
+            self.line_start = line.ends_with(b"\n");
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// The value of a variable assignment
+enum Value {
+    Scalar(String),
+    Array(Vec<String>),
+}
+
+/// Whether the argument is a variable assignment, like `NAME=value`
+pub(super) fn is_assignment(arg: &str) -> bool {
+    arg.split_once('=')
+        .is_some_and(|(name, _)| vars::is_name(name))
+}
+
+/// Splits off the variable assignments at the start of the arguments, returning them along
+/// with the rest of the arguments. Arrays are assigned with `NAME=(a b c)`, where the elements
+/// are separate arguments.
+fn parse_assignments(args: Vec<String>) -> (Vec<(String, Value)>, Vec<String>) {
+    let mut assignments = Vec::new();
+    let mut args = args.into_iter().peekable();
+    while let Some(arg) = args.next_if(|arg| is_assignment(arg)) {
+        let (name, value) = arg.split_once('=').unwrap_or_default();
+
+        let Some(first) = value.strip_prefix('(') else {
+            assignments.push((name.to_owned(), Value::Scalar(value.to_owned())));
+            continue;
+        };
+        let mut elements = Vec::new();
+        let mut element = first.to_owned();
+        loop {
+            let last = element.ends_with(')');
+            let element_value = element.strip_suffix(')').unwrap_or(&element);
+            if !element_value.is_empty() {
+                elements.push(element_value.to_owned());
+            }
+            if last {
+                break;
+            }
+            match args.next() {
+                Some(next) => element = next,
+                None => break,
+            }
+        }
+        assignments.push((name.to_owned(), Value::Array(elements)));
+    }
+    (assignments, args.collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Shell, CWD_LOCK};
+    use crate::helpers;
+
+    #[test]
+    fn test_parameter_expansion() {
+        let mut shell = Shell::default();
+        let (output, status) =
+            shell.run_captured("NAME=world; echo $NAME ${NAME}s \"$NAME\" '$NAME' $ $UNSET_NAME.");
+        assert_eq!(output, "world worlds world $NAME $ .\n");
+        assert_eq!(status, 0);
+    }
+
+    #[test]
+    fn test_tilde_expansion() {
+        let _lock = CWD_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let home = helpers::home::get().expect("HOME should be set");
+        let home = home.to_string_lossy();
+        let mut shell = Shell::default();
+        let (output, _) = shell.run_captured("echo ~ ~/bin '~' \"~\" a~ ~b");
+        assert_eq!(output, format!("{home} {home}/bin ~ ~ a~ ~b\n"));
+    }
+
+    #[test]
+    fn test_cd_home() {
+        let _lock = CWD_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let cwd = std::env::current_dir().unwrap();
+        let home = helpers::home::get().expect("HOME should be set");
+        let home = home.canonicalize().unwrap();
+
+        let mut shell = Shell::default();
+        let (_, status) = shell.run_captured("cd ~");
+        assert_eq!(status, 0);
+        assert_eq!(std::env::current_dir().unwrap(), home);
+
+        let (_, status) = shell.run_captured("cd ~/");
+        assert_eq!(status, 0);
+        assert_eq!(std::env::current_dir().unwrap(), home);
+
+        std::env::set_current_dir(cwd).unwrap();
+    }
+
+    #[test]
+    fn test_only_exported_variables_reach_programs() {
+        let mut shell = Shell::default();
+        let (output, _) = shell.run_captured(
+            "TEST_UNEXPORTED=1; sh -c 'echo [$TEST_UNEXPORTED]'; echo $TEST_UNEXPORTED",
+        );
+        assert_eq!(output, "[]\n1\n");
+
+        let (output, _) = shell.run_captured("TEST_PREFIXED=2 sh -c 'echo [$TEST_PREFIXED]'");
+        assert_eq!(output, "[2]\n");
+        assert_eq!(helpers::vars::get("TEST_PREFIXED"), None);
+
+        let (output, _) = shell.run_captured(
+            "export TEST_EXPORTED=3; sh -c 'echo [$TEST_EXPORTED]'; export -n TEST_EXPORTED; \
+             sh -c 'echo [$TEST_EXPORTED]'",
+        );
+        assert_eq!(output, "[3]\n[]\n");
+        helpers::vars::unset("TEST_EXPORTED");
+    }
+
+    #[test]
+    fn test_redirections_replace_the_file() {
+        let path = std::env::temp_dir().join(format!("shell-truncate-{}", std::process::id()));
+        let file = path.display();
+        let mut shell = Shell::default();
+
+        shell.run_captured(&format!("echo a longer line > {file}; echo x > {file}"));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "x\n");
+
+        shell.run_captured(&format!(
+            "sh -c 'echo a longer line' > {file}; sh -c 'echo y' > {file}"
+        ));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "y\n");
+
+        shell.run_captured(&format!("echo z >> {file}"));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "y\nz\n");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_cd_cdpath() {
+        let _lock = CWD_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let cwd = std::env::current_dir().unwrap();
+        let base = std::env::temp_dir().join(format!("shell-cdpath-{}", std::process::id()));
+        std::fs::create_dir_all(base.join("project")).unwrap();
+        let base = base.canonicalize().unwrap();
+
+        // `CDPATH` works without being exported, like any other shell variable
+        let mut shell = Shell::default();
+        let (output, status) = shell.run_captured(&format!(
+            "CDPATH=/nonexistent:{}; cd project",
+            base.display()
+        ));
+        assert_eq!(output, format!("{}/project\n", base.display()));
+        assert_eq!(status, 0);
+        assert_eq!(std::env::current_dir().unwrap(), base.join("project"));
+
+        std::env::set_current_dir(cwd).unwrap();
+        std::fs::remove_dir_all(base).unwrap();
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_cd_previous_and_symbolic_links() {
+        let _lock = CWD_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let cwd = std::env::current_dir().unwrap();
+        let base = std::env::temp_dir().join(format!("shell-cd-links-{}", std::process::id()));
+        std::fs::create_dir_all(base.join("real")).unwrap();
+        let base = base.canonicalize().unwrap();
+        std::os::unix::fs::symlink("real", base.join("link")).unwrap();
+        let dir = base.display();
+
+        // `cd -` goes back to `OLDPWD`, and prints where it went
+        let mut shell = Shell::default();
+        let (output, status) = shell.run_captured(&format!("cd {dir}; cd /; cd -; pwd"));
+        assert_eq!(output, format!("{dir}\n{dir}\n"));
+        assert_eq!(status, 0);
+
+        // `-L` keeps the symbolic link in `PWD`, and `..` goes back out of it, while `-P`
+        // follows the link
+        let (output, _) = shell.run_captured("cd -L link; echo $PWD; cd ..; pwd");
+        assert_eq!(output, format!("{dir}/link\n{dir}\n"));
+        let (output, _) = shell.run_captured("cd -P link; echo $PWD; cd -P ..; pwd");
+        assert_eq!(output, format!("{dir}/real\n{dir}\n"));
+        assert_eq!(std::env::current_dir().unwrap(), base);
+
+        std::env::set_current_dir(cwd).unwrap();
+        std::fs::remove_dir_all(base).unwrap();
+    }
+}
@@ -0,0 +1,881 @@
+// Library
+use std::{
+    io::{self, Read, Write},
+    process::{ChildStdout, Stdio},
+    thread,
+    time::Duration,
+};
+
+use super::executor::ProgramCall;
+use crate::{
+    helpers::{
+        sys::{self, WaitStatus},
+        vars,
+    },
+    parser::{ParseOptions, Parsed, Parser},
+};
+
+// ----
+// JOBS
+// ----
+
+/// The state of a process in a job
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum State {
+    Running,
+    /// Stopped by the signal
+    Stopped(i32),
+    /// Exited with the status
+    Exited(i32),
+    /// Killed by the signal, and whether it dumped core
+    Signaled(i32, bool),
+}
+
+impl State {
+    /// Whether the process has finished for good
+    fn is_finished(self) -> bool {
+        matches!(self, State::Exited(_) | State::Signaled(..))
+    }
+
+    /// The exit status this state stands for, like bash's `$?`
+    fn status(self) -> i32 {
+        match self {
+            State::Running => 0,
+            State::Exited(status) => status,
+            State::Stopped(signal) | State::Signaled(signal, _) => 128 + signal,
+        }
+    }
+}
+
+impl From<WaitStatus> for State {
+    fn from(status: WaitStatus) -> Self {
+        match status {
+            WaitStatus::Exited(status) => State::Exited(status),
+            WaitStatus::Signaled(signal, core) => State::Signaled(signal, core),
+            WaitStatus::Stopped(signal) => State::Stopped(signal),
+            WaitStatus::Continued => State::Running,
+        }
+    }
+}
+
+impl std::fmt::Display for State {
+    /// The state the way `jobs` shows it
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            State::Running => write!(f, "Running"),
+            State::Stopped(signal) => write!(f, "{}", sys::signal_description(*signal)),
+            State::Exited(0) => write!(f, "Done"),
+            State::Exited(status) => write!(f, "Exit {}", status),
+            State::Signaled(signal, false) => write!(f, "{}", sys::signal_description(*signal)),
+            State::Signaled(signal, true) => {
+                write!(f, "{} (core dumped)", sys::signal_description(*signal))
+            }
+        }
+    }
+}
+
+/// A pipeline started by the shell, whose processes share a process group
+pub struct Job {
+    /// The job number, as in `%1`
+    pub id: usize,
+    /// The process group of the job, which is the ID of its first process
+    pub pgid: i32,
+    /// The processes of the pipeline, in order, with what they're doing
+    processes: Vec<(i32, State)>,
+    /// The command line that started the job
+    pub command: String,
+    /// Whether the job is running in the background, as opposed to being waited for
+    background: bool,
+    /// Whether the job is sent `SIGHUP` when the shell exits, unless `disown -h` says otherwise
+    hang_up: bool,
+}
+
+impl Job {
+    /// The state of the job as a whole: running while any process is, then stopped while any
+    /// process is, and otherwise finished the way its last process did
+    pub fn state(&self) -> State {
+        let mut states = self.processes.iter().map(|(_, state)| *state);
+        if states.clone().any(|state| state == State::Running) {
+            return State::Running;
+        }
+        if let Some(stopped) = states
+            .clone()
+            .find(|state| matches!(state, State::Stopped(_)))
+        {
+            return stopped;
+        }
+        states.next_back().unwrap_or(State::Exited(0))
+    }
+
+    /// The exit status of the job: the one of its last process, or the one of the signal that
+    /// stopped it. With `pipefail`, it's the one of the last process that failed.
+    fn status(&self, pipefail: bool) -> i32 {
+        let mut statuses = self.processes.iter().map(|(_, state)| state.status());
+        match self.state() {
+            state @ State::Stopped(_) => state.status(),
+            _ if pipefail => statuses.rfind(|status| *status != 0).unwrap_or(0),
+            _ => statuses.next_back().unwrap_or(0),
+        }
+    }
+
+    /// The IDs of the processes in the job. Stages that ran in the shell itself have none.
+    pub fn pids(&self) -> impl Iterator<Item = i32> + '_ {
+        self.processes
+            .iter()
+            .map(|(pid, _)| *pid)
+            .filter(|pid| *pid > 0)
+    }
+
+    /// The job as `jobs` lists it, like `[1]+  Running                 sleep 10 &`.
+    /// In the long format, the process group is listed as well.
+    pub fn describe(&self, marker: char, long: bool) -> String {
+        let state = self.state();
+        let ampersand = if state == State::Running && self.background {
+            " &"
+        } else {
+            ""
+        };
+        let pid = if long {
+            format!("{} ", self.pgid)
+        } else {
+            String::from(" ")
+        };
+        format!(
+            "[{}]{} {}{:<23} {}{}",
+            self.id,
+            marker,
+            pid,
+            state.to_string(),
+            self.command,
+            ampersand
+        )
+    }
+
+    /// Check on the processes that haven't finished yet, without waiting for them
+    fn update(&mut self) {
+        for (pid, state) in &mut self.processes {
+            if !state.is_finished() {
+                if let Some(status) = sys::wait(*pid, false) {
+                    *state = State::from(status);
+                }
+            }
+        }
+    }
+}
+
+/// The jobs that are running in the background or are stopped
+#[derive(Default)]
+pub struct Jobs {
+    list: Vec<Job>,
+    /// The job numbers, from the least to the most recently stopped or put in the background.
+    /// The last one is the current job `%+`, and the one before it the previous job `%-`.
+    order: Vec<usize>,
+}
+
+impl Jobs {
+    /// The jobs, by job number
+    pub fn iter(&self) -> impl Iterator<Item = &Job> {
+        self.list.iter()
+    }
+
+    /// The number of jobs
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    /// The marker `jobs` shows for the job: `+` for the current job, `-` for the previous one
+    pub fn marker(&self, id: usize) -> char {
+        match self.order.iter().rev().position(|&job| job == id) {
+            Some(0) => '+',
+            Some(1) => '-',
+            _ => ' ',
+        }
+    }
+
+    /// Find the job number for a job spec:
+    /// - `%n` is job number n
+    /// - `%+`, `%%` and `%` are the current job, and `%-` the previous one
+    /// - `%string` is the job whose command starts with the string, and `%?string` the one
+    ///   whose command contains it
+    pub fn find(&self, spec: &str) -> Result<usize, String> {
+        let no_such_job = || format!("{}: no such job", spec);
+        let Some(spec) = spec.strip_prefix('%') else {
+            return Err(no_such_job());
+        };
+        let from_end = |n: usize| self.order.iter().rev().nth(n).copied();
+
+        let found = match spec {
+            "" | "+" | "%" => from_end(0),
+            "-" => from_end(1).or_else(|| from_end(0)),
+            n if n.parse::<usize>().is_ok() => {
+                let id = n.parse().unwrap_or_default();
+                self.list.iter().any(|job| job.id == id).then_some(id)
+            }
+            text => {
+                let matches: Vec<usize> = self
+                    .list
+                    .iter()
+                    .filter(|job| match text.strip_prefix('?') {
+                        Some(text) => job.command.contains(text),
+                        None => job.command.starts_with(text),
+                    })
+                    .map(|job| job.id)
+                    .collect();
+                if matches.len() > 1 {
+                    return Err(format!("%{}: ambiguous job spec", text));
+                }
+                matches.first().copied()
+            }
+        };
+        found.ok_or_else(no_such_job)
+    }
+
+    /// The job with the number
+    pub fn get(&self, id: usize) -> Option<&Job> {
+        self.list.iter().find(|job| job.id == id)
+    }
+
+    /// The number of the job the process is part of
+    pub fn find_pid(&self, pid: i32) -> Option<usize> {
+        self.list
+            .iter()
+            .find(|job| job.pids().any(|other| other == pid))
+            .map(|job| job.id)
+    }
+
+    fn get_mut(&mut self, id: usize) -> Option<&mut Job> {
+        self.list.iter_mut().find(|job| job.id == id)
+    }
+
+    /// Add a job and make it the current job. A new job gets the next job number, and a job
+    /// that was in the table before (and was brought to the foreground) keeps its number.
+    fn add(&mut self, mut job: Job) -> usize {
+        if job.id == 0 {
+            job.id = self.list.last().map_or(1, |last| last.id + 1);
+        }
+        let id = job.id;
+        let index = self.list.partition_point(|other| other.id < id);
+        self.list.insert(index, job);
+        self.make_current(id);
+        id
+    }
+
+    /// Take the job out of the table
+    fn remove(&mut self, id: usize) -> Option<Job> {
+        let index = self.list.iter().position(|job| job.id == id)?;
+        self.order.retain(|&job| job != id);
+        Some(self.list.remove(index))
+    }
+
+    fn make_current(&mut self, id: usize) {
+        self.order.retain(|&job| job != id);
+        self.order.push(id);
+    }
+}
+
+// --------
+// PIPELINE
+// --------
+
+impl super::Shell {
+    /// Runs a pipeline of one or more stages from [`Parser::split_list`], in the background or
+    /// waiting for it
+    pub(super) fn execute_pipeline(&mut self, command: &str, background: bool) -> io::Result<()> {
+        let stages = Parser::split_pipeline(command);
+        if stages.iter().any(|stage| stage.trim().is_empty()) {
+            eprintln!(
+                "{}syntax error near unexpected token `|'",
+                self.error_prefix()
+            );
+            self.status = 2;
+            return Ok(());
+        }
+
+        // `$?` and `$-` expand to the status and options as they are before the pipeline runs.
+        // An interactive shell has an `i` among its options.
+        let interactive = if self.job_control { "i" } else { "" };
+        vars::set_status(self.status);
+        vars::set_flags(&format!("{}{}", self.set_options.letters(), interactive));
+
+        // With `set -u`, a variable that isn't set stops the pipeline from running, and a shell
+        // that isn't interactive exits
+        let nounset = self.is_set("nounset");
+        let parse = |stage| {
+            if nounset {
+                Parser::parse_with_options(stage, ParseOptions { nounset })
+            } else {
+                Parser::parse(stage)
+            }
+        };
+        let stages = match stages
+            .into_iter()
+            .map(parse)
+            .collect::<Result<Vec<Parsed>, String>>()
+        {
+            Ok(stages) => stages,
+            Err(e) if nounset && e.ends_with("unbound variable") => {
+                eprintln!("{}{}", self.error_prefix(), e);
+                self.status = 1;
+                if !self.job_control {
+                    self.status = 127;
+                    self.request_exit();
+                }
+                return Ok(());
+            }
+            Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidInput, e)),
+        };
+        if self.is_set("xtrace") {
+            for (args, _, _) in &stages {
+                self.trace(args);
+            }
+        }
+        match (stages.len(), background) {
+            // A simple command runs as a job only if it runs a program
+            (1, false) => {
+                let (args, out_target, err_target) = stages.into_iter().next().unwrap_or_default();
+                self.execute_command(args, out_target, err_target)
+            }
+            _ => self.run_job(stages, command.trim(), background),
+        }
+    }
+
+    /// Runs the stages of a pipeline, with the output of each one going to the next.
+    /// Programs run in a process group of their own, which gets the terminal unless the job is
+    /// in the background. Builtins and functions run in the shell itself, one after the other,
+    /// and their output is handed to the next stage once they're done.
+    pub(super) fn run_job(
+        &mut self,
+        stages: Vec<Parsed>,
+        command: &str,
+        background: bool,
+    ) -> io::Result<()> {
+        let mut job = Job {
+            id: 0,
+            pgid: 0,
+            processes: Vec::new(),
+            command: command.to_owned(),
+            background,
+            hang_up: true,
+        };
+        // The output of the stage before, for the next one to read
+        let mut previous: Option<ChildStdout> = None;
+        let mut feed: Option<Vec<u8>> = None;
+        // The output of the last stage, when it is captured by a command substitution
+        let mut capture = None;
+        let count = stages.len();
+
+        for (i, (args, out_target, err_target)) in stages.into_iter().enumerate() {
+            let last = i + 1 == count;
+            let ProgramCall { path, env, args } = match self.resolve_program(args) {
+                Ok(program) => program,
+                Err(args) => {
+                    // Anything the stage before writes goes nowhere
+                    previous = None;
+                    // The stage is done by now, and is kept without a process ID for its status
+                    if last {
+                        self.execute_command(args, out_target, err_target)?;
+                    } else {
+                        feed = Some(self.capture_command(args, out_target, err_target)?);
+                    }
+                    job.processes.push((0, State::Exited(self.status)));
+                    continue;
+                }
+            };
+
+            let mut process = std::process::Command::new(&path);
+            #[cfg(unix)]
+            std::os::unix::process::CommandExt::arg0(&mut process, &args[0]);
+            process.args(&args[1..]).envs(env);
+
+            let stdin = if feed.is_some() {
+                Stdio::piped()
+            } else if let Some(output) = previous.take() {
+                Stdio::from(output)
+            } else if i > 0 || (background && !self.job_control) {
+                Stdio::null()
+            } else {
+                Stdio::inherit()
+            };
+            // A stage whose file can't be opened fails without running
+            let files = (
+                out_target
+                    .as_ref()
+                    .map(|target| self.open_target(target))
+                    .transpose(),
+                err_target
+                    .as_ref()
+                    .map(|target| self.open_target(target))
+                    .transpose(),
+            );
+            let (out_file, err_file) = match files {
+                (Ok(out_file), Ok(err_file)) => (out_file, err_file),
+                (Err(e), _) | (_, Err(e)) => {
+                    eprintln!("{}{}", self.error_prefix(), e);
+                    feed = None;
+                    job.processes.push((0, State::Exited(1)));
+                    continue;
+                }
+            };
+            let stdout = match out_file {
+                Some(file) => Stdio::from(file),
+                None if !last || self.capture.is_some() => Stdio::piped(),
+                None => Stdio::inherit(),
+            };
+            let stderr = match err_file {
+                Some(file) => Stdio::from(file),
+                None => Stdio::inherit(),
+            };
+            process.stdin(stdin).stdout(stdout).stderr(stderr);
+            self.prepare_process(&mut process, job.pgid, background);
+
+            let mut child = match process.spawn() {
+                Ok(child) => child,
+                Err(e) => {
+                    eprintln!("{}{}: {}", self.error_prefix(), args[0], e);
+                    job.processes.push((0, State::Exited(126)));
+                    continue;
+                }
+            };
+            let pid = child.id() as i32;
+            if self.job_control {
+                // Also set the group from the shell's side, so that it is in place whichever
+                // of the shell and the child gets to run first
+                let pgid = if job.pgid == 0 { pid } else { job.pgid };
+                sys::set_process_group(pid, pgid);
+            }
+            if job.pgid == 0 {
+                job.pgid = pid;
+            }
+            job.processes.push((pid, State::Running));
+
+            if let (Some(input), Some(mut stdin)) = (feed.take(), child.stdin.take()) {
+                thread::spawn(move || stdin.write_all(&input));
+            }
+            match child.stdout.take() {
+                Some(mut output) if last => {
+                    capture = Some(thread::spawn(move || {
+                        let mut buffer = Vec::new();
+                        output.read_to_end(&mut buffer).map(|_| buffer)
+                    }));
+                }
+                output => previous = output,
+            }
+            // The child is waited for with `waitpid`, so that stopped processes are noticed
+            drop(child);
+        }
+
+        // Without a program, every stage has already run
+        if job.pgid == 0 {
+            self.status = job.status(self.is_set("pipefail"));
+            return Ok(());
+        }
+        if background {
+            let pid = job.pids().last().unwrap_or_default();
+            let id = self.jobs.add(job);
+            vars::set_last_background(pid);
+            // Only interactive shells report the jobs they start
+            if self.job_control {
+                eprintln!("[{}] {}", id, pid);
+            }
+            self.status = 0;
+            return Ok(());
+        }
+
+        self.wait_for(job)?;
+        if let Some(reader) = capture {
+            if let (Ok(Ok(output)), Some(buffer)) = (reader.join(), &self.capture) {
+                buffer.borrow_mut().extend(output);
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets up a program to run in the job. With job control, it goes in the job's process
+    /// group (or a new one, if the job has no process group yet), and takes the terminal itself
+    /// if the job is in the foreground. Either way, it gets back the signals the shell ignores.
+    fn prepare_process(&self, process: &mut std::process::Command, pgid: i32, background: bool) {
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::CommandExt;
+            let job_control = self.job_control;
+            if job_control {
+                process.process_group(pgid);
+            }
+            // SAFETY: the closure only makes async-signal-safe calls
+            unsafe {
+                process.pre_exec(move || {
+                    if job_control && !background {
+                        sys::set_foreground(sys::process_group());
+                    }
+                    for signal in sys::INTERACTIVE_SIGNALS {
+                        sys::ignore_signal(signal, false);
+                    }
+                    // Without job control, a background job shares the terminal with the
+                    // shell, so the keys that interrupt and quit are meant for the shell
+                    if !job_control && background {
+                        sys::ignore_signal(sys::SIGINT, true);
+                        sys::ignore_signal(sys::SIGQUIT, true);
+                    }
+                    Ok(())
+                });
+            }
+        }
+        #[cfg(not(unix))]
+        let _ = (process, pgid, background);
+    }
+
+    /// Waits for a job in the foreground, with the terminal handed to it, until it finishes or
+    /// stops. A stopped job goes into the job table.
+    fn wait_for(&mut self, mut job: Job) -> io::Result<()> {
+        job.background = false;
+        if self.job_control {
+            sys::set_foreground(job.pgid);
+        }
+        for (pid, state) in &mut job.processes {
+            if state.is_finished() {
+                continue;
+            }
+            if let Some(status) = sys::wait(*pid, true) {
+                *state = State::from(status);
+            }
+            if let State::Stopped(signal) = *state {
+                // The rest of the process group was stopped along with it
+                for (_, state) in &mut job.processes {
+                    if *state == State::Running {
+                        *state = State::Stopped(signal);
+                    }
+                }
+                break;
+            }
+        }
+        if self.job_control {
+            sys::set_foreground(self.pgid);
+        }
+
+        let state = job.state();
+        self.status = job.status(self.is_set("pipefail"));
+        match state {
+            // The terminal has already shown `^C`, so only the line is ended. A broken pipe is
+            // how a pipeline normally ends early, like with `yes | head`.
+            State::Signaled(sys::SIGINT, _) if self.job_control => eprintln!(),
+            State::Signaled(sys::SIGINT | sys::SIGPIPE, _) => {}
+            State::Signaled(..) => eprintln!("{}", state),
+            _ => {}
+        }
+        if let State::Stopped(_) = state {
+            let id = self.jobs.add(job);
+            if let Some(job) = self.jobs.get(id) {
+                eprintln!();
+                eprintln!("{}", job.describe(self.jobs.marker(id), false));
+            }
+        }
+        Ok(())
+    }
+
+    /// Brings a job to the foreground, continuing it if it was stopped, and waits for it.
+    /// The job's command line is written to the writer first.
+    pub fn foreground_job(&mut self, id: usize, writer: &mut impl io::Write) -> io::Result<()> {
+        let Some(mut job) = self.jobs.remove(id) else {
+            return Ok(());
+        };
+        writeln!(writer, "{}", job.command)?;
+        writer.flush()?;
+        if self.job_control {
+            sys::set_foreground(job.pgid);
+        }
+        continue_job(&mut job)?;
+        self.wait_for(job)
+    }
+
+    /// Continues a stopped job in the background, and writes its line to the writer
+    pub fn background_job(&mut self, id: usize, writer: &mut impl io::Write) -> io::Result<()> {
+        let marker = self.jobs.marker(id);
+        let Some(job) = self.jobs.get_mut(id) else {
+            return Ok(());
+        };
+        job.background = true;
+        continue_job(job)?;
+        writeln!(writer, "[{}]{} {} &", job.id, marker, job.command)?;
+        Ok(())
+    }
+
+    /// Sends the signal to the processes of the job. A stopped job is continued after being
+    /// told to terminate or hang up, so that it can act on it.
+    pub fn signal_job(&mut self, id: usize, signal: i32) -> io::Result<()> {
+        let job_control = self.job_control;
+        let Some(job) = self.jobs.get_mut(id) else {
+            return Ok(());
+        };
+        if job_control {
+            sys::kill(-job.pgid, signal)?;
+        } else {
+            // Without job control, the processes are in the shell's own process group
+            for pid in job.pids() {
+                sys::kill(pid, signal)?;
+            }
+        }
+        if matches!(signal, sys::SIGTERM | sys::SIGHUP) {
+            continue_job(job)?;
+        }
+        Ok(())
+    }
+
+    /// Waits for jobs in the background, or for single processes in them, given as the job
+    /// number and the process ID. A job that finishes is taken out of the table, and one that
+    /// stops is done being waited for as well. With `any`, the wait is over as soon as one of
+    /// them is done.
+    ///
+    /// Returns the exit status of the last one that was waited for, or if a signal the shell
+    /// catches interrupts the wait, the signal as the error.
+    pub fn wait_jobs(
+        &mut self,
+        mut targets: Vec<(usize, Option<i32>)>,
+        any: bool,
+    ) -> Result<i32, i32> {
+        let pipefail = self.is_set("pipefail");
+        let mut status = 0;
+        while !targets.is_empty() {
+            let mut waiting = Vec::new();
+            for (id, pid) in targets {
+                let Some(job) = self.jobs.get_mut(id) else {
+                    status = 127;
+                    continue;
+                };
+                job.update();
+                let (state, done_status) = match pid {
+                    Some(pid) => {
+                        let state = job
+                            .processes
+                            .iter()
+                            .find(|(other, _)| *other == pid)
+                            .map_or(State::Exited(127), |(_, state)| *state);
+                        (state, state.status())
+                    }
+                    None => (job.state(), job.status(pipefail)),
+                };
+                if state == State::Running {
+                    waiting.push((id, pid));
+                    continue;
+                }
+                status = done_status;
+                if job.state().is_finished() {
+                    self.jobs.remove(id);
+                }
+                if any {
+                    return Ok(status);
+                }
+            }
+            targets = waiting;
+            if targets.is_empty() {
+                break;
+            }
+            if let Some(signal) = sys::pending_signal() {
+                return Err(signal);
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        Ok(status)
+    }
+
+    /// Takes the job out of the table, so that the shell forgets about it, or with `keep`, only
+    /// makes sure it isn't hung up when the shell exits
+    pub fn disown_job(&mut self, id: usize, keep: bool) {
+        if keep {
+            if let Some(job) = self.jobs.get_mut(id) {
+                job.hang_up = false;
+            }
+        } else {
+            self.jobs.remove(id);
+        }
+    }
+
+    /// Whether the shell can exit now. With stopped jobs, the first attempt is refused, so
+    /// that they aren't lost by accident. The next one goes ahead, unless a command was run in
+    /// between.
+    pub fn can_exit(&mut self) -> bool {
+        let stopped = self
+            .jobs
+            .iter()
+            .any(|job| matches!(job.state(), State::Stopped(_)));
+        if !stopped || self.exit_warned {
+            return true;
+        }
+        self.exit_warned = true;
+        false
+    }
+
+    /// Hangs up the jobs as the interactive shell exits, except for the ones that were
+    /// disowned. Stopped jobs would otherwise be left stopped for good.
+    pub fn hang_up_jobs(&mut self) {
+        if !self.job_control {
+            return;
+        }
+        let ids: Vec<usize> = self
+            .jobs
+            .iter()
+            .filter(|job| job.hang_up)
+            .map(|job| job.id)
+            .collect();
+        for id in ids {
+            let _ = self.signal_job(id, sys::SIGHUP);
+        }
+    }
+
+    /// The jobs that are running in the background or are stopped
+    pub fn jobs(&self) -> &Jobs {
+        &self.jobs
+    }
+
+    /// Check on the jobs without waiting for them. The ones that have finished are reported
+    /// like `[1]+  Done                    sleep 10`, and taken out of the table.
+    /// Background jobs that stopped, like on reading from the terminal, are reported as well.
+    pub fn notify_jobs(&mut self) {
+        let mut finished = Vec::new();
+        let mut stopped = Vec::new();
+        for job in &mut self.jobs.list {
+            let before = job.state();
+            job.update();
+            let after = job.state();
+            if after.is_finished() {
+                finished.push(job.id);
+            } else if after != before && matches!(after, State::Stopped(_)) {
+                job.background = false;
+                stopped.push(job.id);
+            }
+        }
+        for id in stopped {
+            self.jobs.make_current(id);
+            if let Some(job) = self.jobs.get(id) {
+                eprintln!("{}", job.describe(self.jobs.marker(id), false));
+            }
+        }
+        for id in finished {
+            let marker = self.jobs.marker(id);
+            if let Some(job) = self.jobs.remove(id) {
+                eprintln!("{}", job.describe(marker, false));
+            }
+        }
+    }
+
+    /// Takes over the terminal for the shell, in a process group of its own, so that it can
+    /// hand the terminal to jobs and take it back. Job control is only on when the shell reads
+    /// from a terminal.
+    pub fn start_job_control(&mut self) {
+        use std::io::IsTerminal;
+        self.job_control = io::stdin().is_terminal();
+        if !self.job_control {
+            return;
+        }
+        // The keys that interrupt, quit and suspend are for the job in the foreground, not the
+        // shell. Taking the terminal back from a job happens from the background, which the
+        // terminal would otherwise stop the shell for.
+        for signal in sys::INTERACTIVE_SIGNALS {
+            sys::ignore_signal(signal, true);
+        }
+        // An interrupt is still noticed, so that `wait` can be interrupted
+        sys::catch_signal(sys::SIGINT);
+        sys::set_process_group(0, 0);
+        self.pgid = sys::process_group();
+        sys::set_foreground(self.pgid);
+    }
+
+    /// Makes sure the shell has the terminal before it reads the next line
+    pub(super) fn reclaim_terminal(&self) {
+        if self.job_control && sys::foreground() != Some(self.pgid) {
+            sys::set_foreground(self.pgid);
+        }
+    }
+}
+
+/// Sends a stopped job the signal to continue
+fn continue_job(job: &mut Job) -> io::Result<()> {
+    if !matches!(job.state(), State::Stopped(_)) {
+        return Ok(());
+    }
+    sys::kill(-job.pgid, sys::SIGCONT)?;
+    for (_, state) in &mut job.processes {
+        if let State::Stopped(_) = state {
+            *state = State::Running;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(command: &str, states: &[State]) -> Job {
+        Job {
+            id: 0,
+            pgid: 100,
+            processes: states.iter().map(|state| (100, *state)).collect(),
+            command: command.to_owned(),
+            background: true,
+            hang_up: true,
+        }
+    }
+
+    #[test]
+    fn test_job_specs() {
+        let mut jobs = Jobs::default();
+        jobs.add(job("sleep 10", &[State::Running]));
+        jobs.add(job("vim notes", &[State::Stopped(sys::SIGTSTP)]));
+        jobs.add(job("make | tee log", &[State::Running, State::Running]));
+
+        assert_eq!(jobs.find("%"), Ok(3));
+        assert_eq!(jobs.find("%-"), Ok(2));
+        assert_eq!(jobs.find("%1"), Ok(1));
+        assert_eq!(jobs.find("%vim"), Ok(2));
+        assert_eq!(jobs.find("%?tee"), Ok(3));
+        assert!(jobs.find("%4").is_err());
+        assert!(jobs.find("%?e").is_err());
+
+        // Bringing a job back makes it the current one
+        jobs.make_current(1);
+        assert_eq!(
+            (jobs.marker(1), jobs.marker(3), jobs.marker(2)),
+            ('+', '-', ' ')
+        );
+    }
+
+    #[test]
+    fn test_describe() {
+        let sleeping = Job {
+            id: 1,
+            ..job("sleep 10", &[State::Running])
+        };
+        assert_eq!(
+            sleeping.describe('+', false),
+            "[1]+  Running                 sleep 10 &"
+        );
+        let failed = job("false | true", &[State::Exited(1), State::Exited(0)]);
+        assert_eq!(failed.state(), State::Exited(0));
+        let stopped = job("vim", &[State::Stopped(sys::SIGTSTP)]);
+        assert_eq!(
+            stopped.describe('-', true),
+            "[0]- 100 Stopped                 vim"
+        );
+        let crashed = job("./a.out", &[State::Signaled(11, true)]);
+        assert_eq!(
+            crashed.describe(' ', false),
+            "[0]   Segmentation fault (core dumped) ./a.out"
+        );
+    }
+
+    #[test]
+    fn test_fg_and_bg_write_to_their_output() {
+        let mut shell = super::super::Shell::default();
+        let path = std::env::temp_dir().join(format!("shell-bg-{}", std::process::id()));
+        let file = path.display();
+        // A job that is already running is left alone
+        let (output, status) = shell.run_captured(&format!("sleep 0.1 & bg 2> {file}; fg %1"));
+        assert_eq!(output, "sleep 0.1\n");
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "bg: job 1 already in background\n"
+        );
+        assert_eq!(status, 0);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
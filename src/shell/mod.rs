@@ -1,15 +1,68 @@
-use std::io;
+use std::{cell::RefCell, collections::HashMap, io, rc::Rc};
 
-use readline::ReadLine;
+use readline::{
+    keymap::Keymap, CommandCompleter, FilenameCompleter, Matching, ReadLine, Signal,
+    VariableCompleter,
+};
 
-use crate::{helpers, parser::Parser};
+use crate::{
+    commands::{self, Builtin, CommandInfo},
+    helpers::{self, vars},
+    parser::Parser,
+};
 
 mod executor;
+mod jobs;
+mod options;
+mod prompt;
 mod readline;
+mod script;
+mod startup;
+mod timing;
+mod traps;
+
+pub use jobs::{Jobs, State};
+pub use options::Options;
+pub use readline::{keymap, CompSpec};
+pub use startup::Startup;
+pub use traps::Condition;
 
 /// Struct that encapsulates the shell functionality
 pub struct Shell {
     readline: ReadLine,
+    /// The exit status of the last command
+    status: i32,
+    /// The shell functions, by name, with their bodies
+    functions: HashMap<String, String>,
+    /// The options set with `shopt`
+    options: Options,
+    /// The options set with `set`
+    set_options: Options,
+    /// Where stdout goes while a command substitution is running
+    capture: Option<Rc<RefCell<Vec<u8>>>>,
+    /// How long the last command line took, once one has run
+    timing: Option<timing::Timing>,
+    /// The jobs that are running in the background or are stopped
+    jobs: Jobs,
+    /// Whether jobs get process groups of their own and the terminal is handed to them
+    job_control: bool,
+    /// The process group of the shell, which has the terminal while it reads a line
+    pgid: i32,
+    /// Whether the last attempt to exit was refused because of stopped jobs
+    exit_warned: bool,
+    /// The traps set with `trap`
+    traps: traps::Traps,
+    /// Whether `exit` was run, and the shell exits once the command is done
+    exiting: bool,
+    /// The file and line of the command that's running, if it was read from a file
+    location: Option<(String, usize)>,
+    /// How many functions and sourced files are running, which `return` can end
+    calls: usize,
+    /// Whether `return` was run, and the function or sourced file ends once the command is done
+    returning: bool,
+    /// How many commands whose failure `set -e` ignores are running, like the left side of `&&`
+    /// or `||` and a pipeline negated with `!`. Everything they run is ignored as well.
+    errexit_suppressed: usize,
 }
 
 // Default implementation for the Shell struct
@@ -17,26 +70,43 @@ impl Default for Shell {
     fn default() -> Self {
         let mut readline = ReadLine::default();
 
-        let builtin_completions: Vec<String> = vec![
-            "cd".into(),
-            "echo".into(),
-            "exit".into(),
-            "pwd".into(),
-            "type".into(),
-        ];
-        let path_completions = helpers::path::get_executables()
+        let builtin_completions = commands::BUILTINS
             .iter()
-            .filter_map(|p| {
-                p.file_name()
-                    .and_then(|x| Some(x.to_string_lossy().into_owned()))
-            })
+            .filter_map(|name| name.parse::<Builtin>().ok())
+            .map(|builtin| (builtin.name(), builtin.description()))
             .collect();
 
         readline
             .with_prompt("$ ")
-            .with_completions(builtin_completions)
-            .with_completions(path_completions);
-        Shell { readline }
+            .with_descriptions(builtin_completions)
+            .register_completer(Box::new(CommandCompleter))
+            .register_completer(Box::new(FilenameCompleter))
+            .register_completer(Box::new(VariableCompleter));
+
+        // Like other shells, `PWD` starts out as the current directory, and is exported
+        if let Some(dir) = helpers::dirs::logical() {
+            vars::set("PWD", &dir.to_string_lossy());
+            vars::export("PWD", true);
+        }
+        Shell {
+            readline,
+            status: 0,
+            functions: HashMap::new(),
+            options: Options::default(),
+            set_options: Options::for_set(),
+            capture: None,
+            timing: None,
+            jobs: Jobs::default(),
+            job_control: false,
+            pgid: 0,
+            exit_warned: false,
+            traps: traps::Traps::default(),
+            exiting: false,
+            location: None,
+            calls: 0,
+            returning: false,
+            errexit_suppressed: 0,
+        }
     }
 }
 
@@ -47,20 +117,444 @@ impl Shell {
     /// that will keep reading the input and processing commands
     /// until the user exits the shell.
     pub fn run(&mut self) -> io::Result<()> {
+        // The number of consecutive end-of-file presses
+        let mut eof_count = 0;
+        // Whether the next read starts a new line, rather than resuming the one being edited
+        let mut new_line = true;
+        if !self.job_control {
+            self.start_job_control();
+        }
+        // A startup file may have run `exit`
+        if self.exiting {
+            return self.finish();
+        }
+
         loop {
+            // The prompt is expanded again for every new line, after reporting on the jobs
+            if new_line {
+                self.run_signal_traps()?;
+                self.notify_jobs();
+                let (prompt, right) = (self.prompt(), self.right_prompt());
+                let transient = self.transient_prompt();
+                self.readline
+                    .with_prompt(&prompt)
+                    .with_right_prompt(&right)
+                    .with_transient_prompt(transient);
+            }
+
             // Render the prompt and wait for user input
-            let input = self.readline.read()?;
-            let input = input.trim();
-            if input.is_empty() {
-                continue; //Skip this iteration if input was empty
+            self.reclaim_terminal();
+            let signal = self.readline.read()?;
+            new_line = !matches!(signal, Signal::Execute(_) | Signal::Complete(_));
+            if !matches!(signal, Signal::EndOfFile) {
+                eof_count = 0;
+            }
+            // A refused exit only lets the very next attempt go ahead
+            let exit_warned = self.exit_warned;
+
+            match signal {
+                Signal::Success(input) => self.eval_timed(&input)?,
+                Signal::Execute(command) => self.execute_binding(&command)?,
+                Signal::Complete(function) => self.run_completion_function(&function)?,
+                Signal::Interrupt => self.status = 130, // 128 + SIGINT
+                Signal::EndOfFile => {
+                    eof_count += 1;
+                    if eof_count <= ignore_eof() {
+                        println!("Use \"exit\" to leave the shell.");
+                    } else if self.can_exit() {
+                        println!("exit");
+                        self.request_exit();
+                    } else {
+                        eprintln!("There are stopped jobs.");
+                    }
+                }
+            }
+            if exit_warned {
+                self.exit_warned = false;
             }
+            if self.exiting {
+                return self.finish();
+            }
+        }
+    }
 
-            // Split the input into a vector
-            let (args, out_target, err_target) = Parser::parse(&input)
-                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    /// Parses and executes a line of input, which may hold several commands separated by `;`
+    pub fn eval(&mut self, input: &str) -> io::Result<()> {
+        for command in Parser::split_commands(input) {
+            if self.exiting || self.returning {
+                break;
+            }
 
-            // Act on the command-name
-            self.execute_command(args, out_target, err_target)?;
+            // Function definitions are stored, and run when the function is called
+            if let Some((name, body)) = Parser::parse_function(command) {
+                self.readline.functions().insert(name.clone());
+                self.functions.insert(name, body);
+                self.status = 0;
+                continue;
+            }
+
+            // With `set -n`, commands are only read. An interactive shell ignores it, or there
+            // would be no way to turn it back off.
+            if self.is_set("noexec") && !self.job_control {
+                continue;
+            }
+            self.execute_list(command)?;
+            self.run_signal_traps()?;
         }
+        Ok(())
+    }
+
+    /// Runs an and-or list, like `make && make install || echo failed`. Each pipeline after the
+    /// first only runs if the one before it succeeded (`&&`) or failed (`||`), and a `&` at the
+    /// end runs the last pipeline in the background.
+    ///
+    /// Only a failure of the last pipeline sets off the `ERR` trap and `set -e`, since the others
+    /// are tested by the `&&` or `||` after them.
+    fn execute_list(&mut self, command: &str) -> io::Result<()> {
+        let (command, background) = Parser::background(command);
+        let pipelines = Parser::split_list(command);
+        if let Some(i) = pipelines.iter().position(|(_, p)| p.trim().is_empty()) {
+            // The operator after the missing pipeline is the one that's out of place
+            let token = match pipelines.get(i + 1) {
+                Some((operator, _)) => operator.unwrap_or_default(),
+                None if background => "&",
+                None => "newline",
+            };
+            eprintln!(
+                "{}syntax error near unexpected token `{}'",
+                self.error_prefix(),
+                token
+            );
+            self.status = 2;
+            return Ok(());
+        }
+
+        let last = pipelines.len() - 1;
+        for (i, (operator, pipeline)) in pipelines.into_iter().enumerate() {
+            let skip = match operator {
+                Some("&&") => self.status != 0,
+                Some("||") => self.status == 0,
+                _ => false,
+            };
+            if skip || self.exiting || self.returning {
+                continue;
+            }
+            let (pipeline, negated) = Parser::negation(pipeline);
+            // Only the last pipeline of the list can fail the shell, unless it is negated
+            let checked = i == last && !negated;
+            if !checked {
+                self.errexit_suppressed += 1;
+            }
+            self.run_debug_trap(pipeline)?;
+            let result = self.execute_pipeline(pipeline, background && i == last);
+            if !checked {
+                self.errexit_suppressed -= 1;
+            }
+            result?;
+            if negated {
+                self.status = i32::from(self.status == 0);
+            }
+            if checked
+                && self.status != 0
+                && self.errexit_suppressed == 0
+                && !self.exiting
+                && !self.returning
+            {
+                self.run_trap(traps::Condition::Err)?;
+                if self.is_set("errexit") {
+                    self.request_exit();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs a shell command bound to a key with `bind -x`.
+    /// The line being edited is exposed to the command as `READLINE_LINE` (and the cursor
+    /// position as `READLINE_POINT`), and any changes the command makes to them are
+    /// carried back into the line editor.
+    fn execute_binding(&mut self, command: &str) -> io::Result<()> {
+        let (line, point) = self.readline.line();
+        vars::set("READLINE_LINE", line);
+        vars::set("READLINE_POINT", &point.to_string());
+
+        if let Err(e) = self.eval(command) {
+            eprintln!("{}", e);
+        }
+
+        let line = vars::get("READLINE_LINE").unwrap_or_default();
+        let point = vars::get("READLINE_POINT")
+            .and_then(|p| p.parse().ok())
+            .unwrap_or(line.chars().count());
+        self.readline.set_line(&line, point);
+        vars::unset("READLINE_LINE");
+        vars::unset("READLINE_POINT");
+        Ok(())
+    }
+
+    /// Runs a completion function registered with `complete -F`, and hands the candidates it
+    /// puts in `COMPREPLY` back to the line editor. Like bash, the function is called with the
+    /// command name, the word being completed and the word before it, and can look at the
+    /// command line through `COMP_WORDS`, `COMP_CWORD`, `COMP_LINE` and `COMP_POINT`.
+    fn run_completion_function(&mut self, function: &str) -> io::Result<()> {
+        let Some(body) = self.functions.get(function).cloned() else {
+            eprintln!();
+            eprintln!("completion: function `{}' not found", function);
+            self.readline.complete_with(Vec::new());
+            return Ok(());
+        };
+
+        let ctx = self.readline.context();
+        let words: Vec<String> = ctx.words.iter().map(|w| w.to_string()).collect();
+        let previous = ctx.index.checked_sub(1).map_or("", |i| ctx.words[i]);
+        let args = vec![
+            function.to_owned(),
+            words[0].clone(),
+            ctx.word.to_owned(),
+            previous.to_owned(),
+        ];
+        let point = ctx.line[..ctx.cursor].chars().count();
+        vars::set("COMP_LINE", ctx.line);
+        vars::set("COMP_POINT", &point.to_string());
+        vars::set("COMP_CWORD", &ctx.index.to_string());
+        vars::set_array("COMP_WORDS", words);
+        vars::unset("COMPREPLY");
+
+        if let Err(e) = self.call_function(&body, args) {
+            eprintln!("{}", e);
+        }
+
+        let reply = vars::get_array("COMPREPLY").unwrap_or_default();
+        for name in [
+            "COMP_LINE",
+            "COMP_POINT",
+            "COMP_CWORD",
+            "COMP_WORDS",
+            "COMPREPLY",
+        ] {
+            vars::unset(name);
+        }
+        self.readline.complete_with(reply);
+        Ok(())
+    }
+
+    /// The exit status of the last command
+    pub fn status(&self) -> i32 {
+        self.status
+    }
+
+    /// The key bindings used by the line editor
+    pub fn keymap(&mut self) -> &mut Keymap {
+        self.readline.keymap()
+    }
+
+    /// The completion specs registered with `complete`, by command name
+    pub fn completion_specs(&mut self) -> &mut HashMap<String, CompSpec> {
+        self.readline.specs()
+    }
+
+    /// The options set with `shopt`, and whether they are on
+    pub fn options(&self) -> impl Iterator<Item = (&str, bool)> {
+        self.options.all()
+    }
+
+    /// Whether the `shopt` option is on, or `None` if there is no such option
+    pub fn option(&self, name: &str) -> Option<bool> {
+        self.options.get(name)
+    }
+
+    /// Turn a `shopt` option on or off, and apply it
+    pub fn set_option(&mut self, name: &str, on: bool) -> Result<(), String> {
+        self.options.set(name, on)?;
+        self.readline.set_matching(Matching {
+            ignore_case: self.options.get("complete_ignore_case") == Some(true),
+            substring: self.options.get("complete_substring") == Some(true),
+            fuzzy: self.options.get("complete_fuzzy") == Some(true),
+        });
+        self.readline
+            .set_confirm_paste(self.options.get("confirm_paste") == Some(true));
+        Ok(())
+    }
+
+    /// The options set with `set`, and whether they are on
+    pub fn shell_options(&self) -> impl Iterator<Item = (&str, bool)> {
+        self.set_options.all()
+    }
+
+    /// Turn a `set` option on or off
+    pub fn set_shell_option(&mut self, name: &str, on: bool) -> Result<(), String> {
+        self.set_options
+            .set(name, on)
+            .map_err(|_| format!("{}: invalid option name", name))
+    }
+
+    /// Whether the `set` option is on
+    fn is_set(&self, name: &str) -> bool {
+        self.set_options.is_on(name)
+    }
+
+    /// Whether a shell function with the given name is defined
+    pub fn has_function(&self, name: &str) -> bool {
+        self.functions.contains_key(name)
+    }
+}
+
+/// The number of consecutive end-of-file presses to ignore before exiting, from `IGNOREEOF`.
+/// Like bash, a value that is set but isn't a number counts as 10.
+fn ignore_eof() -> u32 {
+    match vars::get("IGNOREEOF") {
+        Some(value) => value.parse().unwrap_or(10),
+        None => 0,
+    }
+}
+
+/// Held by the tests that change the current directory or `HOME`, which the whole process shares
+#[cfg(test)]
+pub(crate) static CWD_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+#[cfg(test)]
+impl Shell {
+    /// Runs the commands like a `-c` command string, and returns what they wrote to stdout along
+    /// with the exit status
+    pub(crate) fn run_captured(&mut self, input: &str) -> (String, i32) {
+        let output = self
+            .capture(|shell| shell.run_command(input))
+            .expect("the commands should run");
+        (String::from_utf8_lossy(&output).into_owned(), self.status)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_lists() {
+        let mut shell = Shell::default();
+        let (output, status) = shell.run_captured("echo a; echo 'b;c' \"d;e\"; false");
+        assert_eq!(output, "a\nb;c d;e\n");
+        assert_eq!(status, 1);
+    }
+
+    #[test]
+    fn test_comments() {
+        let mut shell = Shell::default();
+        let (output, status) = shell.run_captured(
+            "# a whole line\necho a#b '#c' \\#d # it's the rest; echo not run\necho e",
+        );
+        assert_eq!(output, "a#b #c #d\ne\n");
+        assert_eq!(status, 0);
+    }
+
+    #[test]
+    fn test_newlines() {
+        let mut shell = Shell::default();
+        let (output, status) = shell.run_captured("echo a\n\necho b; echo c\nfalse\n");
+        assert_eq!(output, "a\nb\nc\n");
+        assert_eq!(status, 1);
+    }
+
+    #[test]
+    fn test_line_continuations() {
+        let mut shell = Shell::default();
+        let (output, status) = shell.run_captured("echo one \\\n  two\necho th\\\nree 'a\\\nb'");
+        assert_eq!(output, "one two\nthree a\\\nb\n");
+        assert_eq!(status, 0);
+    }
+
+    #[test]
+    fn test_errexit_is_ignored_in_conditions() {
+        let mut shell = Shell::default();
+        let (output, status) =
+            shell.run_captured("set -e; f() { false; echo inner; }; f || echo caught; echo end");
+        assert_eq!(output, "inner\nend\n");
+        assert_eq!(status, 0);
+
+        let mut shell = Shell::default();
+        let (output, status) =
+            shell.run_captured("set -e; ! true; false && echo no; ! false | true; echo end");
+        assert_eq!(output, "end\n");
+        assert_eq!(status, 0);
+
+        let mut shell = Shell::default();
+        let (output, status) = shell.run_captured("set -e; true && false; echo not run");
+        assert_eq!(output, "");
+        assert_eq!(status, 1);
+    }
+
+    #[test]
+    fn test_set_options() {
+        let path = std::env::temp_dir().join(format!("shell-set-{}", std::process::id()));
+        let file = path.display();
+        let mut shell = Shell::default();
+
+        let (output, status) = shell.run_captured("set -- a 'b c'; echo $# $2");
+        assert_eq!(output, "2 b c\n");
+        assert_eq!(status, 0);
+
+        // `-C` keeps `>` from overwriting a file, but not `>|`
+        let (_, status) = shell.run_captured(&format!("set -C; echo a > {file}; echo b > {file}"));
+        assert_eq!(status, 1);
+        let (output, _) = shell.run_captured(&format!("cat {file}; echo c >| {file}; cat {file}"));
+        assert_eq!(output, "a\nc\n");
+        std::fs::remove_file(&path).unwrap();
+
+        let (_, status) = shell.run_captured("false | true");
+        assert_eq!(status, 0);
+        let (_, status) = shell.run_captured("set -o pipefail; false | true");
+        assert_eq!(status, 1);
+
+        let (output, status) = shell.run_captured("set -u; echo $UNSET_NAME; echo not run");
+        assert_eq!(output, "");
+        assert_ne!(status, 0);
+
+        let (output, status) = shell.run_captured("set -n; echo not run");
+        assert_eq!(output, "");
+        assert_eq!(status, 0);
+
+        let (output, _) = Shell::default().run_captured("set -o xtrace; set +o");
+        assert!(output.contains("set -o xtrace\n"));
+        assert!(output.contains("set +o errexit\n"));
+    }
+
+    #[test]
+    fn test_special_parameters() {
+        let mut shell = Shell::default();
+        let (output, _) = shell.run_captured("false; echo $? ${?}; true; echo $?");
+        assert_eq!(output, "1 1\n0\n");
+
+        let (output, status) = shell.run_captured("sleep 0.1 & wait $!; echo $? $!");
+        assert_eq!(output, format!("0 {}\n", vars::get("!").unwrap()));
+        assert_eq!(status, 0);
+
+        let (output, _) = shell.run_captured("echo $$; set -eu; echo $-");
+        assert_eq!(output, format!("{}\neu\n", std::process::id()));
+    }
+
+    #[test]
+    fn test_functions() {
+        let mut shell = Shell::default();
+        let (output, status) = shell
+            .run_captured("set -- outer; greet() { echo hello $1; }; greet world; greet; echo $1");
+        assert_eq!(output, "hello world\nhello\nouter\n");
+        assert_eq!(status, 0);
+        assert!(shell.has_function("greet"));
+
+        let (output, _) = shell.run_captured("f() { return $1; }; f 257; echo $?; f -1; echo $?");
+        assert_eq!(output, "1\n255\n");
+        let (_, status) = Shell::default().run_captured("exit 258");
+        assert_eq!(status, 2);
+    }
+
+    #[test]
+    fn test_arrays() {
+        let mut shell = Shell::default();
+        let (output, _) = shell
+            .run_captured("words=(one 'two three'); echo ${words[1]}; echo $words ${words[@]}");
+        assert_eq!(output, "two three\none one two three\n");
+        assert_eq!(
+            vars::get_array("words"),
+            Some(vec!["one".into(), "two three".into()])
+        );
     }
 }
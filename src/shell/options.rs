@@ -0,0 +1,103 @@
+// Library
+use std::collections::BTreeMap;
+
+// -------------
+// SHELL OPTIONS
+// -------------
+
+/// The names of the shell options that are set with `shopt`, and whether they are on by default
+const DEFAULTS: &[(&str, bool)] = &[
+    // Ignore the case of letters when completing
+    ("complete_ignore_case", false),
+    // Complete words that contain the text anywhere, not just at the start
+    ("complete_substring", false),
+    // Complete words that contain the characters of the text in order, like `gco` for `git-checkout`
+    ("complete_fuzzy", false),
+    // Ask before running a pasted snippet of several lines
+    ("confirm_paste", false),
+    // Replace the prompt of an accepted line with the compact `TRANSIENT_PS1`, to keep the scrollback clean
+    ("transient_prompt", false),
+];
+
+/// The names of the shell options that are set with `set -o`, and the letters that stand for
+/// them in `set -e` and the like. They are all off by default.
+const SET_OPTIONS: &[(&str, Option<char>)] = &[
+    // Exit as soon as a command fails
+    ("errexit", Some('e')),
+    // Don't let `>` overwrite existing files, only `>|`
+    ("noclobber", Some('C')),
+    // Read commands without running them, to check a script for syntax errors
+    ("noexec", Some('n')),
+    // Treat expanding a variable that isn't set as an error
+    ("nounset", Some('u')),
+    // Give a pipeline the status of the last command in it that failed
+    ("pipefail", None),
+    // Print each command, after expansion, before running it
+    ("xtrace", Some('x')),
+];
+
+/// The shell options that are set with the `shopt` builtin, or with `set`
+#[derive(Clone)]
+pub struct Options {
+    values: BTreeMap<&'static str, bool>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            values: DEFAULTS.iter().copied().collect(),
+        }
+    }
+}
+
+impl Options {
+    /// The options of the `set` builtin, all off
+    pub fn for_set() -> Self {
+        Options {
+            values: SET_OPTIONS.iter().map(|(name, _)| (*name, false)).collect(),
+        }
+    }
+
+    /// The name of the `set` option a letter stands for, like `errexit` for `e`
+    pub fn name_for(letter: char) -> Option<&'static str> {
+        SET_OPTIONS
+            .iter()
+            .find(|(_, other)| *other == Some(letter))
+            .map(|(name, _)| *name)
+    }
+
+    /// The letters of the `set` options that are on, like `eu` after `set -eu`
+    pub fn letters(&self) -> String {
+        SET_OPTIONS
+            .iter()
+            .filter(|(name, _)| self.is_on(name))
+            .filter_map(|(_, letter)| *letter)
+            .collect()
+    }
+
+    /// Whether the option is on. Options that don't exist are off.
+    pub fn is_on(&self, name: &str) -> bool {
+        self.get(name).unwrap_or(false)
+    }
+
+    /// Whether the option is on, or `None` if there is no such option
+    pub fn get(&self, name: &str) -> Option<bool> {
+        self.values.get(name).copied()
+    }
+
+    /// Turn the option on or off. Returns an error if there is no such option.
+    pub fn set(&mut self, name: &str, on: bool) -> Result<(), String> {
+        match self.values.get_mut(name) {
+            Some(value) => {
+                *value = on;
+                Ok(())
+            }
+            None => Err(format!("{}: invalid shell option name", name)),
+        }
+    }
+
+    /// All options and whether they are on, sorted by name
+    pub fn all(&self) -> impl Iterator<Item = (&str, bool)> {
+        self.values.iter().map(|(name, on)| (*name, *on))
+    }
+}
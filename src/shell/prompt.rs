@@ -0,0 +1,340 @@
+// Library
+use std::{io, time::Duration};
+
+use crate::helpers::{
+    ansi::{END_IGNORE, START_IGNORE},
+    dirs, git, home,
+    sys::{self, LocalTime},
+    vars,
+};
+
+// ------
+// PROMPT
+// ------
+
+/// The names of the days of the week and the months, for `\d`
+const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// How long `\g` waits for `git status` by default, unless `GIT_PROMPT_TIMEOUT` (in milliseconds) says otherwise
+const GIT_PROMPT_TIMEOUT: u64 = 300;
+
+impl super::Shell {
+    /// The prompt for the next line: `PS1` with its escapes, variables and command substitutions
+    /// expanded, or `$ ` if `PS1` isn't set
+    pub(super) fn prompt(&mut self) -> String {
+        match vars::get("PS1") {
+            Some(ps1) => self.expand_prompt(&ps1),
+            None => String::from("$ "),
+        }
+    }
+
+    /// The prompt on the right of the input line: `RPROMPT` expanded like `PS1`, or nothing
+    pub(super) fn right_prompt(&mut self) -> String {
+        match vars::get("RPROMPT") {
+            Some(rprompt) => self.expand_prompt(&rprompt),
+            None => String::new(),
+        }
+    }
+
+    /// The compact prompt that replaces the full one once a line is accepted, if the
+    /// `transient_prompt` option is on: `TRANSIENT_PS1` expanded like `PS1`, or `\$ `
+    pub(super) fn transient_prompt(&mut self) -> Option<String> {
+        if self.options.get("transient_prompt") != Some(true) {
+            return None;
+        }
+        let ps1 = vars::get("TRANSIENT_PS1").unwrap_or_else(|| String::from("\\$ "));
+        Some(self.expand_prompt(&ps1))
+    }
+
+    /// Expands a prompt string in a single pass, so that the expansions aren't expanded again.
+    /// Non-printing text between `\[` and `\]` is marked with [`START_IGNORE`] and [`END_IGNORE`].
+    /// ```sh
+    /// $ PS1='\u@\h:\w\$ '
+    /// ```
+    /// ```output
+    /// user@host:~/src$
+    /// ```
+    pub(super) fn expand_prompt(&mut self, ps1: &str) -> String {
+        let chars: Vec<char> = ps1.chars().collect();
+        let mut prompt = String::new();
+        let mut i = 0;
+
+        while i < chars.len() {
+            let c = chars[i];
+            i += 1;
+            match c {
+                '\\' if i < chars.len() => {
+                    // Up to three octal digits stand for a character, like `\033` for escape
+                    let digits = chars[i..]
+                        .iter()
+                        .take(3)
+                        .take_while(|c| ('0'..='7').contains(c))
+                        .count();
+                    if digits > 0 {
+                        let octal: String = chars[i..i + digits].iter().collect();
+                        let code = u32::from_str_radix(&octal, 8).unwrap_or_default();
+                        prompt.extend(char::from_u32(code));
+                        i += digits;
+                        continue;
+                    }
+                    prompt.push_str(&self.prompt_escape(chars[i]));
+                    i += 1;
+                }
+                '$' => i += self.prompt_expansion(&chars[i..], &mut prompt),
+                c => prompt.push(c),
+            }
+        }
+        prompt
+    }
+
+    /// The text a backslash escape in the prompt stands for
+    fn prompt_escape(&self, c: char) -> String {
+        let time = || LocalTime::now();
+        match c {
+            'u' => sys::username().unwrap_or_default(),
+            'h' => sys::hostname()
+                .map(|host| host.split('.').next().unwrap_or_default().to_owned())
+                .unwrap_or_default(),
+            'H' => sys::hostname().unwrap_or_default(),
+            'w' => working_directory(false),
+            'W' => working_directory(true),
+            'g' => git_status(),
+            '$' => match sys::uid() {
+                Some(0) => String::from("#"),
+                _ => String::from("$"),
+            },
+            't' => {
+                let t = time();
+                format!("{:02}:{:02}:{:02}", t.hour, t.minute, t.second)
+            }
+            'T' => {
+                let t = time();
+                format!("{:02}:{:02}:{:02}", twelve_hour(t.hour), t.minute, t.second)
+            }
+            '@' => {
+                let t = time();
+                let meridiem = if t.hour < 12 { "AM" } else { "PM" };
+                format!("{:02}:{:02} {}", twelve_hour(t.hour), t.minute, meridiem)
+            }
+            'A' => {
+                let t = time();
+                format!("{:02}:{:02}", t.hour, t.minute)
+            }
+            'd' => {
+                let t = time();
+                format!(
+                    "{} {} {:02}",
+                    WEEKDAYS[t.weekday as usize % 7],
+                    MONTHS[(t.month as usize + 11) % 12],
+                    t.day
+                )
+            }
+            'j' => self.jobs.len().to_string(),
+            '?' => self.status.to_string(),
+            'C' => self.last_duration(),
+            's' => std::env::args()
+                .next()
+                .and_then(|arg0| {
+                    let name = std::path::Path::new(&arg0).file_name()?;
+                    Some(name.to_string_lossy().into_owned())
+                })
+                .unwrap_or_default(),
+            'n' => String::from("\n"),
+            'e' => String::from("\u{1b}"),
+            'a' => String::from("\u{7}"),
+            '\\' => String::from("\\"),
+            '[' => START_IGNORE.to_string(),
+            ']' => END_IGNORE.to_string(),
+            // Unknown escapes are kept as they are
+            c => format!("\\{}", c),
+        }
+    }
+
+    /// Expands the variable or command substitution after a `$` in the prompt into the prompt.
+    /// Returns the number of characters that were used up after the `$`.
+    fn prompt_expansion(&mut self, rest: &[char], prompt: &mut String) -> usize {
+        match rest.first() {
+            Some('(') => {
+                let Some(end) = matching_paren(rest) else {
+                    prompt.push('$');
+                    return 0;
+                };
+                let command: String = rest[1..end].iter().collect();
+                // A failing command substitution leaves nothing behind, like its output would
+                if let Ok(output) = self.prompt_substitution(&command) {
+                    prompt.push_str(&output);
+                }
+                end + 1
+            }
+            Some('{') => {
+                let Some(end) = rest.iter().position(|&c| c == '}') else {
+                    prompt.push('$');
+                    return 0;
+                };
+                let name: String = rest[1..end].iter().collect();
+                prompt.push_str(&self.prompt_variable(&name));
+                end + 1
+            }
+            Some('?') => {
+                prompt.push_str(&self.status.to_string());
+                1
+            }
+            Some(c) if c.is_ascii_digit() => {
+                prompt.push_str(&vars::get(&c.to_string()).unwrap_or_default());
+                1
+            }
+            Some(c) if c.is_alphabetic() || *c == '_' => {
+                let length = rest
+                    .iter()
+                    .take_while(|c| c.is_alphanumeric() || **c == '_')
+                    .count();
+                let name: String = rest[..length].iter().collect();
+                prompt.push_str(&vars::get(&name).unwrap_or_default());
+                length
+            }
+            _ => {
+                prompt.push('$');
+                0
+            }
+        }
+    }
+
+    /// Runs a command substitution in the prompt. Drawing the prompt leaves the shell as it was:
+    /// the exit status and the `set` options are put back afterwards, and traps, `set -x` and
+    /// `set -e` are off while the command runs.
+    fn prompt_substitution(&mut self, command: &str) -> io::Result<String> {
+        let status = self.status;
+        let set_options = self.set_options.clone();
+        for name in ["errexit", "xtrace"] {
+            self.set_options.set(name, false).ok();
+        }
+        let result = self.without_traps(|shell| shell.capture_output(command));
+        self.set_options = set_options;
+        self.status = status;
+        result
+    }
+
+    /// The value of a variable in the prompt, including the exit status `?`
+    fn prompt_variable(&self, name: &str) -> String {
+        match name {
+            "?" => self.status.to_string(),
+            name => vars::get(name).unwrap_or_default(),
+        }
+    }
+}
+
+/// The index of the parenthesis that closes the one at the start, skipping over quoted text
+fn matching_paren(chars: &[char]) -> Option<usize> {
+    let mut depth = 0;
+    let mut quote = None;
+    for (i, &c) in chars.iter().enumerate() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '(') => depth += 1,
+            (None, ')') => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The current directory as `PWD` has it, with the home directory shortened to `~`. With
+/// `basename`, only the last part of it is kept, unless it is the home directory itself.
+fn working_directory(basename: bool) -> String {
+    let Some(cwd) = dirs::logical() else {
+        return String::new();
+    };
+    if let Some(rest) = home::get().and_then(|home| cwd.strip_prefix(home).ok()) {
+        if rest.as_os_str().is_empty() {
+            return String::from("~");
+        }
+        if !basename {
+            return format!("~/{}", rest.display());
+        }
+    }
+    match cwd.file_name() {
+        Some(name) if basename => name.to_string_lossy().into_owned(),
+        _ => cwd.display().to_string(),
+    }
+}
+
+/// The git branch and status of the current directory, for `\g`. Slow repositories
+/// leave out the status rather than hold up the prompt.
+fn git_status() -> String {
+    let Ok(cwd) = std::env::current_dir() else {
+        return String::new();
+    };
+    let timeout = vars::get("GIT_PROMPT_TIMEOUT")
+        .and_then(|ms| ms.parse().ok())
+        .unwrap_or(GIT_PROMPT_TIMEOUT);
+    git::prompt(&cwd, Duration::from_millis(timeout))
+}
+
+/// The hour on a 12-hour clock
+fn twelve_hour(hour: u32) -> u32 {
+    match hour % 12 {
+        0 => 12,
+        hour => hour,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shell::Shell;
+
+    #[test]
+    fn test_escapes() {
+        let mut shell = Shell {
+            status: 3,
+            ..Shell::default()
+        };
+        assert_eq!(
+            shell.expand_prompt(r"\? $? \\ \e[1m \101 \q"),
+            "3 3 \\ \u{1b}[1m A \\q"
+        );
+        assert_eq!(
+            shell.expand_prompt(r"\[\e[32m\]> "),
+            "\u{1}\u{1b}[32m\u{2}> "
+        );
+    }
+
+    #[test]
+    fn test_expansions() {
+        let mut shell = Shell::default();
+        vars::set("PROMPT_TEST", "value");
+        assert_eq!(
+            shell.expand_prompt("$PROMPT_TEST ${PROMPT_TEST}! $(echo 'a)b') $ "),
+            "value value! a)b $ "
+        );
+        vars::unset("PROMPT_TEST");
+    }
+
+    #[test]
+    fn test_substitutions_leave_the_shell_alone() {
+        let mut shell = Shell::default();
+        shell.run_captured("trap 'echo trapped' DEBUG ERR; set -ex");
+        shell.status = 3;
+        assert_eq!(shell.expand_prompt("[$(echo a; false; echo b)]"), "[a\nb]");
+        assert_eq!(shell.status, 3);
+        assert!(shell.is_set("errexit") && shell.is_set("xtrace"));
+        assert!(!shell.exiting);
+    }
+
+    #[test]
+    fn test_matching_paren() {
+        let chars: Vec<char> = "(a (b) ')' c) d".chars().collect();
+        assert_eq!(matching_paren(&chars), Some(12));
+        assert_eq!(matching_paren(&['(', 'a']), None);
+    }
+}
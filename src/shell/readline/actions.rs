@@ -0,0 +1,197 @@
+use std::io::Write;
+
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEventKind},
+    terminal, QueueableCommand,
+};
+
+use super::{keymap::Action, Signal};
+
+// -------
+// ACTIONS
+// -------
+
+impl super::ReadLine {
+    /// Perform the named editor action.
+    /// Returns a [`Signal`] if the action completes the line.
+    pub(super) fn perform(&mut self, action: Action) -> std::io::Result<Option<Signal>> {
+        match action {
+            Action::AcceptLine => {
+                // A multi-line paste only runs once the user agrees to it, if they asked for that
+                let lines = self.buffer.lines().filter(|l| !l.trim().is_empty()).count();
+                if self.confirm_paste
+                    && lines > 1
+                    && !self.confirm(&format!("Run {} lines?", lines))?
+                {
+                    return Ok(None);
+                }
+                // On Enter, finish the line (from the end, if the cursor was moved back),
+                // and take the suggestion off the screen
+                if let Some(transient) = self.transient_prompt.clone() {
+                    self.cursor = self.buffer.len();
+                    self.draw_transient(&transient)?;
+                } else if self.cursor != self.buffer.len() || self.suggestion.is_some() {
+                    self.finish_line()?;
+                }
+                writeln!(self.writer)?;
+                self.writer.flush()?;
+                // Remember the line, unless it is blank or the same as the one before
+                if !self.buffer.trim().is_empty() && self.history.last() != Some(&self.buffer) {
+                    self.history.push(self.buffer.clone());
+                }
+                return Ok(Some(Signal::Success(self.buffer.clone())));
+            }
+            Action::Interrupt => {
+                // Leave the discarded line on the screen, marked with `^C`
+                self.finish_line()?;
+                writeln!(self.writer, "^C")?;
+                self.writer.flush()?;
+                return Ok(Some(Signal::Interrupt));
+            }
+            Action::EndOfFile if self.buffer.is_empty() => {
+                writeln!(self.writer)?;
+                self.writer.flush()?;
+                return Ok(Some(Signal::EndOfFile));
+            }
+            // On a non-empty line, Ctrl+D deletes forward instead
+            Action::EndOfFile => self.delete_range(self.cursor, self.next_char(), false),
+            Action::ClearScreen => {
+                self.writer
+                    .queue(terminal::Clear(terminal::ClearType::All))?
+                    .queue(cursor::MoveTo(0, 0))?;
+            }
+            Action::Abort => {
+                write!(self.writer, "\x07")?;
+                self.writer.flush()?;
+            }
+            // At the end of the line, moving forward accepts the suggestion
+            Action::ForwardChar | Action::EndOfLine if self.at_suggestion() => {
+                self.accept_suggestion(false)
+            }
+            Action::ForwardWord if self.at_suggestion() => self.accept_suggestion(true),
+            Action::BackwardChar => self.move_to(self.prev_char()),
+            Action::ForwardChar => self.move_to(self.next_char()),
+            Action::BackwardWord => self.move_to(self.prev_word(is_word_char)),
+            Action::ForwardWord => self.move_to(self.next_word(is_word_char)),
+            Action::BeginningOfLine => self.move_to(0),
+            Action::EndOfLine => self.move_to(self.buffer.len()),
+            Action::BackwardDeleteChar => self.delete_range(self.prev_char(), self.cursor, false),
+            Action::DeleteChar => self.delete_range(self.cursor, self.next_char(), false),
+            Action::KillLine => self.delete_range(self.cursor, self.buffer.len(), true),
+            Action::UnixLineDiscard => self.delete_range(0, self.cursor, true),
+            Action::KillWord => self.delete_range(self.cursor, self.next_word(is_word_char), true),
+            Action::BackwardKillWord => {
+                self.delete_range(self.prev_word(is_word_char), self.cursor, true)
+            }
+            Action::UnixWordRubout => {
+                self.delete_range(self.prev_word(|c| !c.is_whitespace()), self.cursor, true)
+            }
+            Action::Yank => {
+                let text = self.kill_buffer.clone();
+                self.buffer.insert_str(self.cursor, &text);
+                self.cursor += text.len();
+            }
+            Action::TransposeChars => self.transpose_chars(),
+            Action::Complete => return self.handle_tab_completion(),
+        }
+
+        self.refresh_line()?;
+        Ok(None)
+    }
+
+    /// Move the cursor to the given byte offset
+    fn move_to(&mut self, position: usize) {
+        self.cursor = position;
+    }
+
+    /// The byte offset of the character before the cursor
+    fn prev_char(&self) -> usize {
+        self.buffer[..self.cursor]
+            .char_indices()
+            .next_back()
+            .map_or(0, |(i, _)| i)
+    }
+
+    /// The byte offset of the character after the cursor
+    fn next_char(&self) -> usize {
+        self.buffer[self.cursor..]
+            .chars()
+            .next()
+            .map_or(self.cursor, |c| self.cursor + c.len_utf8())
+    }
+
+    /// The byte offset of the start of the current or previous word
+    fn prev_word(&self, is_word: impl Fn(char) -> bool) -> usize {
+        let before = &self.buffer[..self.cursor];
+        // Skip any separators directly behind the cursor, and then the word itself
+        let word_end = before.trim_end_matches(|c| !is_word(c)).len();
+        before[..word_end].trim_end_matches(is_word).len()
+    }
+
+    /// The byte offset of the end of the current or next word
+    fn next_word(&self, is_word: impl Fn(char) -> bool) -> usize {
+        let after = &self.buffer[self.cursor..];
+        // Skip any separators directly in front of the cursor, and then the word itself
+        let word_start = after.len() - after.trim_start_matches(|c| !is_word(c)).len();
+        let rest = &after[word_start..];
+        self.cursor + word_start + (rest.len() - rest.trim_start_matches(is_word).len())
+    }
+
+    /// Delete the text between the two byte offsets, optionally saving it for `yank`
+    fn delete_range(&mut self, start: usize, end: usize, kill: bool) {
+        if start >= end {
+            return;
+        }
+        let removed: String = self.buffer.drain(start..end).collect();
+        if kill {
+            self.kill_buffer = removed;
+        }
+        self.cursor = start;
+    }
+
+    /// Ask a yes-or-no question below the line, and wait for the answer. The question is
+    /// cleared again afterwards, leaving the cursor on the line.
+    fn confirm(&mut self, question: &str) -> std::io::Result<bool> {
+        write!(self.writer, "\r\n{} [y/N] ", question)?;
+        self.writer.flush()?;
+        let answer = loop {
+            if let Event::Key(evt) = event::read()? {
+                if evt.kind == KeyEventKind::Press {
+                    break matches!(evt.code, KeyCode::Char('y' | 'Y'));
+                }
+            }
+        };
+        self.writer
+            .queue(cursor::MoveToColumn(0))?
+            .queue(terminal::Clear(terminal::ClearType::CurrentLine))?
+            .queue(cursor::MoveToPreviousLine(1))?;
+        self.draw_line()?;
+        Ok(answer)
+    }
+
+    /// Swap the character before the cursor with the one under it, and move the cursor forward.
+    /// At the end of the line, the last two characters are swapped instead.
+    fn transpose_chars(&mut self) {
+        let original = self.cursor;
+        if self.cursor == self.buffer.len() {
+            self.cursor = self.prev_char();
+        }
+        let (start, end) = (self.prev_char(), self.next_char());
+        if start == self.cursor {
+            // There are not enough characters on either side of the cursor
+            self.cursor = original;
+            return;
+        }
+        let mut chars: Vec<char> = self.buffer[start..end].chars().collect();
+        chars.swap(0, 1);
+        self.buffer
+            .replace_range(start..end, &chars.into_iter().collect::<String>());
+        self.cursor = end;
+    }
+}
+
+/// Characters that make up a word for the word-wise movement and kill commands
+pub(super) fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
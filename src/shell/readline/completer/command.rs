@@ -0,0 +1,31 @@
+// Library
+use super::{escape, Candidate, Completer, Context, Position};
+use crate::helpers;
+
+// ------------------
+// COMMAND COMPLETION
+// ------------------
+
+/// A [`Completer`] for the names of the executables in the `PATH`.
+///
+/// The names come from the shared hash table in [`helpers::path`], which is kept up to date
+/// as programs are installed and `PATH` changes.
+pub struct CommandCompleter;
+
+impl Completer for CommandCompleter {
+    fn complete(&self, ctx: &Context) -> Vec<Candidate> {
+        // Commands given with a path are completed as files instead
+        if ctx.position != Position::Command || ctx.value.contains('/') {
+            return Vec::new();
+        }
+        helpers::path::get_executables()
+            .into_iter()
+            .filter_map(|name| {
+                Some(Candidate {
+                    rank: ctx.rank(&name)?,
+                    ..Candidate::new(ctx, escape(&name, ctx.quote()), name)
+                })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,86 @@
+// Library
+use super::{escape, open_quote, Candidate, Completer, Context, Position};
+use crate::parser::Parser;
+
+// -------------------
+// FILENAME COMPLETION
+// -------------------
+
+/// A [`Completer`] for file and directory paths.
+///
+/// Paths are completed for arguments, redirection targets and commands given with a path.
+/// The arguments of `cd` are only completed with directories.
+///
+/// The word under the cursor may contain quotes, escapes, `~` and `$VAR`s. Everything up to
+/// the last `/` is kept as it was typed, and only the last component of the path is completed.
+/// Directories are completed with a trailing `/`.
+pub struct FilenameCompleter;
+
+impl Completer for FilenameCompleter {
+    fn complete(&self, ctx: &Context) -> Vec<Candidate> {
+        let dirs_only = match &ctx.position {
+            Position::Command if ctx.value.contains('/') => false,
+            Position::Argument { command, .. } => command == "cd",
+            Position::Redirection => false,
+            _ => return Vec::new(),
+        };
+        let input = ctx.word;
+
+        // Split the word into the directory part and the part of the filename typed so far
+        let (dir_raw, file_raw) = input.split_at(input.rfind('/').map_or(0, |i| i + 1));
+
+        // Expand the word the same way the parser would when the command is run
+        let dir = expand_word(dir_raw);
+        let full = expand_word(input);
+        let file_prefix = full.strip_prefix(dir.as_str()).unwrap_or_default();
+
+        // The filenames have to be quoted the same way as the text in front of them
+        let (quote, opening) = match open_quote(&ctx.line[..ctx.span.start + dir_raw.len()]) {
+            Some(q) => (Some(q), ""),
+            None => match file_raw.chars().next() {
+                Some(q @ ('"' | '\'')) => (Some(q), &file_raw[..1]),
+                _ => (None, ""),
+            },
+        };
+
+        let search_dir = if dir.is_empty() { "." } else { dir.as_str() };
+        let Ok(entries) = std::fs::read_dir(search_dir) else {
+            return Vec::new();
+        };
+
+        let mut completions: Vec<Candidate> = entries
+            .filter_map(Result::ok)
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                // Hidden files are only suggested if the user asked for them
+                if name.starts_with('.') && !file_prefix.starts_with('.') {
+                    return None;
+                }
+                let rank = ctx.matching.rank(file_prefix, &name)?;
+                let is_dir = entry.path().is_dir();
+                if dirs_only && !is_dir {
+                    return None;
+                }
+                let suffix = if is_dir { "/" } else { "" };
+                let replacement =
+                    format!("{}{}{}{}", dir_raw, opening, escape(&name, quote), suffix);
+                Some(Candidate {
+                    append_whitespace: !is_dir,
+                    rank,
+                    ..Candidate::new(ctx, replacement, format!("{}{}", name, suffix))
+                })
+            })
+            .collect();
+        completions.sort_by(|a, b| a.replacement.cmp(&b.replacement));
+        completions
+    }
+}
+
+/// Expands a word like the parser would, joining the pieces if it contains spaces
+fn expand_word(word: &str) -> String {
+    Parser::tokenize(word)
+        .into_iter()
+        .map(|t| t.value)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -0,0 +1,136 @@
+// Library
+use std::cmp::Reverse;
+
+// --------
+// MATCHING
+// --------
+
+/// Which kinds of matches count when completing. Prefix matches always count.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Matching {
+    /// Ignore the case of letters
+    pub ignore_case: bool,
+    /// Match the text anywhere in a candidate
+    pub substring: bool,
+    /// Match the characters of the text in order, with anything in between
+    pub fuzzy: bool,
+}
+
+/// How well a candidate matches the text being completed. Better matches compare greater,
+/// and a prefix match always beats a substring match, which always beats a fuzzy match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Rank {
+    /// The characters appear in order. The score is higher the closer together they are,
+    /// and the more of them start a word
+    Fuzzy(i32),
+    /// The text appears in the candidate. Earlier is better
+    Substring(Reverse<usize>),
+    /// The candidate starts with the text, ignoring case
+    PrefixIgnoreCase,
+    /// The candidate starts with the text
+    Prefix,
+}
+
+impl Rank {
+    /// Whether both ranks are the same kind of match
+    pub fn same_kind(&self, other: &Rank) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+}
+
+impl Matching {
+    /// Work out how well the candidate matches the pattern, or `None` if it doesn't
+    pub fn rank(&self, pattern: &str, candidate: &str) -> Option<Rank> {
+        if candidate.starts_with(pattern) {
+            return Some(Rank::Prefix);
+        }
+
+        let fold = |s: &str| match self.ignore_case {
+            true => s.to_lowercase(),
+            false => s.to_owned(),
+        };
+        let (pattern, candidate) = (fold(pattern), fold(candidate));
+
+        if self.ignore_case && candidate.starts_with(&pattern) {
+            return Some(Rank::PrefixIgnoreCase);
+        }
+        if self.substring {
+            if let Some(position) = candidate.find(&pattern) {
+                return Some(Rank::Substring(Reverse(position)));
+            }
+        }
+        if self.fuzzy {
+            return fuzzy_score(&pattern, &candidate).map(Rank::Fuzzy);
+        }
+        None
+    }
+}
+
+/// Scores a subsequence match of the pattern in the candidate, or `None` if the characters of
+/// the pattern don't all appear in order.
+fn fuzzy_score(pattern: &str, candidate: &str) -> Option<i32> {
+    let chars: Vec<char> = candidate.chars().collect();
+    let mut score = 0;
+    let mut next = 0;
+    let mut previous: Option<usize> = None;
+
+    for p in pattern.chars() {
+        let found = next + chars[next..].iter().position(|&c| c == p)?;
+        // Matches right after the previous one, or at the start of a word, are worth more
+        if previous.is_some_and(|i| i + 1 == found) {
+            score += 5;
+        }
+        if found == 0 || matches!(chars[found - 1], '-' | '_' | '.' | '/' | ' ') {
+            score += 3;
+        }
+        // Skipped characters count against the match
+        score -= (found - next) as i32;
+        previous = Some(found);
+        next = found + 1;
+    }
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: Matching = Matching {
+        ignore_case: true,
+        substring: true,
+        fuzzy: true,
+    };
+
+    #[test]
+    fn test_prefix_only_by_default() {
+        let matching = Matching::default();
+        assert_eq!(matching.rank("gi", "git"), Some(Rank::Prefix));
+        assert_eq!(matching.rank("Gi", "git"), None);
+        assert_eq!(matching.rank("it", "git"), None);
+    }
+
+    #[test]
+    fn test_kinds_of_matches() {
+        assert_eq!(ALL.rank("RE", "readme"), Some(Rank::PrefixIgnoreCase));
+        assert_eq!(
+            ALL.rank("out", "checkout"),
+            Some(Rank::Substring(Reverse(5)))
+        );
+        assert!(matches!(
+            ALL.rank("gco", "git-checkout-object"),
+            Some(Rank::Fuzzy(_))
+        ));
+        assert_eq!(ALL.rank("xyz", "git-checkout-object"), None);
+    }
+
+    #[test]
+    fn test_ranking() {
+        let prefix = ALL.rank("gc", "gcc").unwrap();
+        let fuzzy = ALL.rank("gc", "git-commit").unwrap();
+        let loose = ALL.rank("gc", "tagcloud-generator").unwrap();
+        assert!(prefix > fuzzy);
+        // A substring match beats any fuzzy match
+        assert!(loose > fuzzy);
+        assert!(ALL.rank("gco", "git-checkout") > ALL.rank("gco", "gnu-fancy-cow"));
+    }
+}
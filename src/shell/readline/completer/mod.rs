@@ -0,0 +1,403 @@
+// Library
+use std::ops::Range;
+
+use crate::parser::{Parser, Token};
+
+// Modules
+mod command;
+pub use command::CommandCompleter;
+mod filename;
+pub use filename::FilenameCompleter;
+mod matcher;
+pub use matcher::{Matching, Rank};
+mod programmable;
+pub use programmable::CompSpec;
+mod variable;
+pub use variable::VariableCompleter;
+
+// ---------
+// COMPLETER
+// ---------
+
+/// A trait for generating completion suggestions based on current input
+pub trait Completer {
+    /// Returns a list of possible completions for the text under the cursor.
+    /// Completers should only return candidates that make sense at the [`Context::position`].
+    fn complete(&self, ctx: &Context) -> Vec<Candidate>;
+}
+
+/// A completion suggestion
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+    /// The text that replaces the span, quoted and escaped as needed
+    pub replacement: String,
+    /// The byte range of the line that is replaced. Usually [`Context::span`]
+    pub span: Range<usize>,
+    /// The text to show when the candidates are listed
+    pub display: String,
+    /// A short description of the candidate, e.g. what a command does
+    pub description: Option<String>,
+    /// Whether the completion is finished once inserted, and should be followed by a space.
+    /// Directories, for example, aren't, because the user will likely continue with a file inside.
+    pub append_whitespace: bool,
+    /// How well the candidate matches the text being completed
+    pub rank: Rank,
+}
+
+impl Candidate {
+    /// A candidate that replaces the text being completed with the given text
+    pub fn new(ctx: &Context, replacement: String, display: String) -> Self {
+        Candidate {
+            replacement,
+            span: ctx.span.clone(),
+            display,
+            description: None,
+            append_whitespace: true,
+            rank: Rank::Prefix,
+        }
+    }
+}
+
+// -------
+// CONTEXT
+// -------
+
+/// Where the text being completed appears on the command line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Position {
+    /// The name of the command to run
+    Command,
+    /// An argument to a command. The index of the first argument is 1
+    Argument { command: String, index: usize },
+    /// The filename after a redirection operator like `>` or `2>>`
+    Redirection,
+    /// The name of a variable after a `$` or `${`
+    Variable,
+}
+
+/// Everything a [`Completer`] needs to know about the text being completed
+#[derive(Debug)]
+pub struct Context<'a> {
+    /// The full line being edited
+    pub line: &'a str,
+    /// The position of the cursor as a byte offset into the line
+    pub cursor: usize,
+    /// The byte range of the text being completed. It always ends at the cursor
+    pub span: Range<usize>,
+    /// The text being completed, as it was typed (quotes and escapes included)
+    pub word: &'a str,
+    /// The text being completed with quotes and escapes removed and variables expanded.
+    /// For [`Position::Variable`] it is the part of the variable name typed so far
+    pub value: String,
+    /// All the words on the line, as they were typed
+    pub words: Vec<&'a str>,
+    /// The index of the word being completed in `words`
+    pub index: usize,
+    /// Where the text being completed appears on the command line
+    pub position: Position,
+    /// Which kinds of matches count, besides prefix matches
+    pub matching: Matching,
+}
+
+impl<'a> Context<'a> {
+    /// Work out what is being completed at the cursor
+    pub fn new(line: &'a str, cursor: usize) -> Self {
+        // Only the command the cursor is in matters, which starts after the last `;`
+        let mut tokens = Parser::tokenize(&line[..cursor]);
+        if let Some(separator) = tokens.iter().rposition(|t| is_separator(t, line)) {
+            tokens.drain(..=separator);
+        }
+
+        // Find the word under the cursor. If the cursor isn't touching a word, complete a new one.
+        let (start, index) = match tokens.last() {
+            Some(token) if token.span.end == cursor => (token.span.start, tokens.len() - 1),
+            _ => (cursor, tokens.len()),
+        };
+
+        // All words of the command, including the ones after the cursor.
+        // The word under the cursor is included in full, even though only the part before the
+        // cursor is completed.
+        let rest = Parser::tokenize(&line[cursor..]);
+        let mut rest = rest
+            .iter()
+            .take_while(|t| !is_separator(t, &line[cursor..]))
+            .peekable();
+        let end = match rest.peek() {
+            Some(t) if t.span.start == 0 => cursor + rest.next().unwrap().span.end,
+            _ => cursor,
+        };
+        let mut words: Vec<&str> = tokens[..index]
+            .iter()
+            .map(|t| &line[t.span.clone()])
+            .collect();
+        words.push(&line[start..end]);
+        words.extend(rest.map(|t| &line[cursor + t.span.start..cursor + t.span.end]));
+
+        let mut ctx = Context {
+            line,
+            cursor,
+            span: start..cursor,
+            word: &line[start..cursor],
+            value: tokens
+                .get(index)
+                .map(|t| t.value.clone())
+                .unwrap_or_default(),
+            words,
+            index,
+            position: position_of(line, &tokens, index),
+            matching: Matching::default(),
+        };
+
+        // A variable name being typed at the end of the word takes priority
+        if let Some(dollar) = variable_start(ctx.word) {
+            let name = ctx.word[dollar..].trim_start_matches(['$', '{']);
+            ctx.value = name.to_string();
+            ctx.span = start + dollar..cursor;
+            ctx.word = &line[ctx.span.clone()];
+            ctx.position = Position::Variable;
+        }
+
+        ctx
+    }
+
+    /// Use the given kinds of matches
+    pub fn with_matching(mut self, matching: Matching) -> Self {
+        self.matching = matching;
+        self
+    }
+
+    /// Work out how well the candidate matches the text being completed,
+    /// or `None` if it doesn't match at all
+    pub fn rank(&self, candidate: &str) -> Option<Rank> {
+        self.matching.rank(&self.value, candidate)
+    }
+
+    /// The quote that is open at the start of the text being completed, if any
+    pub fn quote(&self) -> Option<char> {
+        open_quote(&self.line[..self.span.start])
+    }
+}
+
+/// Work out the position of the word at the given index
+fn position_of(line: &str, tokens: &[Token], index: usize) -> Position {
+    if index == 0 {
+        return Position::Command;
+    }
+    let previous = &tokens[index - 1];
+    if previous.plain(line).is_some_and(is_redirection) {
+        return Position::Redirection;
+    }
+
+    // Redirections and their targets don't count as arguments
+    let mut arguments = 0;
+    let mut skip = false;
+    for token in &tokens[1..index] {
+        if std::mem::take(&mut skip) {
+            continue;
+        }
+        if token.plain(line).is_some_and(is_redirection) {
+            skip = true;
+        } else {
+            arguments += 1;
+        }
+    }
+    Position::Argument {
+        command: tokens[0].value.clone(),
+        index: arguments + 1,
+    }
+}
+
+/// Whether the token separates two commands, or the stages of a pipeline
+fn is_separator(token: &Token, line: &str) -> bool {
+    matches!(token.plain(line), Some(";" | "&" | "|" | "&&" | "||"))
+}
+
+/// Whether the token is a redirection operator
+pub(super) fn is_redirection(token: &str) -> bool {
+    matches!(
+        token,
+        ">" | ">>" | ">|" | "1>" | "1>>" | "1>|" | "2>" | "2>>" | "2>|"
+    )
+}
+
+/// If the word ends with a variable name that is being typed (`$NA` or `${NA`), returns the
+/// byte offset of the `$`
+fn variable_start(word: &str) -> Option<usize> {
+    let dollar = word.rfind('$')?;
+    let name = word[dollar + 1..]
+        .strip_prefix('{')
+        .unwrap_or(&word[dollar + 1..]);
+    if !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        return None;
+    }
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    // The `$` must not be escaped, or inside single quotes
+    let backslashes = word[..dollar].len() - word[..dollar].trim_end_matches('\\').len();
+    if backslashes % 2 == 1 || open_quote(&word[..dollar]) == Some('\'') {
+        return None;
+    }
+    Some(dollar)
+}
+
+// -------
+// HELPERS
+// -------
+
+/// A basic implementation of the [`Completer`] trait that matches command names on the input prefix
+pub struct DefaultCompleter {
+    completions: Vec<(String, Option<String>)>,
+}
+
+impl DefaultCompleter {
+    /// Instantiate a new `DefaultCompleter` for commands that have a description
+    pub fn with_descriptions(completions: Vec<(String, String)>) -> Self {
+        DefaultCompleter {
+            completions: completions.into_iter().map(|(c, d)| (c, Some(d))).collect(),
+        }
+    }
+}
+
+impl Completer for DefaultCompleter {
+    fn complete(&self, ctx: &Context) -> Vec<Candidate> {
+        // Commands given with a path are completed as files instead
+        if ctx.position != Position::Command || ctx.value.contains('/') {
+            return Vec::new();
+        }
+        self.completions
+            .iter()
+            .filter_map(|(cmd, description)| {
+                Some(Candidate {
+                    description: description.clone(),
+                    rank: ctx.rank(cmd)?,
+                    ..Candidate::new(ctx, escape(cmd, ctx.quote()), cmd.clone())
+                })
+            })
+            .collect()
+    }
+}
+
+/// Returns the quote that is left open at the end of the given text, if any
+pub fn open_quote(text: &str) -> Option<char> {
+    Parser::tokenize(text).last().and_then(|t| t.open_quote)
+}
+
+/// Escapes the characters in the text that the parser would otherwise treat specially.
+/// Inside quotes only the characters that are special within that kind of quote are escaped.
+pub fn escape(text: &str, quote: Option<char>) -> String {
+    match quote {
+        Some('\'') => text.replace('\'', "'\\''"),
+        Some(_) => text.chars().fold(String::new(), |mut s, c| {
+            if matches!(c, '"' | '\\' | '$') {
+                s.push('\\');
+            }
+            s.push(c);
+            s
+        }),
+        None => text
+            .chars()
+            .enumerate()
+            .fold(String::new(), |mut s, (i, c)| {
+                let special = c.is_whitespace()
+                    || "\\'\"$`&;|<>()*?[]{}!#".contains(c)
+                    || (i == 0 && c == '~');
+                if special {
+                    s.push('\\');
+                }
+                s.push(c);
+                s
+            }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_context_command_position() {
+        let ctx = Context::new("ec", 2);
+        assert_eq!(ctx.position, Position::Command);
+        assert_eq!(ctx.span, 0..2);
+        assert_eq!(ctx.value, "ec");
+    }
+
+    #[test]
+    fn test_context_argument_position() {
+        let ctx = Context::new("cd src/ma", 9);
+        assert_eq!(
+            ctx.position,
+            Position::Argument {
+                command: "cd".into(),
+                index: 1
+            }
+        );
+        assert_eq!(ctx.word, "src/ma");
+    }
+
+    #[test]
+    fn test_context_new_word() {
+        let line = "echo hello ";
+        let ctx = Context::new(line, line.len());
+        assert_eq!(ctx.span, 11..11);
+        assert_eq!(ctx.words, vec!["echo", "hello", ""]);
+        assert_eq!(ctx.index, 2);
+    }
+
+    #[test]
+    fn test_context_words_after_cursor() {
+        let ctx = Context::new("echo he world", 7);
+        assert_eq!(ctx.words, vec!["echo", "he", "world"]);
+        assert_eq!(ctx.index, 1);
+
+        let ctx = Context::new("echo hello world", 7);
+        assert_eq!(ctx.words, vec!["echo", "hello", "world"]);
+        assert_eq!(ctx.word, "he");
+        assert_eq!(ctx.index, 1);
+    }
+
+    #[test]
+    fn test_context_after_semicolon() {
+        let ctx = Context::new("cd src; ec; ls", 10);
+        assert_eq!(ctx.position, Position::Command);
+        assert_eq!(ctx.words, vec!["ec"]);
+
+        crate::helpers::vars::set("COMPLETER_TEST_SEPARATOR", ";");
+        let line = "echo $COMPLETER_TEST_SEPARATOR ls";
+        let ctx = Context::new(line, line.len());
+        assert_eq!(ctx.words, vec!["echo", "$COMPLETER_TEST_SEPARATOR", "ls"]);
+    }
+
+    #[test]
+    fn test_context_redirection_position() {
+        let ctx = Context::new("echo hi > ou", 12);
+        assert_eq!(ctx.position, Position::Redirection);
+        let ctx = Context::new("echo hi > out x", 15);
+        assert_eq!(
+            ctx.position,
+            Position::Argument {
+                command: "echo".into(),
+                index: 2
+            }
+        );
+    }
+
+    #[test]
+    fn test_context_variable_position() {
+        let ctx = Context::new("echo \"${HO", 10);
+        assert_eq!(ctx.position, Position::Variable);
+        assert_eq!(ctx.word, "${HO");
+        assert_eq!(ctx.value, "HO");
+
+        let ctx = Context::new("echo '$HO", 9);
+        assert_ne!(ctx.position, Position::Variable);
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("my file", None), "my\\ file");
+        assert_eq!(escape("it's", Some('\'')), "it'\\''s");
+        assert_eq!(escape("a \"b\"", Some('"')), "a \\\"b\\\"");
+    }
+}
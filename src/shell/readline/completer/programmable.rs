@@ -0,0 +1,101 @@
+// Library
+use std::fmt;
+
+use crate::{commands, helpers};
+
+// -----------------------
+// PROGRAMMABLE COMPLETION
+// -----------------------
+
+/// A completion specification, registered for a command with the `complete` builtin.
+/// It describes where the candidates for the command's arguments come from.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CompSpec {
+    /// Complete directory names (`-d`)
+    pub directories: bool,
+    /// Complete file names (`-f`)
+    pub files: bool,
+    /// Complete command names (`-c`)
+    pub commands: bool,
+    /// Complete the words of this list (`-W`), split on whitespace
+    pub wordlist: Option<String>,
+    /// Run this shell function to generate the candidates (`-F`)
+    pub function: Option<String>,
+}
+
+impl CompSpec {
+    /// Generate the candidates that start with the given word.
+    /// The candidates from the function have to be generated by the shell.
+    pub fn generate(&self, word: &str) -> Vec<String> {
+        let mut candidates = Vec::new();
+        if self.files || self.directories {
+            candidates.extend(paths(word, !self.files));
+        }
+        if self.commands {
+            candidates.extend(command_names().into_iter().filter(|c| c.starts_with(word)));
+        }
+        if let Some(wordlist) = &self.wordlist {
+            candidates.extend(
+                wordlist
+                    .split_whitespace()
+                    .filter(|w| w.starts_with(word))
+                    .map(String::from),
+            );
+        }
+        candidates
+    }
+}
+
+impl fmt::Display for CompSpec {
+    /// Display the options of the spec, the way `complete -p` prints them
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut options = Vec::new();
+        if self.commands {
+            options.push(String::from("-c"));
+        }
+        if self.directories {
+            options.push(String::from("-d"));
+        }
+        if self.files {
+            options.push(String::from("-f"));
+        }
+        if let Some(wordlist) = &self.wordlist {
+            options.push(format!("-W '{}'", wordlist.replace('\'', "'\\''")));
+        }
+        if let Some(function) = &self.function {
+            options.push(format!("-F {}", function));
+        }
+        write!(f, "{}", options.join(" "))
+    }
+}
+
+/// The paths that start with the given word. Only directories are included if `dirs_only` is set.
+fn paths(word: &str, dirs_only: bool) -> Vec<String> {
+    let (dir, prefix) = word.split_at(word.rfind('/').map_or(0, |i| i + 1));
+    let search_dir = if dir.is_empty() { "." } else { dir };
+    let Ok(entries) = std::fs::read_dir(search_dir) else {
+        return Vec::new();
+    };
+
+    let mut paths: Vec<String> = entries
+        .filter_map(Result::ok)
+        .filter(|entry| !dirs_only || entry.path().is_dir())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        // Hidden files are only suggested if the user asked for them
+        .filter(|name| {
+            name.starts_with(prefix) && (!name.starts_with('.') || prefix.starts_with('.'))
+        })
+        .map(|name| format!("{}{}", dir, name))
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// The names of all builtins and the executables on the `PATH`
+fn command_names() -> Vec<String> {
+    let mut names: Vec<String> = commands::BUILTINS.iter().map(|b| b.to_string()).collect();
+    names.extend(helpers::path::get_executables());
+    names.sort();
+    names.dedup();
+    names
+}
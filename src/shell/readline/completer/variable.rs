@@ -0,0 +1,41 @@
+// Library
+use super::{Candidate, Completer, Context, Position};
+use crate::helpers::vars;
+
+// -------------------
+// VARIABLE COMPLETION
+// -------------------
+
+/// A [`Completer`] for the names of variables after a `$` or `${`.
+///
+/// The braces are closed when the name is completed.
+pub struct VariableCompleter;
+
+impl Completer for VariableCompleter {
+    fn complete(&self, ctx: &Context) -> Vec<Candidate> {
+        if ctx.position != Position::Variable {
+            return Vec::new();
+        }
+        let braced = ctx.word.starts_with("${");
+
+        let mut completions: Vec<Candidate> = vars::variables()
+            .into_iter()
+            .map(|(name, _)| name)
+            .filter_map(|name| Some((ctx.rank(&name)?, name)))
+            .map(|(rank, name)| {
+                let replacement = match braced {
+                    true => format!("${{{}}}", name),
+                    false => format!("${}", name),
+                };
+                Candidate {
+                    // The variable could be part of a longer word
+                    append_whitespace: false,
+                    rank,
+                    ..Candidate::new(ctx, replacement, name)
+                }
+            })
+            .collect();
+        completions.sort_by(|a, b| a.display.cmp(&b.display));
+        completions
+    }
+}
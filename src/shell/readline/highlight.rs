@@ -0,0 +1,255 @@
+use std::{
+    collections::{HashMap, HashSet},
+    iter,
+    path::Path,
+};
+
+use super::completer::is_redirection;
+use crate::{
+    commands::Command,
+    helpers::{
+        ansi::{Color, Colorable, Style},
+        vars,
+    },
+    parser::{FragmentKind, Parser},
+    shell::executor::is_assignment,
+};
+
+// -------------------
+// SYNTAX HIGHLIGHTING
+// -------------------
+
+/// What a part of the line is, which decides its color
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Highlight {
+    Plain,
+    /// A command that resolves to a function, builtin or program
+    Command,
+    /// A command that doesn't resolve to anything
+    Unknown,
+    Quoted,
+    Variable,
+    /// A `;`, or a brace around a function body
+    Operator,
+    Redirection,
+    Comment,
+}
+
+/// The highlight of every byte of the line, and whether it is part of an existing path
+type Styles = Vec<(Highlight, bool)>;
+
+/// Whether each command name resolves to a builtin or a program. The line is highlighted again
+/// on every key-press, so the names are only looked up once for each line that is read.
+pub(super) type Lookups = HashMap<String, bool>;
+
+/// Colors the line for display, in the styles of the [`Theme`]. Commands are looked up the same
+/// way they are when the line is run, with the names of the shell functions passed in.
+pub(super) fn highlight(line: &str, functions: &HashSet<String>, lookups: &mut Lookups) -> String {
+    if line.is_empty() {
+        return String::new();
+    }
+    let styles = classify(line, functions, lookups);
+    let theme = Theme::parse(&vars::get("HIGHLIGHT_COLORS").unwrap_or_default());
+
+    // Paint each run of bytes that look the same in one go
+    let mut output = String::new();
+    let mut start = 0;
+    let boundaries = line.char_indices().skip(1).map(|(i, _)| i);
+    for end in boundaries.chain(iter::once(line.len())) {
+        if end == line.len() || styles[end] != styles[start] {
+            output.push_str(&paint(&line[start..end], styles[start], &theme));
+            start = end;
+        }
+    }
+    output
+}
+
+/// Works out the highlight of every byte of the line
+fn classify(line: &str, functions: &HashSet<String>, lookups: &mut Lookups) -> Styles {
+    let mut styles = vec![(Highlight::Plain, false); line.len()];
+    let mut mark = |span: std::ops::Range<usize>, highlight: Highlight| {
+        for style in &mut styles[span] {
+            style.0 = highlight;
+        }
+    };
+
+    // Quotes and variables are found inside words, and variables inside quotes win
+    for fragment in Parser::fragments(line) {
+        let highlight = match fragment.kind {
+            FragmentKind::Quoted | FragmentKind::Escape => Highlight::Quoted,
+            FragmentKind::Variable => Highlight::Variable,
+            FragmentKind::Comment => Highlight::Comment,
+        };
+        mark(fragment.span, highlight);
+    }
+
+    // Whole words are colored by where they are in the command
+    let mut paths = Vec::new();
+    let mut command_start = true;
+    for token in Parser::tokenize(line) {
+        let operator = token.plain(line).unwrap_or_default();
+        if matches!(operator, ";" | "&" | "|" | "&&" | "||")
+            || (command_start && matches!(operator, "{" | "}"))
+        {
+            mark(token.span, Highlight::Operator);
+            command_start = true;
+        } else if is_redirection(operator) {
+            mark(token.span, Highlight::Redirection);
+        } else if command_start && is_assignment(&token.value) {
+            // Assignments in front of a command don't take its place
+        } else if command_start && operator.ends_with("()") {
+            // The name of a function being defined, followed by its body
+            mark(token.span, Highlight::Command);
+        } else if command_start {
+            let found = functions.contains(&token.value)
+                || *lookups
+                    .entry(token.value.clone())
+                    .or_insert_with(|| Command::exists(&token.value));
+            let highlight = match found {
+                true => Highlight::Command,
+                false => Highlight::Unknown,
+            };
+            mark(token.span, highlight);
+            command_start = false;
+        } else if !token.value.is_empty() && Path::new(&token.value).exists() {
+            paths.push(token.span);
+        }
+    }
+
+    for span in paths {
+        for style in &mut styles[span] {
+            style.1 = true;
+        }
+    }
+    styles
+}
+
+/// Colors a piece of text that looks the same throughout
+fn paint(text: &str, (highlight, path): (Highlight, bool), theme: &Theme) -> String {
+    let style = theme.style(highlight);
+    match path {
+        true => text.styled(style.underline()),
+        false => text.styled(style),
+    }
+}
+
+/// The styles that the highlights are painted in, by name
+struct Theme(HashMap<String, Style>);
+
+impl Theme {
+    /// The styles from `HIGHLIGHT_COLORS`, which is a list like `command=bold green:comment=244`
+    /// with the styles written the way [`Style`] parses them. Entries that don't parse are left
+    /// out, and the highlights they don't name keep their default style.
+    fn parse(colors: &str) -> Theme {
+        let styles = colors
+            .split(':')
+            .filter_map(|entry| {
+                let (name, style) = entry.split_once('=')?;
+                Some((name.trim().to_owned(), style.parse().ok()?))
+            })
+            .collect();
+        Theme(styles)
+    }
+
+    /// The style of the highlight
+    fn style(&self, highlight: Highlight) -> Style {
+        let (name, default) = match highlight {
+            Highlight::Plain => ("plain", Style::new()),
+            Highlight::Command => ("command", Style::new().fg(Color::Green)),
+            Highlight::Unknown => ("unknown", Style::new().fg(Color::Red)),
+            Highlight::Quoted => ("quoted", Style::new().fg(Color::Yellow)),
+            Highlight::Variable => ("variable", Style::new().fg(Color::Cyan)),
+            Highlight::Operator => ("operator", Style::new().fg(Color::Magenta)),
+            Highlight::Redirection => ("redirection", Style::new().fg(Color::Blue)),
+            Highlight::Comment => ("comment", Style::new().fg(Color::BrightBlack)),
+        };
+        self.0.get(name).copied().unwrap_or(default)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shell::CWD_LOCK;
+
+    /// The highlight of the first byte of each word in the line
+    fn words(line: &str) -> Vec<Highlight> {
+        let functions = HashSet::from([String::from("greet")]);
+        let styles = classify(line, &functions, &mut Lookups::new());
+        Parser::tokenize(line)
+            .iter()
+            .map(|token| styles[token.span.start].0)
+            .collect()
+    }
+
+    #[test]
+    fn test_commands_are_resolved() {
+        use Highlight::*;
+        assert_eq!(
+            words("echo hi; no-such-command-here; greet"),
+            vec![Command, Plain, Operator, Unknown, Operator, Command]
+        );
+        assert_eq!(
+            words("NAME=1 echo ${NAME}x"),
+            vec![Plain, Command, Variable]
+        );
+    }
+
+    #[test]
+    fn test_quotes_redirections_and_comments() {
+        use Highlight::*;
+        assert_eq!(
+            words("echo 'a b' \"$X\" > out"),
+            vec![Command, Quoted, Quoted, Redirection, Plain]
+        );
+        let styles = classify("echo \"$X\" # note", &HashSet::new(), &mut Lookups::new());
+        assert_eq!(styles[6].0, Variable);
+        assert_eq!(styles[11].0, Comment);
+
+        crate::helpers::vars::set("HIGHLIGHT_TEST_REDIRECT", ">");
+        assert_eq!(
+            words("echo $HIGHLIGHT_TEST_REDIRECT out"),
+            vec![Command, Variable, Plain]
+        );
+    }
+
+    #[test]
+    fn test_commands_are_looked_up_once() {
+        let mut lookups = Lookups::new();
+        classify(
+            "echo hi; no-such-command-here",
+            &HashSet::new(),
+            &mut lookups,
+        );
+        assert_eq!(lookups.get("echo"), Some(&true));
+        assert_eq!(lookups.get("no-such-command-here"), Some(&false));
+
+        // What was looked up before is taken as it is
+        lookups.insert(String::from("no-such-command-here"), true);
+        let styles = classify("no-such-command-here", &HashSet::new(), &mut lookups);
+        assert_eq!(styles[0].0, Highlight::Command);
+    }
+
+    #[test]
+    fn test_theme() {
+        let theme = Theme::parse("command=bold 82:comment=#808080:unknown=nonsense");
+        assert_eq!(
+            theme.style(Highlight::Command),
+            Style::new().bold().fg(Color::Ansi256(82))
+        );
+        assert_eq!(
+            theme.style(Highlight::Comment),
+            Style::new().fg(Color::Rgb(128, 128, 128))
+        );
+        assert_eq!(theme.style(Highlight::Unknown), Style::new().fg(Color::Red));
+    }
+
+    #[test]
+    fn test_existing_paths_are_underlined() {
+        let _lock = CWD_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let line = "echo src no-such-path";
+        let styles = classify(line, &HashSet::new(), &mut Lookups::new());
+        assert!(styles[5].1);
+        assert!(!styles[9].1);
+    }
+}
@@ -1,133 +1,207 @@
-use std::io::Write;
+use std::{io::Write, time};
 
-use crossterm::{
-    cursor,
-    event::{KeyCode, KeyEvent, KeyModifiers},
-    ExecutableCommand,
+use crossterm::{cursor, event::KeyEvent, ExecutableCommand};
+
+use super::{
+    completer::{escape, open_quote, Candidate, Context, Position},
+    keymap::{Action, Binding, Key, Lookup},
+    Signal,
 };
 
 impl super::ReadLine {
-    pub(super) fn handle_key_press(&mut self, evt: KeyEvent) -> std::io::Result<bool> {
-        match evt {
-            // Exit on Ctrl+C or Esc
-            KeyEvent {
-                code: KeyCode::Char('c'),
-                modifiers: KeyModifiers::CONTROL,
-                ..
-            }
-            | KeyEvent {
-                code: KeyCode::Esc, ..
-            } => {
-                return Ok(true); // Exit the loop immediately
-            }
+    /// Handles a key-press by looking it up in the keymap.
+    /// Returns a [`Signal`] once the line is complete.
+    pub(super) fn handle_key_press(&mut self, evt: KeyEvent) -> std::io::Result<Option<Signal>> {
+        // The completion menu gets the first look at the keys while it is open
+        if self.menu.is_some() && self.handle_menu_key(&evt)? {
+            return Ok(None);
+        }
 
-            // Finish input on Enter or Ctrl+J
-            KeyEvent {
-                code: KeyCode::Enter,
-                ..
-            }
-            // Important: Looks like codecrafters use Ctrl+J to enter the line.
-            // So, if this case isn't handled, all input pickup a trailing j causing everything to crash and burn
-            | KeyEvent {
-                code: KeyCode::Char('j'),
-                modifiers: KeyModifiers::CONTROL,
-                ..
-            } => {
-                // On Enter, finish the line.
-                writeln!(self.writer, "")?;
-                self.writer.flush()?;
-                // TODO: Add the complete line to a history buffer
-                return Ok(true);
-            }
+        self.pending.push(Key::from(evt));
+        self.pending_since = time::Instant::now();
 
-            // Process backspace
-            KeyEvent {
-                code: KeyCode::Backspace,
-                ..
-            } => {
-                self.handle_backspace()?;
+        let binding = match self.keymap.lookup(&self.pending) {
+            // Wait for the rest of the key sequence. If the keys so far are bound themselves, the
+            // read loop runs that binding once the sequence times out
+            Lookup::Prefix | Lookup::Ambiguous(_) => return Ok(None),
+            Lookup::Exact(binding) => {
+                self.pending.clear();
+                binding.clone()
             }
+            Lookup::None => {
+                let keys = std::mem::take(&mut self.pending);
+                let (last, prefix) = keys.split_last().expect("pending keys cannot be empty");
+                if prefix.is_empty() {
+                    // An unbound key inserts itself, if it is printable
+                    if let Some(c) = last.printable() {
+                        self.tab_count = 0;
+                        self.handle_character_input(c)?;
+                    }
+                    return Ok(None);
+                }
 
-            // Process Tab completion
-            KeyEvent {
-                code: KeyCode::Tab, ..
-            } => {
-                self.handle_tab_completion()?;
+                // The key sequence was broken off. Run whatever the keys so far were bound to,
+                // and then start over with the last key.
+                if let Some(binding) = self.keymap.get(prefix).cloned() {
+                    if let Some(signal) = self.run_binding(binding)? {
+                        return Ok(Some(signal));
+                    }
+                }
+                let (code, modifiers) = (last.code, last.modifiers);
+                return self.handle_key_press(KeyEvent::new(code, modifiers));
             }
+        };
 
-            // Process any other character
-            KeyEvent {
-                code: KeyCode::Char(c),
-                ..
-            } => {
-                self.handle_character_input(c)?;
+        self.run_binding(binding)
+    }
+
+    /// Run the binding of the pending keys, once the longer key sequence they start has not
+    /// followed within the [`KEYSEQ_TIMEOUT`](super::KEYSEQ_TIMEOUT)
+    pub(super) fn handle_keyseq_timeout(&mut self) -> std::io::Result<Option<Signal>> {
+        match self.keymap.lookup(&self.pending) {
+            Lookup::Ambiguous(binding) => {
+                let binding = binding.clone();
+                self.pending.clear();
+                self.run_binding(binding)
             }
+            // Keys that are only the start of a sequence keep waiting for the rest of it
+            _ => Ok(None),
+        }
+    }
 
-            _ => {} // Ignore other events
+    /// Run the given binding
+    fn run_binding(&mut self, binding: Binding) -> std::io::Result<Option<Signal>> {
+        // Consecutive tab-presses are only counted if nothing else happens in between
+        if binding != Binding::Action(Action::Complete) {
+            self.tab_count = 0;
         }
 
-        Ok(false)
+        match binding {
+            Binding::Action(action) => self.perform(action),
+            Binding::Macro(text) => {
+                for c in text.chars() {
+                    self.handle_character_input(c)?;
+                }
+                Ok(None)
+            }
+            Binding::Command(command) => {
+                // Move past the line being edited, so that the command's output doesn't overwrite it
+                if self.suggestion.is_some() {
+                    self.suggestion = None;
+                    self.draw_line()?;
+                }
+                writeln!(self.writer)?;
+                self.writer.execute(cursor::MoveToColumn(0))?;
+                Ok(Some(Signal::Execute(command)))
+            }
+        }
     }
 
-    /// Appends a character to the buffer and displays it to the screen
-    fn handle_character_input(&mut self, c: char) -> Result<(), std::io::Error> {
-        self.buffer.push(c);
-        write!(self.writer, "{}", c)?;
-        self.writer.flush()?;
-        Ok(())
+    /// Inserts a character into the buffer at the cursor and displays it to the screen
+    pub(super) fn handle_character_input(&mut self, c: char) -> Result<(), std::io::Error> {
+        self.buffer.insert(self.cursor, c);
+        self.cursor += c.len_utf8();
+        // The whole line is redrawn, since a character can change the colors of the words around it
+        self.refresh_line()
     }
 
-    /// Handles the `backspace` key by removing the last character
-    fn handle_backspace(&mut self) -> Result<(), std::io::Error> {
-        Ok(if !self.buffer.is_empty() {
-            // Remove the last character from the buffer.
-            self.buffer.pop();
-            // Move the cursor back, clear the character, and move back again.
-            self.writer.execute(cursor::MoveLeft(1))?;
-            write!(self.writer, " ")?;
-            self.writer.execute(cursor::MoveLeft(1))?;
-            self.writer.flush()?;
-        })
+    /// Handles pasted text, which is inserted at the cursor as it is. None of it is looked up in
+    /// the keymap, so newlines don't accept the line and tabs don't complete.
+    pub(super) fn handle_paste(&mut self, text: &str) -> std::io::Result<()> {
+        self.close_menu()?;
+        self.pending.clear();
+        self.tab_count = 0;
+        // Terminals send line breaks as carriage returns
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+        self.buffer.insert_str(self.cursor, &text);
+        self.cursor += text.len();
+        self.refresh_line()
     }
 
-    /// Extended Tab-completion:
-    /// - If there is progress (the longest common prefix of suggestions is longer than current buffer),
-    ///   update the buffer.
+    /// Extended Tab-completion of the text under the cursor:
+    /// - The [`Context`] of the text (command, argument, redirection target, variable) is worked
+    ///   out once, and every registered completer is asked for candidates. The arguments of a
+    ///   command with a completion spec from `complete` are completed by the spec alone.
+    /// - A spec with a function returns [`Signal::Complete`], so that the shell can run it.
+    /// - Candidates are ranked by how well they match. If there is progress (the longest common
+    ///   prefix of the best-ranked kind of suggestions is longer than the word), update the word.
     /// - If not, on first Tab press, ring the bell.
-    /// - On second consecutive Tab press, print all suggestions.
-    fn handle_tab_completion(&mut self) -> std::io::Result<()> {
-        // Aggregate suggestions from all completers.
-        let mut suggestions = Vec::new();
-        for completer in &self.completers {
-            suggestions.extend(completer.complete(&self.buffer));
-        }
-        suggestions.sort();
-        suggestions.dedup();
+    /// - On second consecutive Tab press, show all suggestions in a menu below the line.
+    ///   Further Tab presses move through the menu.
+    pub(super) fn handle_tab_completion(&mut self) -> std::io::Result<Option<Signal>> {
+        let ctx = Context::new(&self.buffer, self.cursor).with_matching(self.matching);
+
+        let spec = match &ctx.position {
+            Position::Argument { command, .. } => self.specs.get(command),
+            _ => None,
+        };
+        let mut candidates: Vec<Candidate> = match spec {
+            Some(spec) => {
+                let mut words = spec.generate(&ctx.value);
+                if let Some(function) = &spec.function {
+                    match self.reply.take() {
+                        Some(reply) => words.extend(reply),
+                        None => return Ok(Some(Signal::Complete(function.clone()))),
+                    }
+                }
+                words
+                    .into_iter()
+                    .map(|w| Candidate::new(&ctx, escape(&w, ctx.quote()), w))
+                    .collect()
+            }
+            // Aggregate suggestions from all completers.
+            None => self
+                .completers
+                .iter()
+                .flat_map(|completer| completer.complete(&ctx))
+                .collect(),
+        };
+        self.reply = None;
+        candidates.sort_by(|a, b| b.rank.cmp(&a.rank).then_with(|| a.display.cmp(&b.display)));
+        candidates.dedup_by(|a, b| a.replacement == b.replacement && a.span == b.span);
 
-        if suggestions.is_empty() {
+        if candidates.is_empty() {
             // No suggestions: ring the bell.
             write!(self.writer, "\x07")?;
             self.writer.flush()?;
             self.tab_count = 0;
-            return Ok(());
+            return Ok(None);
         }
 
+        // Only the best kind of match (e.g. prefix matches over fuzzy ones) is inserted into the line.
+        // Candidates may replace different parts of the line, so compare the line up to the
+        // cursor as it would look after each of them is applied.
+        let best: Vec<&Candidate> = candidates
+            .iter()
+            .take_while(|c| c.rank.same_kind(&candidates[0].rank))
+            .collect();
+        let lines: Vec<String> = best
+            .iter()
+            .map(|c| format!("{}{}", &self.buffer[..c.span.start], c.replacement))
+            .collect();
+
         // Compute longest common prefix (LCP) among suggestions.
-        let lcp = longest_common_prefix(&suggestions);
-
-        if lcp.len() > self.buffer.len() {
-            // There is progress; update buffer to LCP.
-            let len = self.buffer.len() as u16;
-            self.writer.execute(cursor::MoveLeft(len))?;
-            write!(self.writer, "{}", " ".repeat(len as usize))?;
-            self.writer.execute(cursor::MoveLeft(len))?;
-            if suggestions.len() > 1 {
-                self.buffer = lcp;
-            } else {
-                self.buffer = lcp + " ";
+        let mut lcp = longest_common_prefix(&lines);
+        // Don't stop in the middle of an escape sequence
+        if (lcp.len() - lcp.trim_end_matches('\\').len()) % 2 == 1 {
+            lcp.pop();
+        }
+
+        // Matches that ignore case can also make progress by fixing the case of the word,
+        // and a unique match that is already complete still needs its trailing space.
+        let finished = candidates.len() == 1 && candidates[0].append_whitespace;
+        if lcp.len() >= self.cursor && (lcp != self.buffer[..self.cursor] || finished) {
+            // There is progress; update the word to LCP.
+            if finished {
+                // A unique match is complete: close any open quote and move on to the next word
+                if let Some(quote) = open_quote(&lcp) {
+                    lcp.push(quote);
+                }
+                lcp.push(' ');
             }
-            write!(self.writer, "{}", self.buffer)?;
-            self.writer.flush()?;
+            self.buffer.replace_range(..self.cursor, &lcp);
+            self.cursor = lcp.len();
+            self.refresh_line()?;
             self.tab_count = 0;
         } else {
             // No progress: if multiple suggestions, use tab_count to decide.
@@ -137,21 +211,12 @@ impl super::ReadLine {
                 write!(self.writer, "\x07")?;
                 self.writer.flush()?;
             } else {
-                // Second consecutive Tab press: print all suggestions.
-                writeln!(self.writer)?;
-                self.writer.execute(cursor::MoveToColumn(0))?;
-                for suggestion in &suggestions {
-                    write!(self.writer, "{}  ", suggestion)?;
-                }
-                writeln!(self.writer)?;
-                self.writer.execute(cursor::MoveToColumn(0))?;
-                // Reprint the prompt with the current buffer (assumed prompt "$ ").
-                write!(self.writer, "$ {}", self.buffer)?;
-                self.writer.flush()?;
+                // Second consecutive Tab press: show all suggestions.
                 self.tab_count = 0;
+                self.open_menu(candidates)?;
             }
         }
-        Ok(())
+        Ok(None)
     }
 }
 
@@ -0,0 +1,718 @@
+// Library
+use std::{collections::HashMap, fmt, path::Path, str::FromStr};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+// ---
+// KEY
+// ---
+
+/// A single key-press, normalized so that it can be used to look up bindings.
+///
+/// Terminals report the same key in slightly different ways (e.g. `Shift` is reported alongside
+/// upper-case characters, `Ctrl+I` is indistinguishable from `Tab`), so every [`KeyEvent`] is
+/// converted to a canonical form before it reaches the [`Keymap`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Key {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl Key {
+    /// Instantiate a new normalized key
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        // Only Control and Alt are significant for bindings
+        let mut modifiers = modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT);
+        let code = match code {
+            KeyCode::Char(c) if modifiers.contains(KeyModifiers::CONTROL) => {
+                // Control characters that have a dedicated key on the keyboard
+                match c.to_ascii_lowercase() {
+                    'i' => {
+                        modifiers.remove(KeyModifiers::CONTROL);
+                        KeyCode::Tab
+                    }
+                    'm' => {
+                        modifiers.remove(KeyModifiers::CONTROL);
+                        KeyCode::Enter
+                    }
+                    '[' => {
+                        modifiers.remove(KeyModifiers::CONTROL);
+                        KeyCode::Esc
+                    }
+                    '?' => {
+                        modifiers.remove(KeyModifiers::CONTROL);
+                        KeyCode::Backspace
+                    }
+                    c => KeyCode::Char(c),
+                }
+            }
+            code => code,
+        };
+        Self { code, modifiers }
+    }
+
+    /// Returns the character this key would insert into the buffer, if any
+    pub fn printable(&self) -> Option<char> {
+        match self.code {
+            KeyCode::Char(c) if self.modifiers.is_empty() => Some(c),
+            _ => None,
+        }
+    }
+}
+
+impl From<KeyEvent> for Key {
+    fn from(evt: KeyEvent) -> Self {
+        Key::new(evt.code, evt.modifiers)
+    }
+}
+
+// -------
+// KEY SEQ
+// -------
+
+/// A sequence of one or more keys that can be bound to a [`Binding`].
+///
+/// Key sequences are written using the GNU Readline syntax, either as a double-quoted string of
+/// escapes (`"\C-a"`, `"\M-f"`, `"\e[A"`, `"\C-x\C-e"`) or as a symbolic key name (`Control-a`,
+/// `Meta-f`, `Tab`, `Return`).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KeySeq(pub Vec<Key>);
+
+impl KeySeq {
+    /// Parse a double-quoted readline key sequence (without the surrounding quotes)
+    fn parse_escaped(s: &str) -> Result<Self, String> {
+        let mut keys = Vec::new();
+        let mut chars = s.chars().peekable();
+
+        // Modifiers that apply to the next key
+        let mut pending = KeyModifiers::NONE;
+
+        while let Some(ch) = chars.next() {
+            let code = match ch {
+                '\\' => match chars.next() {
+                    Some('C') if chars.peek() == Some(&'-') => {
+                        chars.next();
+                        pending |= KeyModifiers::CONTROL;
+                        continue;
+                    }
+                    Some('M') if chars.peek() == Some(&'-') => {
+                        chars.next();
+                        pending |= KeyModifiers::ALT;
+                        continue;
+                    }
+                    Some('e') => {
+                        // `\e[` and `\eO` introduce the escape sequences sent by special keys
+                        if let Some(key) = parse_escape_sequence(&mut chars) {
+                            keys.push(key);
+                            continue;
+                        }
+                        // A lone escape followed by a character is the same as Meta
+                        match chars.peek() {
+                            Some(_) => {
+                                pending |= KeyModifiers::ALT;
+                                continue;
+                            }
+                            None => KeyCode::Esc,
+                        }
+                    }
+                    Some('t') => KeyCode::Tab,
+                    Some('r') => KeyCode::Enter,
+                    Some('n') => {
+                        pending |= KeyModifiers::CONTROL;
+                        KeyCode::Char('j')
+                    }
+                    Some('d') => KeyCode::Backspace,
+                    Some('b') => {
+                        pending |= KeyModifiers::CONTROL;
+                        KeyCode::Char('h')
+                    }
+                    Some('a') => {
+                        pending |= KeyModifiers::CONTROL;
+                        KeyCode::Char('g')
+                    }
+                    Some(c @ ('\\' | '"' | '\'')) => KeyCode::Char(c),
+                    Some(c) => return Err(format!("unknown escape `\\{}`", c)),
+                    None => return Err("trailing backslash".into()),
+                },
+                c => KeyCode::Char(c),
+            };
+            keys.push(Key::new(
+                code,
+                std::mem::replace(&mut pending, KeyModifiers::NONE),
+            ));
+        }
+
+        if !pending.is_empty() {
+            return Err("modifier without a key".into());
+        }
+        if keys.is_empty() {
+            return Err("empty key sequence".into());
+        }
+        Ok(KeySeq(keys))
+    }
+
+    /// Parse a symbolic key name like `Control-u`, `M-f` or `Tab`
+    fn parse_name(s: &str) -> Result<Self, String> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = s;
+        loop {
+            let lower = rest.to_ascii_lowercase();
+            if let Some(tail) = ["control-", "ctrl-", "c-"]
+                .iter()
+                .find_map(|p| lower.strip_prefix(p).map(|t| &rest[rest.len() - t.len()..]))
+            {
+                modifiers |= KeyModifiers::CONTROL;
+                rest = tail;
+            } else if let Some(tail) = ["meta-", "alt-", "m-"]
+                .iter()
+                .find_map(|p| lower.strip_prefix(p).map(|t| &rest[rest.len() - t.len()..]))
+            {
+                modifiers |= KeyModifiers::ALT;
+                rest = tail;
+            } else {
+                break;
+            }
+        }
+
+        let code = match rest.to_ascii_lowercase().as_str() {
+            "tab" => KeyCode::Tab,
+            "return" | "ret" | "enter" => KeyCode::Enter,
+            "newline" | "lfd" => {
+                modifiers |= KeyModifiers::CONTROL;
+                KeyCode::Char('j')
+            }
+            "escape" | "esc" => KeyCode::Esc,
+            "space" | "spc" => KeyCode::Char(' '),
+            "rubout" | "del" | "backspace" => KeyCode::Backspace,
+            "delete" => KeyCode::Delete,
+            "insert" => KeyCode::Insert,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            _ => {
+                let mut chars = rest.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => KeyCode::Char(c),
+                    _ => return Err(format!("unknown key name `{}`", s)),
+                }
+            }
+        };
+        Ok(KeySeq(vec![Key::new(code, modifiers)]))
+    }
+}
+
+impl FromStr for KeySeq {
+    type Err = String;
+
+    /// Parse a key sequence in either the quoted or the symbolic form
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        match s.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+            Some(inner) => Self::parse_escaped(inner),
+            None => Self::parse_name(s),
+        }
+    }
+}
+
+impl fmt::Display for KeySeq {
+    /// Format the key sequence using the quoted readline syntax, so that the output of
+    /// `bind -p` can be read back in by `bind -f`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "\"")?;
+        for key in &self.0 {
+            if key.modifiers.contains(KeyModifiers::ALT) {
+                write!(f, "\\e")?;
+            }
+            if key.modifiers.contains(KeyModifiers::CONTROL) {
+                write!(f, "\\C-")?;
+            }
+            match key.code {
+                KeyCode::Char('\\') => write!(f, "\\\\")?,
+                KeyCode::Char('"') => write!(f, "\\\"")?,
+                KeyCode::Char(c) => write!(f, "{}", c)?,
+                KeyCode::Tab => write!(f, "\\t")?,
+                KeyCode::Enter => write!(f, "\\r")?,
+                KeyCode::Esc => write!(f, "\\e")?,
+                KeyCode::Backspace => write!(f, "\\d")?,
+                KeyCode::Up => write!(f, "\\e[A")?,
+                KeyCode::Down => write!(f, "\\e[B")?,
+                KeyCode::Right => write!(f, "\\e[C")?,
+                KeyCode::Left => write!(f, "\\e[D")?,
+                KeyCode::Home => write!(f, "\\e[H")?,
+                KeyCode::End => write!(f, "\\e[F")?,
+                KeyCode::Insert => write!(f, "\\e[2~")?,
+                KeyCode::Delete => write!(f, "\\e[3~")?,
+                KeyCode::PageUp => write!(f, "\\e[5~")?,
+                KeyCode::PageDown => write!(f, "\\e[6~")?,
+                KeyCode::BackTab => write!(f, "\\e[Z")?,
+                other => write!(f, "<{:?}>", other)?,
+            }
+        }
+        write!(f, "\"")
+    }
+}
+
+/// Parse the remainder of an ANSI escape sequence that follows `\e` (e.g. `[A` for the up arrow).
+/// The iterator is only advanced if a known sequence is found.
+fn parse_escape_sequence(chars: &mut std::iter::Peekable<std::str::Chars>) -> Option<Key> {
+    let rest: String = chars.clone().collect();
+    let sequences = [
+        ("[1;5C", KeyCode::Right, KeyModifiers::CONTROL),
+        ("[1;5D", KeyCode::Left, KeyModifiers::CONTROL),
+        ("[1;3C", KeyCode::Right, KeyModifiers::ALT),
+        ("[1;3D", KeyCode::Left, KeyModifiers::ALT),
+        ("[2~", KeyCode::Insert, KeyModifiers::NONE),
+        ("[3~", KeyCode::Delete, KeyModifiers::NONE),
+        ("[5~", KeyCode::PageUp, KeyModifiers::NONE),
+        ("[6~", KeyCode::PageDown, KeyModifiers::NONE),
+        ("[A", KeyCode::Up, KeyModifiers::NONE),
+        ("[B", KeyCode::Down, KeyModifiers::NONE),
+        ("[C", KeyCode::Right, KeyModifiers::NONE),
+        ("[D", KeyCode::Left, KeyModifiers::NONE),
+        ("[H", KeyCode::Home, KeyModifiers::NONE),
+        ("[F", KeyCode::End, KeyModifiers::NONE),
+        ("[Z", KeyCode::BackTab, KeyModifiers::NONE),
+        ("OA", KeyCode::Up, KeyModifiers::NONE),
+        ("OB", KeyCode::Down, KeyModifiers::NONE),
+        ("OC", KeyCode::Right, KeyModifiers::NONE),
+        ("OD", KeyCode::Left, KeyModifiers::NONE),
+        ("OH", KeyCode::Home, KeyModifiers::NONE),
+        ("OF", KeyCode::End, KeyModifiers::NONE),
+    ];
+    let (seq, code, modifiers) = sequences.iter().find(|(seq, ..)| rest.starts_with(seq))?;
+    for _ in 0..seq.len() {
+        chars.next();
+    }
+    Some(Key::new(*code, *modifiers))
+}
+
+// ------
+// ACTION
+// ------
+
+/// Named editor actions that a key sequence can be bound to.
+/// The names follow the GNU Readline function names wherever possible.
+/// `interrupt` is our own: it discards the line, like Ctrl+C does in other shells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    AcceptLine,
+    Interrupt,
+    EndOfFile,
+    ClearScreen,
+    Abort,
+    BackwardChar,
+    ForwardChar,
+    BackwardWord,
+    ForwardWord,
+    BeginningOfLine,
+    EndOfLine,
+    BackwardDeleteChar,
+    DeleteChar,
+    KillLine,
+    UnixLineDiscard,
+    KillWord,
+    BackwardKillWord,
+    UnixWordRubout,
+    Yank,
+    TransposeChars,
+    Complete,
+}
+
+impl Action {
+    /// All the actions, in the order they are listed by `bind -l`
+    pub const ALL: &'static [Action] = &[
+        Action::Abort,
+        Action::AcceptLine,
+        Action::BackwardChar,
+        Action::BackwardDeleteChar,
+        Action::BackwardKillWord,
+        Action::BackwardWord,
+        Action::BeginningOfLine,
+        Action::ClearScreen,
+        Action::Complete,
+        Action::DeleteChar,
+        Action::EndOfFile,
+        Action::EndOfLine,
+        Action::ForwardChar,
+        Action::ForwardWord,
+        Action::Interrupt,
+        Action::KillLine,
+        Action::KillWord,
+        Action::TransposeChars,
+        Action::UnixLineDiscard,
+        Action::UnixWordRubout,
+        Action::Yank,
+    ];
+
+    /// The readline name of the action
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::AcceptLine => "accept-line",
+            Action::Interrupt => "interrupt",
+            Action::EndOfFile => "end-of-file",
+            Action::ClearScreen => "clear-screen",
+            Action::Abort => "abort",
+            Action::BackwardChar => "backward-char",
+            Action::ForwardChar => "forward-char",
+            Action::BackwardWord => "backward-word",
+            Action::ForwardWord => "forward-word",
+            Action::BeginningOfLine => "beginning-of-line",
+            Action::EndOfLine => "end-of-line",
+            Action::BackwardDeleteChar => "backward-delete-char",
+            Action::DeleteChar => "delete-char",
+            Action::KillLine => "kill-line",
+            Action::UnixLineDiscard => "unix-line-discard",
+            Action::KillWord => "kill-word",
+            Action::BackwardKillWord => "backward-kill-word",
+            Action::UnixWordRubout => "unix-word-rubout",
+            Action::Yank => "yank",
+            Action::TransposeChars => "transpose-chars",
+            Action::Complete => "complete",
+        }
+    }
+}
+
+impl FromStr for Action {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Action::ALL
+            .iter()
+            .find(|action| action.name() == s)
+            .copied()
+            .ok_or_else(|| format!("unknown function name `{}`", s))
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+// -------
+// BINDING
+// -------
+
+/// What happens when a key sequence is pressed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Binding {
+    /// Run a named editor action
+    Action(Action),
+    /// Insert the text into the buffer as if it was typed (a readline macro)
+    Macro(String),
+    /// Run a shell command (`bind -x`). The buffer is exposed as `READLINE_LINE`
+    Command(String),
+}
+
+impl Binding {
+    /// Parse a `keyseq: function-name` or `keyseq: "macro"` line, as found in an inputrc file
+    pub fn parse(line: &str) -> Result<(KeySeq, Binding), String> {
+        let (keyseq, rhs) = split_binding(line)?;
+        let binding = match unquote(rhs) {
+            Some(text) => Binding::Macro(text.to_string()),
+            None => Binding::Action(rhs.parse()?),
+        };
+        Ok((keyseq, binding))
+    }
+
+    /// Parse a `keyseq: shell-command` line, as given to `bind -x`
+    pub fn parse_command(line: &str) -> Result<(KeySeq, Binding), String> {
+        let (keyseq, rhs) = split_binding(line)?;
+        let command = unquote(rhs).unwrap_or(rhs);
+        Ok((keyseq, Binding::Command(command.to_string())))
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Binding::Action(action) => write!(f, "{}", action),
+            Binding::Macro(text) | Binding::Command(text) => write!(f, "\"{}\"", text),
+        }
+    }
+}
+
+/// Split a binding line at the colon that separates the key sequence from its value
+fn split_binding(line: &str) -> Result<(KeySeq, &str), String> {
+    let line = line.trim();
+
+    // The colon may appear inside a quoted key sequence, so skip over it
+    let colon = if line.starts_with('"') {
+        let mut escaped = false;
+        let close = line
+            .char_indices()
+            .skip(1)
+            .find(|&(_, c)| {
+                let found = c == '"' && !escaped;
+                escaped = c == '\\' && !escaped;
+                found
+            })
+            .map(|(i, _)| i)
+            .ok_or("unterminated key sequence")?;
+        close
+            + line[close..]
+                .find(':')
+                .ok_or("missing `:` after key sequence")?
+    } else {
+        line.find(':').ok_or("missing `:` after key sequence")?
+    };
+
+    let keyseq = line[..colon].parse()?;
+    Ok((keyseq, line[colon + 1..].trim()))
+}
+
+/// Strip a matching pair of single or double quotes from the text, if present
+fn unquote(text: &str) -> Option<&str> {
+    ['"', '\'']
+        .iter()
+        .find_map(|&q| text.strip_prefix(q).and_then(|t| t.strip_suffix(q)))
+}
+
+// ------
+// KEYMAP
+// ------
+
+/// The result of looking up a key sequence in the [`Keymap`]
+pub enum Lookup<'a> {
+    /// The keys are bound to the binding
+    Exact(&'a Binding),
+    /// The keys are the start of a longer key sequence
+    Prefix,
+    /// The keys are bound to the binding, and are also the start of a longer key sequence.
+    /// The binding only runs if the rest of the longer sequence doesn't follow in time
+    Ambiguous(&'a Binding),
+    /// Nothing is bound to the keys
+    None,
+}
+
+/// Maps key sequences to the [`Binding`]s they trigger
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: HashMap<KeySeq, Binding>,
+}
+
+impl Default for Keymap {
+    /// The default set of emacs-style bindings
+    fn default() -> Self {
+        let mut keymap = Keymap {
+            bindings: HashMap::new(),
+        };
+
+        let ctrl = |c| Key::new(KeyCode::Char(c), KeyModifiers::CONTROL);
+        let alt = |c| Key::new(KeyCode::Char(c), KeyModifiers::ALT);
+        let key = |code| Key::new(code, KeyModifiers::NONE);
+
+        let defaults = [
+            (key(KeyCode::Enter), Action::AcceptLine),
+            // Important: Looks like codecrafters use Ctrl+J to enter the line.
+            // So, if this case isn't handled, all input pickup a trailing j causing everything to crash and burn
+            (ctrl('j'), Action::AcceptLine),
+            (ctrl('c'), Action::Interrupt),
+            (ctrl('d'), Action::EndOfFile),
+            (ctrl('l'), Action::ClearScreen),
+            (ctrl('g'), Action::Abort),
+            (key(KeyCode::Left), Action::BackwardChar),
+            (ctrl('b'), Action::BackwardChar),
+            (key(KeyCode::Right), Action::ForwardChar),
+            (ctrl('f'), Action::ForwardChar),
+            (alt('b'), Action::BackwardWord),
+            (
+                Key::new(KeyCode::Left, KeyModifiers::CONTROL),
+                Action::BackwardWord,
+            ),
+            (alt('f'), Action::ForwardWord),
+            (
+                Key::new(KeyCode::Right, KeyModifiers::CONTROL),
+                Action::ForwardWord,
+            ),
+            (key(KeyCode::Home), Action::BeginningOfLine),
+            (ctrl('a'), Action::BeginningOfLine),
+            (key(KeyCode::End), Action::EndOfLine),
+            (ctrl('e'), Action::EndOfLine),
+            (key(KeyCode::Backspace), Action::BackwardDeleteChar),
+            (ctrl('h'), Action::BackwardDeleteChar),
+            (key(KeyCode::Delete), Action::DeleteChar),
+            (ctrl('k'), Action::KillLine),
+            (ctrl('u'), Action::UnixLineDiscard),
+            (alt('d'), Action::KillWord),
+            (
+                Key::new(KeyCode::Backspace, KeyModifiers::ALT),
+                Action::BackwardKillWord,
+            ),
+            (ctrl('w'), Action::UnixWordRubout),
+            (ctrl('y'), Action::Yank),
+            (ctrl('t'), Action::TransposeChars),
+            (key(KeyCode::Tab), Action::Complete),
+        ];
+        for (key, action) in defaults {
+            keymap.bind(KeySeq(vec![key]), Binding::Action(action));
+        }
+
+        keymap
+    }
+}
+
+impl Keymap {
+    /// Bind the key sequence, replacing any previous binding
+    pub fn bind(&mut self, keyseq: KeySeq, binding: Binding) {
+        self.bindings.insert(keyseq, binding);
+    }
+
+    /// Remove the binding for the key sequence. Returns `false` if nothing was bound
+    pub fn unbind(&mut self, keyseq: &KeySeq) -> bool {
+        self.bindings.remove(keyseq).is_some()
+    }
+
+    /// Remove all the key sequences bound to the action
+    pub fn unbind_action(&mut self, action: Action) {
+        self.bindings
+            .retain(|_, binding| *binding != Binding::Action(action));
+    }
+
+    /// Look up the given keys
+    pub fn lookup(&self, keys: &[Key]) -> Lookup<'_> {
+        let is_prefix = self
+            .bindings
+            .keys()
+            .any(|seq| seq.0.len() > keys.len() && seq.0.starts_with(keys));
+        match (self.bindings.get(&KeySeq(keys.to_vec())), is_prefix) {
+            (Some(binding), true) => Lookup::Ambiguous(binding),
+            (Some(binding), false) => Lookup::Exact(binding),
+            (None, true) => Lookup::Prefix,
+            (None, false) => Lookup::None,
+        }
+    }
+
+    /// Look up the binding for exactly these keys, ignoring longer sequences
+    pub fn get(&self, keys: &[Key]) -> Option<&Binding> {
+        self.bindings.get(&KeySeq(keys.to_vec()))
+    }
+
+    /// All bindings, sorted by their key sequence for stable output
+    pub fn bindings(&self) -> Vec<(&KeySeq, &Binding)> {
+        let mut bindings: Vec<_> = self.bindings.iter().collect();
+        bindings.sort_by_key(|(seq, _)| seq.to_string());
+        bindings
+    }
+
+    /// Read bindings from an inputrc-style file. Comments, blank lines and readline
+    /// directives (`set`, `$if`, ...) are skipped. Returns the errors for the lines that failed
+    /// to parse, prefixed with their line numbers.
+    pub fn read_file(&mut self, path: &Path) -> std::io::Result<Vec<String>> {
+        let contents = std::fs::read_to_string(path)?;
+        let mut errors = Vec::new();
+        for (n, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty()
+                || line.starts_with('#')
+                || line.starts_with('$')
+                || line.starts_with("set ")
+            {
+                continue;
+            }
+            match Binding::parse(line) {
+                Ok((keyseq, binding)) => self.bind(keyseq, binding),
+                Err(e) => errors.push(format!("{}:{}: {}", path.display(), n + 1, e)),
+            }
+        }
+        Ok(errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_control_sequence() {
+        let seq: KeySeq = "\"\\C-a\"".parse().unwrap();
+        assert_eq!(
+            seq.0,
+            vec![Key::new(KeyCode::Char('a'), KeyModifiers::CONTROL)]
+        );
+    }
+
+    #[test]
+    fn test_parse_meta_and_escape_sequences() {
+        let meta: KeySeq = "\"\\M-f\"".parse().unwrap();
+        let escape: KeySeq = "\"\\ef\"".parse().unwrap();
+        assert_eq!(meta, escape);
+        assert_eq!(
+            meta.0,
+            vec![Key::new(KeyCode::Char('f'), KeyModifiers::ALT)]
+        );
+    }
+
+    #[test]
+    fn test_parse_arrow_key_sequence() {
+        let seq: KeySeq = "\"\\e[A\"".parse().unwrap();
+        assert_eq!(seq.0, vec![Key::new(KeyCode::Up, KeyModifiers::NONE)]);
+    }
+
+    #[test]
+    fn test_parse_key_names() {
+        let seq: KeySeq = "Control-u".parse().unwrap();
+        assert_eq!(
+            seq.0,
+            vec![Key::new(KeyCode::Char('u'), KeyModifiers::CONTROL)]
+        );
+        let seq: KeySeq = "Tab".parse().unwrap();
+        assert_eq!(seq.0, vec![Key::new(KeyCode::Tab, KeyModifiers::NONE)]);
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        for input in ["\"\\C-x\\C-e\"", "\"\\ef\"", "\"\\e[A\"", "\"\\t\""] {
+            let seq: KeySeq = input.parse().unwrap();
+            assert_eq!(seq.to_string().parse::<KeySeq>().unwrap(), seq);
+        }
+    }
+
+    #[test]
+    fn test_parse_binding_line() {
+        let (seq, binding) = Binding::parse("\"\\C-a\": end-of-line").unwrap();
+        assert_eq!(
+            seq.0,
+            vec![Key::new(KeyCode::Char('a'), KeyModifiers::CONTROL)]
+        );
+        assert_eq!(binding, Binding::Action(Action::EndOfLine));
+
+        let (_, binding) = Binding::parse("\"\\C-xg\": \"git status\"").unwrap();
+        assert_eq!(binding, Binding::Macro("git status".into()));
+
+        let (_, binding) = Binding::parse_command("\"\\C-t\": \"echo hi\"").unwrap();
+        assert_eq!(binding, Binding::Command("echo hi".into()));
+
+        assert!(Binding::parse("\"\\C-a\": no-such-function").is_err());
+    }
+
+    #[test]
+    fn test_lookup_prefix() {
+        let mut keymap = Keymap::default();
+        let seq: KeySeq = "\"\\C-x\\C-e\"".parse().unwrap();
+        keymap.bind(seq.clone(), Binding::Action(Action::EndOfLine));
+        assert!(matches!(keymap.lookup(&seq.0[..1]), Lookup::Prefix));
+        assert!(matches!(keymap.lookup(&seq.0), Lookup::Exact(_)));
+    }
+
+    #[test]
+    fn test_lookup_ambiguous() {
+        let mut keymap = Keymap::default();
+        let seq: KeySeq = "\"\\C-e\\C-e\"".parse().unwrap();
+        keymap.bind(seq.clone(), Binding::Action(Action::KillLine));
+        assert!(matches!(
+            keymap.lookup(&seq.0[..1]),
+            Lookup::Ambiguous(Binding::Action(Action::EndOfLine))
+        ));
+        assert!(matches!(
+            keymap.lookup(&seq.0),
+            Lookup::Exact(Binding::Action(Action::KillLine))
+        ));
+    }
+}
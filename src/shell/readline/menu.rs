@@ -0,0 +1,341 @@
+use std::io::Write;
+
+use crossterm::{
+    cursor,
+    event::{KeyCode, KeyEvent, KeyModifiers},
+    style::{Attribute, SetAttribute},
+    terminal, QueueableCommand,
+};
+
+use super::completer::{open_quote, Candidate};
+
+// ---------------
+// COMPLETION MENU
+// ---------------
+
+/// The candidates of a completion, laid out in columns below the line being edited.
+///
+/// Candidates without descriptions are laid out in a grid sized to the terminal width, in
+/// column-major order like `ls`. If any candidate has a description, there is one candidate per
+/// row instead, with the descriptions lined up next to them. Menus that don't fit on the screen
+/// are shown one page at a time.
+pub(super) struct Menu {
+    candidates: Vec<Candidate>,
+    /// The index of the highlighted candidate, once the user starts moving through the menu
+    selected: Option<usize>,
+    /// The line and cursor position before any candidate was inserted
+    original: (String, usize),
+    /// The first row that is shown
+    offset: usize,
+    /// The number of lines the menu took up when it was last drawn
+    drawn: usize,
+}
+
+/// How the candidates are arranged on the screen
+#[derive(Debug, PartialEq)]
+struct Layout {
+    columns: usize,
+    rows: usize,
+    /// The width of a column, including the gap to the next one
+    column_width: usize,
+}
+
+/// The gap between two columns of the menu
+const GAP: usize = 2;
+
+impl Menu {
+    pub(super) fn new(candidates: Vec<Candidate>, line: &str, cursor: usize) -> Self {
+        Menu {
+            candidates,
+            selected: None,
+            original: (line.to_owned(), cursor),
+            offset: 0,
+            drawn: 0,
+        }
+    }
+
+    /// Whether the menu shows the descriptions of the candidates
+    fn has_descriptions(&self) -> bool {
+        self.candidates.iter().any(|c| c.description.is_some())
+    }
+
+    /// The widest candidate, in characters
+    fn display_width(&self) -> usize {
+        self.candidates
+            .iter()
+            .map(|c| c.display.chars().count())
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Work out how the candidates fit in the given width
+    fn layout(&self, width: usize) -> Layout {
+        let column_width = self.display_width() + GAP;
+        let columns = match self.has_descriptions() {
+            true => 1,
+            false => (width / column_width).clamp(1, self.candidates.len().max(1)),
+        };
+        Layout {
+            columns,
+            rows: self.candidates.len().div_ceil(columns),
+            column_width,
+        }
+    }
+
+    /// The number of rows that can be shown at once, keeping a line for the prompt and the status line
+    fn page_size(height: usize) -> usize {
+        height.saturating_sub(2).max(1)
+    }
+
+    /// Move the highlight by the given number of candidates, wrapping around at either end
+    fn step(&mut self, by: isize) {
+        let len = self.candidates.len() as isize;
+        let next = match self.selected {
+            Some(i) => (i as isize + by).rem_euclid(len),
+            None if by < 0 => len - 1,
+            None => 0,
+        };
+        self.selected = Some(next as usize);
+    }
+
+    /// Scroll the menu so that the highlighted candidate is on the page
+    fn scroll(&mut self, layout: &Layout, page: usize) {
+        let Some(selected) = self.selected else {
+            return;
+        };
+        let row = selected % layout.rows;
+        if row < self.offset {
+            self.offset = row;
+        } else if row >= self.offset + page {
+            self.offset = row + 1 - page;
+        }
+    }
+
+    /// The lines of the menu that fit on a screen of the given size
+    fn lines(&self, width: usize, height: usize) -> Vec<String> {
+        let layout = self.layout(width);
+        let page = Self::page_size(height);
+        let last = (self.offset + page).min(layout.rows);
+
+        let mut lines = Vec::new();
+        for row in self.offset..last {
+            let mut line = String::new();
+            for column in 0..layout.columns {
+                let index = column * layout.rows + row;
+                let Some(candidate) = self.candidates.get(index) else {
+                    break;
+                };
+                let padding = layout.column_width - candidate.display.chars().count();
+                let text = match &candidate.description {
+                    // Descriptions are cut off at the edge of the screen
+                    Some(description) => {
+                        let room = width.saturating_sub(layout.column_width + 1);
+                        let description: String = description.chars().take(room).collect();
+                        format!(
+                            "{}{}{}{}{}",
+                            candidate.display,
+                            " ".repeat(padding),
+                            SetAttribute(Attribute::Dim),
+                            description,
+                            SetAttribute(Attribute::NormalIntensity)
+                        )
+                    }
+                    None => format!("{}{}", candidate.display, " ".repeat(padding)),
+                };
+                if self.selected == Some(index) {
+                    line.push_str(&format!(
+                        "{}{}{}",
+                        SetAttribute(Attribute::Reverse),
+                        text.trim_end(),
+                        SetAttribute(Attribute::Reset)
+                    ));
+                    line.push_str(&" ".repeat(text.len() - text.trim_end().len()));
+                } else {
+                    line.push_str(&text);
+                }
+            }
+            lines.push(line.trim_end().to_owned());
+        }
+
+        // Tell the user where they are in a long menu
+        if layout.rows > page {
+            lines.push(format!(
+                "{}rows {} to {} of {}{}",
+                SetAttribute(Attribute::Reverse),
+                self.offset + 1,
+                last,
+                layout.rows,
+                SetAttribute(Attribute::Reset)
+            ));
+        }
+        lines
+    }
+}
+
+impl super::ReadLine {
+    /// Show the candidates in a menu below the line
+    pub(super) fn open_menu(&mut self, candidates: Vec<Candidate>) -> std::io::Result<()> {
+        self.menu = Some(Menu::new(candidates, &self.buffer, self.cursor));
+        self.draw_menu()
+    }
+
+    /// Handles a key-press while the menu is open.
+    /// Returns `true` if the key was used by the menu, or `false` if it should be handled as usual.
+    pub(super) fn handle_menu_key(&mut self, evt: &KeyEvent) -> std::io::Result<bool> {
+        let Some(menu) = &mut self.menu else {
+            return Ok(false);
+        };
+        let rows = menu.layout(terminal_size().0).rows as isize;
+
+        let step = match (evt.code, evt.modifiers) {
+            (KeyCode::Tab, KeyModifiers::NONE) | (KeyCode::Down, _) => 1,
+            (KeyCode::BackTab, _) | (KeyCode::Up, _) => -1,
+            (KeyCode::Right, _) => rows,
+            (KeyCode::Left, _) => -rows,
+            // Enter keeps the highlighted candidate, without accepting the line
+            (KeyCode::Enter, _) if menu.selected.is_some() => {
+                self.insert_selection(true);
+                self.close_menu()?;
+                return Ok(true);
+            }
+            // Escape and Ctrl+G go back to the line as it was before the menu
+            (KeyCode::Esc, _) | (KeyCode::Char('g'), KeyModifiers::CONTROL) => {
+                let (line, cursor) = menu.original.clone();
+                self.buffer = line;
+                self.cursor = cursor;
+                self.close_menu()?;
+                return Ok(true);
+            }
+            // Any other key keeps the highlighted candidate, and is then handled as usual
+            _ => {
+                self.close_menu()?;
+                return Ok(false);
+            }
+        };
+
+        menu.step(step);
+        self.insert_selection(false);
+        self.draw_menu()?;
+        Ok(true)
+    }
+
+    /// Replace the text being completed with the highlighted candidate.
+    /// A `finished` candidate is followed by a space, like a unique match is.
+    fn insert_selection(&mut self, finished: bool) {
+        let Some(menu) = &self.menu else {
+            return;
+        };
+        let Some(candidate) = menu.selected.map(|i| &menu.candidates[i]) else {
+            return;
+        };
+        let (line, cursor) = &menu.original;
+        let mut completed = format!("{}{}", &line[..candidate.span.start], candidate.replacement);
+        if finished && candidate.append_whitespace {
+            if let Some(quote) = open_quote(&completed) {
+                completed.push(quote);
+            }
+            completed.push(' ');
+        }
+        self.buffer = format!("{}{}", completed, &line[*cursor..]);
+        self.cursor = completed.len();
+    }
+
+    /// Draw the line with the menu below it, and put the cursor back on the line
+    fn draw_menu(&mut self) -> std::io::Result<()> {
+        let (width, height) = terminal_size();
+        let Some(menu) = &mut self.menu else {
+            return Ok(());
+        };
+        let layout = menu.layout(width);
+        menu.scroll(&layout, Menu::page_size(height));
+        let lines = menu.lines(width, height);
+        menu.drawn = lines.len();
+
+        self.refresh_line()?;
+        for line in &lines {
+            write!(self.writer, "\r\n")?;
+            self.writer
+                .queue(terminal::Clear(terminal::ClearType::UntilNewLine))?;
+            write!(self.writer, "{}", line)?;
+        }
+        self.writer
+            .queue(terminal::Clear(terminal::ClearType::FromCursorDown))?;
+        if !lines.is_empty() {
+            self.writer
+                .queue(cursor::MoveToPreviousLine(lines.len() as u16))?;
+        }
+        self.refresh_line()
+    }
+
+    /// Remove the menu from the screen
+    pub(super) fn close_menu(&mut self) -> std::io::Result<()> {
+        let Some(menu) = self.menu.take() else {
+            return Ok(());
+        };
+        if menu.drawn > 0 {
+            self.writer
+                .queue(cursor::MoveToNextLine(1))?
+                .queue(terminal::Clear(terminal::ClearType::FromCursorDown))?
+                .queue(cursor::MoveToPreviousLine(1))?;
+        }
+        self.refresh_line()
+    }
+}
+
+/// The size of the terminal as (columns, rows), with a fallback for when it can't be found
+fn terminal_size() -> (usize, usize) {
+    terminal::size().map_or((80, 24), |(w, h)| (w as usize, h as usize))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shell::readline::Rank;
+
+    fn menu(names: &[&str]) -> Menu {
+        let candidates = names
+            .iter()
+            .map(|name| Candidate {
+                replacement: name.to_string(),
+                span: 0..0,
+                display: name.to_string(),
+                description: None,
+                append_whitespace: true,
+                rank: Rank::Prefix,
+            })
+            .collect();
+        Menu::new(candidates, "", 0)
+    }
+
+    #[test]
+    fn test_layout_fits_width() {
+        let menu = menu(&["alpha", "beta", "gamma", "delta", "epsilon"]);
+        // Each column is 7 + 2 characters wide
+        let layout = menu.layout(20);
+        assert_eq!(
+            layout,
+            Layout {
+                columns: 2,
+                rows: 3,
+                column_width: 9
+            }
+        );
+        assert_eq!(menu.layout(5).columns, 1);
+    }
+
+    #[test]
+    fn test_lines_are_column_major() {
+        let menu = menu(&["a", "b", "c", "d"]);
+        assert_eq!(menu.lines(6, 24), vec!["a  c", "b  d"]);
+    }
+
+    #[test]
+    fn test_paging_follows_selection() {
+        let mut menu = menu(&["a", "b", "c", "d", "e"]);
+        menu.step(-1);
+        let layout = menu.layout(1);
+        menu.scroll(&layout, Menu::page_size(4));
+        assert_eq!(menu.selected, Some(4));
+        assert_eq!(menu.offset, 3);
+    }
+}
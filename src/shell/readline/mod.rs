@@ -1,24 +1,91 @@
 use std::{
+    collections::{HashMap, HashSet},
     io::{BufWriter, Write},
+    path::PathBuf,
     time,
 };
 
 use crossterm::{
     cursor,
     event::{self, Event, KeyEventKind},
-    ExecutableCommand,
+    style::{Attribute, SetAttribute},
+    terminal, ExecutableCommand, QueueableCommand,
 };
 
+use crate::helpers::{self, ansi};
+
+mod actions;
 mod completer;
+mod highlight;
 mod key_press;
+pub mod keymap;
+mod menu;
 mod raw_mode;
+mod suggest;
 
 pub use completer::*;
+use keymap::{Key, Keymap};
+
+/// How long to wait for the rest of a key sequence when the keys so far are bound themselves
+const KEYSEQ_TIMEOUT: time::Duration = time::Duration::from_millis(500);
+
+/// The outcome of a call to [`ReadLine::read`]
+pub enum Signal {
+    /// The user accepted the line
+    Success(String),
+    /// The user discarded the line with Ctrl+C
+    Interrupt,
+    /// The user pressed Ctrl+D on an empty line
+    EndOfFile,
+    /// A key bound to a shell command with `bind -x` was pressed.
+    /// The buffer is preserved, and editing resumes on the next call to [`ReadLine::read`]
+    Execute(String),
+    /// The command being completed has a completion function, registered with `complete -F`.
+    /// The shell runs the function and hands the results to [`ReadLine::complete_with`],
+    /// and the completion is finished on the next call to [`ReadLine::read`]
+    Complete(String),
+}
 
 pub(super) struct ReadLine {
+    /// The prompt, which may span several lines. Text between [`ansi::START_IGNORE`] and
+    /// [`ansi::END_IGNORE`] takes up no room on the screen
     prompt: String,
+    /// The prompt shown on the right of the input line, while there is room for it
+    right_prompt: String,
+    /// The compact prompt that replaces the full one once the line is accepted, if any
+    transient_prompt: Option<String>,
     buffer: String,
+    /// The position of the cursor as a byte offset into the buffer
+    cursor: usize,
+    keymap: Keymap,
+    /// Keys that form the start of a multi-key binding
+    pending: Vec<Key>,
+    /// When the last of the pending keys was pressed
+    pending_since: time::Instant,
+    /// The text most recently removed by a kill command, for `yank`
+    kill_buffer: String,
+    /// The lines accepted so far, oldest first
+    history: Vec<String>,
+    /// The text suggested after the end of the buffer. It is only shown, and never part of the buffer
+    suggestion: Option<String>,
+    /// The buffer the completions for the suggestion were last worked out for, and the lines
+    /// they complete it to
+    completed: Option<(String, Vec<String>)>,
     completers: Vec<Box<dyn Completer>>,
+    /// The completion specs registered with `complete`, by command name
+    specs: HashMap<String, CompSpec>,
+    /// The names of the shell functions, which are highlighted as commands
+    functions: HashSet<String>,
+    /// The commands looked up while highlighting the line being read
+    lookups: highlight::Lookups,
+    /// The candidates generated by a completion function, waiting to be completed
+    reply: Option<Vec<String>>,
+    /// Which kinds of matches count when completing
+    matching: Matching,
+    /// Whether accepting a line of several lines, as from a paste, has to be confirmed
+    confirm_paste: bool,
+    /// The completion menu, while it is shown
+    menu: Option<menu::Menu>,
     tab_count: u8,
     writer: BufWriter<std::io::Stdout>,
     poll_interval: time::Duration,
@@ -26,10 +93,38 @@ pub(super) struct ReadLine {
 
 impl Default for ReadLine {
     fn default() -> Self {
+        let mut keymap = Keymap::default();
+
+        // Load user bindings from the readline startup file
+        if let Some(path) = inputrc_path() {
+            if let Ok(errors) = keymap.read_file(&path) {
+                for error in errors {
+                    eprintln!("readline: {}", error);
+                }
+            }
+        }
+
         Self {
             prompt: String::from("$ "),
+            right_prompt: String::new(),
+            transient_prompt: None,
             buffer: String::new(),
+            cursor: 0,
+            keymap,
+            pending: Vec::new(),
+            pending_since: time::Instant::now(),
+            kill_buffer: String::new(),
+            history: Vec::new(),
+            suggestion: None,
+            completed: None,
             completers: Vec::new(),
+            specs: HashMap::new(),
+            functions: HashSet::new(),
+            lookups: highlight::Lookups::new(),
+            reply: None,
+            matching: Matching::default(),
+            confirm_paste: false,
+            menu: None,
             tab_count: 0,
             poll_interval: time::Duration::from_millis(100),
             writer: BufWriter::new(std::io::stdout()),
@@ -37,20 +132,142 @@ impl Default for ReadLine {
     }
 }
 
+/// The text with the newlines and tabs from a paste shown as symbols, so that the buffer is drawn
+/// on a single line with one column per character
+fn visible(text: &str) -> String {
+    text.replace('\n', "↵").replace('\t', "⇥")
+}
+
+/// The path to the readline startup file: `$INPUTRC`, or `~/.inputrc`
+fn inputrc_path() -> Option<PathBuf> {
+    match helpers::vars::get("INPUTRC") {
+        Some(path) => Some(PathBuf::from(path)),
+        None => helpers::home::get().map(|home| home.join(".inputrc")),
+    }
+}
+
 impl ReadLine {
-    /// Set the prompt
+    /// Set the prompt. Escape sequences in it don't count towards its width
     pub fn with_prompt(&mut self, prompt: &str) -> &mut Self {
         self.prompt = prompt.to_owned();
         self
     }
 
+    /// Set the prompt shown on the right of the input line, like zsh's `RPROMPT`
+    pub fn with_right_prompt(&mut self, prompt: &str) -> &mut Self {
+        self.right_prompt = prompt.to_owned();
+        self
+    }
+
+    /// Set the compact prompt that replaces the full one once the line is accepted
+    pub fn with_transient_prompt(&mut self, prompt: Option<String>) -> &mut Self {
+        self.transient_prompt = prompt;
+        self
+    }
+
     /// Render the prompt to the screen
     fn render_prompt(&mut self) -> std::io::Result<()> {
-        write!(self.writer, "{}", self.prompt)?;
+        // Raw mode doesn't go back to the start of the line on a newline by itself
+        let prompt = ansi::strip_markers(&self.prompt).replace('\n', "\r\n");
+        write!(self.writer, "{}", prompt)?;
+        if !self.right_prompt.is_empty() {
+            return self.draw_line();
+        }
         self.writer.flush()?;
         Ok(())
     }
 
+    /// Redraw the accepted line with the transient prompt in place of the full one.
+    /// The lines of a multi-line prompt above the input line are cleared as well.
+    fn draw_transient(&mut self, transient: &str) -> std::io::Result<()> {
+        let width = terminal::size().map_or(80, |(w, _)| w.max(1) as usize);
+        let above: usize = self
+            .prompt
+            .split('\n')
+            .rev()
+            .skip(1)
+            .map(|line| ansi::width(line).max(1).div_ceil(width))
+            .sum();
+        if above > 0 {
+            self.writer
+                .queue(cursor::MoveToPreviousLine(above as u16))?;
+        }
+        self.writer
+            .queue(cursor::MoveToColumn(0))?
+            .queue(terminal::Clear(terminal::ClearType::FromCursorDown))?;
+        let prompt = ansi::strip_markers(transient).replace('\n', "\r\n");
+        let line = visible(&highlight::highlight(
+            &self.buffer,
+            &self.functions,
+            &mut self.lookups,
+        ));
+        write!(self.writer, "{}{}", prompt, line)?;
+        self.writer.flush()
+    }
+
+    /// Work out the suggestion for the buffer, and redraw the line
+    fn refresh_line(&mut self) -> std::io::Result<()> {
+        self.suggestion = self.suggest();
+        self.draw_line()
+    }
+
+    /// Redraw the line without a suggestion, before the cursor leaves it for good
+    fn finish_line(&mut self) -> std::io::Result<()> {
+        self.cursor = self.buffer.len();
+        self.suggestion = None;
+        self.draw_line()
+    }
+
+    /// Redraw the prompt, the highlighted buffer and the dimmed suggestion, and place the cursor at the right position
+    fn draw_line(&mut self) -> std::io::Result<()> {
+        // Only the last line of the prompt is on the same line as the buffer
+        let prompt = self.prompt.rsplit('\n').next().unwrap_or_default();
+        let prompt_width = ansi::width(prompt);
+        let cursor_width = self.buffer[..self.cursor].chars().count();
+        self.writer
+            .queue(cursor::MoveToColumn(0))?
+            .queue(terminal::Clear(terminal::ClearType::UntilNewLine))?;
+        let line = visible(&highlight::highlight(
+            &self.buffer,
+            &self.functions,
+            &mut self.lookups,
+        ));
+        write!(self.writer, "{}{}", ansi::strip_markers(prompt), line)?;
+
+        // The right prompt is hidden when the typed text would run into it.
+        // Like in zsh, the last column is left empty.
+        let width = terminal::size().map_or(80, |(w, _)| w as usize);
+        let used = prompt_width + self.buffer.chars().count();
+        let right = self.right_prompt.lines().next().unwrap_or_default();
+        let right_width = ansi::width(right);
+        let right_start = width.saturating_sub(right_width + 1);
+        let show_right = right_width > 0 && used < right_start;
+
+        if let Some(suggestion) = &self.suggestion {
+            // The suggestion is cut off at the edge of the screen (or the right prompt), so that it never wraps
+            let end = if show_right { right_start } else { width };
+            let suggestion: String = visible(suggestion)
+                .chars()
+                .take(end.saturating_sub(used + 1))
+                .collect();
+            write!(
+                self.writer,
+                "{}{}{}",
+                SetAttribute(Attribute::Dim),
+                suggestion,
+                SetAttribute(Attribute::NormalIntensity)
+            )?;
+        }
+        if show_right {
+            self.writer
+                .queue(cursor::MoveToColumn(right_start as u16))?;
+            write!(self.writer, "{}", ansi::strip_markers(right))?;
+        }
+        self.writer
+            .queue(cursor::MoveToColumn((prompt_width + cursor_width) as u16))?;
+        self.writer.flush()
+    }
+
     /// Register a new completer
     pub fn register_completer(&mut self, completer: Box<dyn Completer>) -> &mut Self {
         self.completers.push(completer);
@@ -58,10 +275,61 @@ impl ReadLine {
     }
 
     /// A convenience helper function to create a [`DefaultCompleter`] from a list of strings
-    pub fn with_completions(&mut self, completions: Vec<String>) -> &mut Self {
-        self.register_completer(Box::new(DefaultCompleter::new(completions)));
+    /// and their descriptions
+    pub fn with_descriptions(&mut self, completions: Vec<(String, String)>) -> &mut Self {
+        self.register_completer(Box::new(DefaultCompleter::with_descriptions(completions)));
         self
     }
+
+    /// The key bindings used by the line editor
+    pub fn keymap(&mut self) -> &mut Keymap {
+        &mut self.keymap
+    }
+
+    /// The completion specs registered with `complete`, by command name
+    pub fn specs(&mut self) -> &mut HashMap<String, CompSpec> {
+        &mut self.specs
+    }
+
+    /// The names of the shell functions, so that they are highlighted as commands
+    pub fn functions(&mut self) -> &mut HashSet<String> {
+        &mut self.functions
+    }
+
+    /// Set which kinds of matches count when completing
+    pub fn set_matching(&mut self, matching: Matching) {
+        self.matching = matching;
+    }
+
+    /// Set whether accepting a line of several lines, as from a paste, has to be confirmed
+    pub fn set_confirm_paste(&mut self, confirm: bool) {
+        self.confirm_paste = confirm;
+    }
+
+    /// The completion context at the cursor
+    pub fn context(&self) -> Context<'_> {
+        Context::new(&self.buffer, self.cursor).with_matching(self.matching)
+    }
+
+    /// Provide the candidates generated by a completion function, in response to [`Signal::Complete`]
+    pub fn complete_with(&mut self, candidates: Vec<String>) {
+        self.reply = Some(candidates);
+    }
+
+    /// The current contents of the buffer and the cursor position (in characters)
+    pub fn line(&self) -> (&str, usize) {
+        (&self.buffer, self.buffer[..self.cursor].chars().count())
+    }
+
+    /// Replace the contents of the buffer and move the cursor to the given character position
+    pub fn set_line(&mut self, line: &str, point: usize) {
+        self.buffer = line.to_owned();
+        self.cursor = self
+            .buffer
+            .char_indices()
+            .nth(point)
+            .map_or(self.buffer.len(), |(i, _)| i);
+    }
 }
 
 // ----
@@ -70,35 +338,55 @@ impl ReadLine {
 
 impl ReadLine {
     /// Read the next line the user inputs
-    pub(super) fn read(&mut self) -> std::io::Result<String> {
+    pub(super) fn read(&mut self) -> std::io::Result<Signal> {
         // Enable terminal raw mode with our `RawModeGuard` that will automatically disable when it is dropped
         let _raw_mode = raw_mode::RawModeGuard::new()?;
 
-        // Render the prompt
-        self.render_prompt()?;
+        // Render the prompt, along with anything left in the buffer by a `bind -x` command.
+        // Commands are looked up afresh for every new line.
+        if self.buffer.is_empty() {
+            self.lookups.clear();
+            self.completed = None;
+            self.render_prompt()?;
+        } else {
+            self.refresh_line()?;
+        }
+
+        // Finish a completion that was waiting for a completion function
+        if self.reply.is_some() {
+            self.handle_tab_completion()?;
+        }
 
-        loop {
+        let signal = loop {
             // Wait for a key-event
             if event::poll(self.poll_interval)? {
                 match event::read()? {
                     // Only check for key-presses to prevent double-trigger on both press and release
                     Event::Key(evt) if evt.kind == KeyEventKind::Press => {
-                        let done = self.handle_key_press(evt)?;
-                        if done {
-                            break; // If we are done processing the line, then exit the loop and continue onwards!
+                        if let Some(signal) = self.handle_key_press(evt)? {
+                            break signal; // If we are done processing the line, then exit the loop and continue onwards!
                         }
                     }
-                    _ => {} // Ignore non-key events
+                    Event::Paste(text) => self.handle_paste(&text)?,
+                    _ => {} // Ignore other events
+                }
+            } else if !self.pending.is_empty() && self.pending_since.elapsed() >= KEYSEQ_TIMEOUT {
+                // The rest of a key sequence didn't follow, so the keys so far stand on their own
+                if let Some(signal) = self.handle_keyseq_timeout()? {
+                    break signal;
                 }
             }
-        }
+        };
 
         // Move the cursor back to the left-most column if it somehow ends up in a weird place.
         self.writer.execute(cursor::MoveToColumn(0))?;
 
-        // Clear the buffer and return the results
-        let result = self.buffer.clone();
-        self.buffer.clear();
-        Ok(result)
+        // Clear the buffer and return the results (unless editing is going to resume)
+        if !matches!(signal, Signal::Execute(_) | Signal::Complete(_)) {
+            self.buffer.clear();
+            self.cursor = 0;
+            self.suggestion = None;
+        }
+        Ok(signal)
     }
 }
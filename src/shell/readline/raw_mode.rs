@@ -1,17 +1,25 @@
-/// RAII guard to enable terminal raw mode and ensure that it's disabled automatically on drop
+use crossterm::{
+    event::{DisableBracketedPaste, EnableBracketedPaste},
+    ExecutableCommand,
+};
+
+/// RAII guard to enable terminal raw mode and bracketed paste, and ensure that they're disabled automatically on drop
 pub(super) struct RawModeGuard;
 
 impl RawModeGuard {
     pub(super) fn new() -> std::io::Result<Self> {
         // Enable terminal raw mode
         crossterm::terminal::enable_raw_mode()?;
+        // Have pasted text arrive in one piece, rather than as key-presses
+        std::io::stdout().execute(EnableBracketedPaste)?;
         Ok(Self)
     }
 }
 
 impl Drop for RawModeGuard {
     fn drop(&mut self) {
-        // Ensure raw mode is disabled (even on error) when the guard is dropped
+        // Ensure raw mode and bracketed paste are disabled (even on error) when the guard is dropped
+        let _ = std::io::stdout().execute(DisableBracketedPaste);
         let _ = crossterm::terminal::disable_raw_mode();
     }
 }
@@ -0,0 +1,163 @@
+use super::{
+    actions::is_word_char,
+    completer::{escape, Candidate, Context, Position, Rank},
+};
+
+// ---------------
+// AUTOSUGGESTIONS
+// ---------------
+
+impl super::ReadLine {
+    /// The text that is suggested after the end of the buffer, shown dimmed as the user types.
+    /// The most recent history entry that starts with the buffer wins; otherwise, the rest of
+    /// the only completion of the last word. Nothing is suggested away from the end of the line,
+    /// or while the completion menu is open.
+    pub(super) fn suggest(&mut self) -> Option<String> {
+        if self.buffer.is_empty() || self.cursor != self.buffer.len() || self.menu.is_some() {
+            return None;
+        }
+        if let Some(entry) = from_history(&self.history, &self.buffer) {
+            return Some(entry.to_owned());
+        }
+        self.completion_suggestion()
+    }
+
+    /// The rest of the line if the word at the end of it has exactly one completion
+    fn completion_suggestion(&mut self) -> Option<String> {
+        let lines = match self.completed.take() {
+            // Typing more of the same word only narrows down the completions that were worked
+            // out for it, so the completers don't have to run again on every key-press
+            Some((typed, lines)) if extends_word(&typed, &self.buffer) => lines
+                .into_iter()
+                .filter(|line| line.starts_with(self.buffer.as_str()))
+                .collect(),
+            _ => self.completed_lines()?,
+        };
+        let suggestion = match lines.as_slice() {
+            [line] => line
+                .strip_prefix(self.buffer.as_str())
+                .filter(|rest| !rest.is_empty())
+                .map(str::to_owned),
+            _ => None,
+        };
+        self.completed = Some((self.buffer.clone(), lines));
+        suggestion
+    }
+
+    /// The lines that the completions of the word at the end of the buffer complete it to
+    fn completed_lines(&self) -> Option<Vec<String>> {
+        // Only prefix matches are suggested, whatever the completion options are
+        let ctx = Context::new(&self.buffer, self.cursor);
+        if ctx.word.is_empty() {
+            return None;
+        }
+
+        let candidates: Vec<Candidate> = match &ctx.position {
+            // Completion functions are only run on Tab, so they can't suggest anything
+            Position::Argument { command, .. } if self.specs.contains_key(command) => {
+                let spec = &self.specs[command];
+                if spec.function.is_some() {
+                    return None;
+                }
+                spec.generate(&ctx.value)
+                    .into_iter()
+                    .map(|w| Candidate::new(&ctx, escape(&w, ctx.quote()), w))
+                    .collect()
+            }
+            _ => self
+                .completers
+                .iter()
+                .flat_map(|completer| completer.complete(&ctx))
+                .collect(),
+        };
+
+        let mut lines: Vec<String> = candidates
+            .iter()
+            .filter(|c| c.rank == Rank::Prefix)
+            .map(|c| format!("{}{}", &self.buffer[..c.span.start], c.replacement))
+            .collect();
+        lines.sort();
+        lines.dedup();
+        Some(lines)
+    }
+
+    /// Whether a suggestion is shown, and the cursor is where accepting it would continue
+    pub(super) fn at_suggestion(&self) -> bool {
+        self.suggestion.is_some() && self.cursor == self.buffer.len()
+    }
+
+    /// Move the suggestion (or only its next word) into the buffer
+    pub(super) fn accept_suggestion(&mut self, word_only: bool) {
+        let Some(suggestion) = self.suggestion.take() else {
+            return;
+        };
+        let end = match word_only {
+            true => word_end(&suggestion),
+            false => suggestion.len(),
+        };
+        self.buffer.push_str(&suggestion[..end]);
+        self.cursor = self.buffer.len();
+    }
+}
+
+/// The rest of the most recent history entry that starts with the line
+fn from_history<'a>(history: &'a [String], line: &str) -> Option<&'a str> {
+    history
+        .iter()
+        .rev()
+        .filter_map(|entry| entry.strip_prefix(line))
+        .find(|rest| !rest.is_empty())
+}
+
+/// Whether the line is the typed line with more of its last word typed out. Characters that
+/// can start another word, a new directory or a quote change what the completions are.
+fn extends_word(typed: &str, line: &str) -> bool {
+    line.strip_prefix(typed).is_some_and(|more| {
+        more.chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | '+' | ',' | '@' | '%'))
+    })
+}
+
+/// The byte offset of the end of the first word in the text, including any separators before it
+fn word_end(text: &str) -> usize {
+    let start = text.len() - text.trim_start_matches(|c| !is_word_char(c)).len();
+    let rest = &text[start..];
+    start + (rest.len() - rest.trim_start_matches(is_word_char).len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_most_recent_history_entry_wins() {
+        let history = vec![
+            String::from("git status"),
+            String::from("git commit"),
+            String::from("echo hi"),
+        ];
+        assert_eq!(from_history(&history, "git "), Some("commit"));
+        assert_eq!(from_history(&history, "git s"), Some("tatus"));
+        // An entry that is already typed out in full suggests nothing
+        assert_eq!(from_history(&history, "echo hi"), None);
+        assert_eq!(from_history(&history, "ls"), None);
+    }
+
+    #[test]
+    fn test_extends_word() {
+        assert!(extends_word("git st", "git sta"));
+        assert!(extends_word("cat READ", "cat README.md"));
+        assert!(!extends_word("git st", "git st "));
+        assert!(!extends_word("cat src", "cat src/"));
+        assert!(!extends_word("echo $HO", "echo $HO}"));
+        assert!(!extends_word("git st", "git s"));
+    }
+
+    #[test]
+    fn test_word_end() {
+        assert_eq!(word_end("tatus --short"), 5);
+        assert_eq!(word_end(" --short"), 8);
+        assert_eq!(word_end("/usr/bin"), 4);
+        assert_eq!(word_end(""), 0);
+    }
+}
@@ -0,0 +1,201 @@
+// Library
+use std::{
+    io::{self, BufRead},
+    path::Path,
+};
+
+use crate::{helpers::vars, parser::Parser};
+
+// -------
+// SCRIPTS
+// -------
+
+impl super::Shell {
+    /// Sets `$0` to the name of the shell or script, and the positional parameters to the
+    /// arguments it was given
+    pub fn set_arguments(&mut self, name: &str, args: Vec<String>) {
+        vars::set_script_name(name);
+        vars::set_positional(args);
+    }
+
+    /// Runs the commands read from the input, without the line editor, and exits once it ends.
+    /// This is how scripts, command strings and input that isn't a terminal are run. Errors
+    /// mention the file and line they come from, if the input is a file.
+    pub fn run_script(&mut self, input: &mut dyn BufRead, file: Option<&str>) -> io::Result<()> {
+        self.run_lines(input, file)?;
+        self.finish()
+    }
+
+    /// Runs the commands read from the input, until it ends or the shell is asked to exit.
+    ///
+    /// Lines are read one at a time, and run as soon as they make up a complete command, so a
+    /// command that reads from the same input gets the lines after it. A command that goes on
+    /// over several lines, like a function definition, runs once it's complete.
+    pub(super) fn run_lines(
+        &mut self,
+        input: &mut dyn BufRead,
+        file: Option<&str>,
+    ) -> io::Result<()> {
+        // Files can run other files, and the errors after that are from this one again
+        let previous = self.location.take();
+        let (mut command, mut line) = (String::new(), String::new());
+        let (mut number, mut start) = (0, 0);
+        let mut result = Ok(());
+        while !self.exiting && !self.returning {
+            line.clear();
+            match input.read_line(&mut line) {
+                Ok(0) => break,
+                Ok(_) => number += 1,
+                Err(e) => {
+                    result = Err(e);
+                    break;
+                }
+            }
+            if command.is_empty() {
+                start = number;
+            }
+            command.push_str(&line);
+            if !Parser::is_complete(&command) {
+                continue;
+            }
+            self.location = file.map(|file| (file.to_owned(), start));
+            if let Err(e) = self.eval(&command) {
+                eprintln!("{}{}", self.error_prefix(), e);
+                self.status = 2;
+            }
+            command.clear();
+        }
+
+        if !self.exiting && !self.returning && !command.trim().is_empty() {
+            self.location = file.map(|file| (file.to_owned(), number));
+            eprintln!(
+                "{}syntax error: unexpected end of file",
+                self.error_prefix()
+            );
+            self.status = 2;
+        }
+        self.location = previous;
+        result
+    }
+
+    /// How errors start: with the file and line of the command that's running, if it was
+    /// read from a file, or else with the name of the shell
+    pub(super) fn error_prefix(&self) -> String {
+        match &self.location {
+            Some((file, line)) => format!("{}: line {}: ", file, line),
+            None => String::from("shell: "),
+        }
+    }
+
+    /// Runs the commands in the file in this shell, for `source`. With arguments, they are the
+    /// positional parameters until the file is done. A `return` in the file ends it early.
+    pub fn source(&mut self, path: &Path, args: Option<Vec<String>>) -> io::Result<()> {
+        let file = std::fs::File::open(path).and_then(check_readable)?;
+        let previous = args.map(vars::set_positional);
+        self.status = 0;
+        let result = self.run_call(|shell| {
+            shell.run_lines(&mut io::BufReader::new(file), Some(&path.to_string_lossy()))
+        });
+        if let Some(previous) = previous {
+            vars::set_positional(previous);
+        }
+        result
+    }
+
+    /// Runs a command string, for `shell -c 'commands'`
+    pub fn run_command(&mut self, command: &str) -> io::Result<()> {
+        self.run_script(&mut command.as_bytes(), None)
+    }
+
+    /// Runs the script in the file. A file that can't be read fails with 127 if it doesn't
+    /// exist, and 126 otherwise.
+    pub fn run_file(&mut self, path: &str) -> io::Result<()> {
+        let file = match std::fs::File::open(path).and_then(check_readable) {
+            Ok(file) => file,
+            Err(e) => {
+                eprintln!("shell: {}: {}", path, e);
+                self.status = match e.kind() {
+                    io::ErrorKind::NotFound => 127,
+                    _ => 126,
+                };
+                return Ok(());
+            }
+        };
+        self.run_script(&mut io::BufReader::new(file), Some(path))
+    }
+
+    /// Runs the commands read from stdin, for `shell -s` or input that isn't a terminal.
+    /// Stdin is read a byte at a time, so that nothing past the current line is taken from the
+    /// programs that read from it too.
+    pub fn run_stdin(&mut self) -> io::Result<()> {
+        #[cfg(unix)]
+        {
+            use std::os::fd::FromRawFd;
+            // SAFETY: stdin stays open for as long as the shell runs, and the file is never
+            // closed since it is never dropped
+            let stdin = std::mem::ManuallyDrop::new(unsafe { std::fs::File::from_raw_fd(0) });
+            self.run_script(&mut io::BufReader::with_capacity(1, &*stdin), None)
+        }
+        #[cfg(not(unix))]
+        self.run_script(&mut io::stdin().lock(), None)
+    }
+}
+
+/// Makes sure the file can be read as a script, rather than being a directory
+fn check_readable(file: std::fs::File) -> io::Result<std::fs::File> {
+    if file.metadata()?.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Is a directory",
+        ));
+    }
+    Ok(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Shell;
+
+    /// Writes a script to a file of its own, and returns its path
+    fn script(name: &str, text: &str) -> String {
+        let path = std::env::temp_dir().join(format!("shell-{}-{}", name, std::process::id()));
+        std::fs::write(&path, text).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn test_script_parameters() {
+        let path = script(
+            "parameters",
+            "echo \"$0\" $#\nprintf '[%s]' \"$@\"; echo\nprintf '[%s]' \"$*\"; echo\n",
+        );
+        let mut shell = Shell::default();
+        shell.set_arguments(&path, vec![String::from("one"), String::from("two words")]);
+        let output = shell.capture(|shell| shell.run_file(&path)).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            format!("{} 2\n[one][two words]\n[one two words]\n", path)
+        );
+        std::fs::remove_file(path).unwrap();
+
+        // A command string gets the name after it as `$0`
+        let mut shell = Shell::default();
+        shell.set_arguments("name", vec![String::from("a")]);
+        let (output, _) = shell.run_captured("echo $0 $1 $#");
+        assert_eq!(output, "name a 1\n");
+    }
+
+    #[test]
+    fn test_source() {
+        let path = script("source", "echo $# \"$1\"\nreturn 3\necho not run\n");
+        let mut shell = Shell::default();
+        // The arguments are the positional parameters until the file is done, and without any
+        // the file gets the shell's own
+        let (output, status) = shell.run_captured(&format!(
+            "set -- outer; source {path} a b; echo $1; . {path}"
+        ));
+        assert_eq!(output, "2 a\nouter\n1 outer\n");
+        assert_eq!(status, 3);
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,169 @@
+// Library
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use crate::helpers::{self, vars};
+
+// -------------
+// STARTUP FILES
+// -------------
+
+/// Which startup files the shell reads, as its command-line options say
+pub struct Startup {
+    /// Whether this is a login shell, which reads the profiles
+    pub login: bool,
+    /// Whether the shell is interactive, which reads the rc file and `$ENV`
+    pub interactive: bool,
+    /// Whether the profiles are read, which `--noprofile` turns off
+    pub profile: bool,
+    /// Whether the rc file and `$ENV` are read, which `--norc` turns off
+    pub rc: bool,
+    /// The rc file to read instead of `~/.shellrc`, from `--rcfile`
+    pub rcfile: Option<PathBuf>,
+}
+
+impl Default for Startup {
+    fn default() -> Self {
+        Startup {
+            login: false,
+            interactive: false,
+            profile: true,
+            rc: true,
+            rcfile: None,
+        }
+    }
+}
+
+impl super::Shell {
+    /// Reads the startup files, which customize the shell:
+    /// - A login shell reads `/etc/profile`, and then the first of `~/.shell_profile` and
+    ///   `~/.profile` that exists.
+    /// - An interactive shell that isn't a login shell reads `~/.shellrc`, or the `--rcfile`.
+    /// - An interactive shell then reads the file that `$ENV` names, once its variables are
+    ///   expanded, like POSIX shells do.
+    ///
+    /// Files that don't exist are skipped. Errors in them are reported with the file and line,
+    /// and the shell starts anyway.
+    pub fn read_startup_files(&mut self, startup: &Startup) {
+        let home = helpers::home::get();
+        if startup.login && startup.profile {
+            self.read_startup_file(Path::new("/etc/profile"));
+            let profile = home
+                .iter()
+                .flat_map(|home| [home.join(".shell_profile"), home.join(".profile")])
+                .find(|path| path.exists());
+            if let Some(profile) = profile {
+                self.read_startup_file(&profile);
+            }
+        }
+
+        if !startup.interactive || !startup.rc {
+            return;
+        }
+        if !startup.login {
+            let rcfile = startup
+                .rcfile
+                .clone()
+                .or_else(|| home.map(|home| home.join(".shellrc")));
+            if let Some(rcfile) = rcfile {
+                self.read_startup_file(&rcfile);
+            }
+        }
+        if let Some(env) = env_file() {
+            self.read_startup_file(&env);
+        }
+    }
+
+    /// Runs the commands in a startup file, if it exists and the shell isn't exiting already
+    fn read_startup_file(&mut self, path: &Path) {
+        if self.exiting || !path.exists() {
+            return;
+        }
+        let name = path.to_string_lossy();
+        let result = std::fs::File::open(path)
+            .and_then(|file| self.run_lines(&mut io::BufReader::new(file), Some(&name)));
+        if let Err(e) = result {
+            eprintln!("{}{}: {}", self.error_prefix(), name, e);
+        }
+    }
+}
+
+/// The file `$ENV` names, if it is set. Like POSIX says, only the parameters in it are expanded.
+fn env_file() -> Option<PathBuf> {
+    let env = vars::get("ENV").filter(|env| !env.is_empty())?;
+    Some(PathBuf::from(vars::expand_parameters(&env)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Shell, CWD_LOCK};
+    use super::*;
+
+    /// Reads the startup files in a new shell, and returns what they wrote
+    fn read(startup: Startup) -> String {
+        vars::unset("ORDER");
+        let mut shell = Shell::default();
+        let output = shell
+            .capture(|shell| {
+                shell.read_startup_files(&startup);
+                Ok(())
+            })
+            .unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_startup_files() {
+        // `HOME` is exported, so changing it changes it for the whole process
+        let _lock = CWD_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let previous = vars::get("HOME");
+        let home = std::env::temp_dir().join(format!("shell-startup-{}", std::process::id()));
+        std::fs::create_dir_all(&home).unwrap();
+        std::fs::write(home.join(".shellrc"), "echo rc; ORDER=rc\n").unwrap();
+        std::fs::write(home.join("custom"), "echo custom; ORDER=custom\n").unwrap();
+        std::fs::write(home.join("env"), "echo env after $ORDER\n").unwrap();
+        vars::set("HOME", &home.to_string_lossy());
+        vars::set("ENV", "$HOME/env");
+
+        let interactive = || Startup {
+            interactive: true,
+            ..Startup::default()
+        };
+        // The rc file comes first, and then the file `$ENV` names
+        assert_eq!(read(interactive()), "rc\nenv after rc\n");
+        assert_eq!(
+            read(Startup {
+                rcfile: Some(home.join("custom")),
+                ..interactive()
+            }),
+            "custom\nenv after custom\n"
+        );
+        assert_eq!(
+            read(Startup {
+                rc: false,
+                ..interactive()
+            }),
+            ""
+        );
+        // A login shell reads the profiles instead of the rc file
+        assert_eq!(
+            read(Startup {
+                login: true,
+                profile: false,
+                ..interactive()
+            }),
+            "env after\n"
+        );
+        // A shell that isn't interactive reads none of them
+        assert_eq!(read(Startup::default()), "");
+
+        vars::unset("ENV");
+        match previous {
+            Some(previous) => vars::set("HOME", &previous),
+            None => vars::unset("HOME"),
+        }
+        std::fs::remove_dir_all(home).unwrap();
+    }
+}
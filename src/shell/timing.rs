@@ -0,0 +1,149 @@
+// Library
+use std::{
+    io::{self, Write},
+    time::{Duration, Instant},
+};
+
+use crate::helpers::{
+    ansi::{Color, Style},
+    sys, vars,
+};
+
+// ------
+// TIMING
+// ------
+
+/// How long a command runs, in seconds, before it is reported, unless `LONG_COMMAND_THRESHOLD` says otherwise
+const DEFAULT_THRESHOLD: f64 = 10.0;
+
+/// The time taken by a command line
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) struct Timing {
+    /// The wall-clock time, from a monotonic clock
+    pub elapsed: Duration,
+    /// The CPU time spent by the programs it ran, in user mode
+    pub user: Duration,
+    /// The CPU time spent by the programs it ran, in the kernel
+    pub system: Duration,
+}
+
+/// Measures a command line, from when it starts to when it finishes
+struct Stopwatch {
+    start: Instant,
+    cpu: Option<(Duration, Duration)>,
+}
+
+impl Stopwatch {
+    fn start() -> Self {
+        Stopwatch {
+            start: Instant::now(),
+            cpu: sys::children_cpu_time(),
+        }
+    }
+
+    /// The time taken since the start. The CPU time of the programs that were run is the
+    /// difference in the resource usage of the shell's finished children.
+    fn stop(self) -> Timing {
+        let elapsed = self.start.elapsed();
+        let (user, system) = match (self.cpu, sys::children_cpu_time()) {
+            (Some((user, system)), Some((user_end, system_end))) => (
+                user_end.saturating_sub(user),
+                system_end.saturating_sub(system),
+            ),
+            _ => (Duration::ZERO, Duration::ZERO),
+        };
+        Timing {
+            elapsed,
+            user,
+            system,
+        }
+    }
+}
+
+impl super::Shell {
+    /// Runs a line typed at the prompt, and times it. The duration is kept for `\C` in the
+    /// prompt and in `CMD_DURATION` (in milliseconds), and commands that take longer than
+    /// `LONG_COMMAND_THRESHOLD` seconds are reported as `LONG_COMMAND_NOTIFY` asks.
+    pub(super) fn eval_timed(&mut self, input: &str) -> io::Result<()> {
+        let stopwatch = Stopwatch::start();
+        let result = self.eval(input);
+        let timing = stopwatch.stop();
+
+        self.timing = Some(timing);
+        vars::set("CMD_DURATION", &timing.elapsed.as_millis().to_string());
+        if timing.elapsed.as_secs_f64() >= threshold() {
+            notify(input, &timing)?;
+        }
+        result
+    }
+
+    /// The duration of the last command line, for `\C` in the prompt
+    pub(super) fn last_duration(&self) -> String {
+        self.timing
+            .map(|timing| format_duration(timing.elapsed))
+            .unwrap_or_default()
+    }
+}
+
+/// The threshold for reporting a command, in seconds
+fn threshold() -> f64 {
+    vars::get("LONG_COMMAND_THRESHOLD")
+        .and_then(|seconds| seconds.parse().ok())
+        .unwrap_or(DEFAULT_THRESHOLD)
+}
+
+/// Reports a long command in each of the ways listed in `LONG_COMMAND_NOTIFY`, which defaults to `print`:
+/// - `print` writes a line like `took 1m32s (user 1m20s, sys 2.1s)`
+/// - `bell` rings the terminal bell
+/// - `osc9` sends a desktop notification the way iTerm2 and Windows Terminal understand
+/// - `osc777` sends a desktop notification the way rxvt, foot and GNOME's VTE understand
+fn notify(command: &str, timing: &Timing) -> io::Result<()> {
+    let methods = vars::get("LONG_COMMAND_NOTIFY").unwrap_or_else(|| String::from("print"));
+    let took = format_duration(timing.elapsed);
+    let message = format!("{} finished, took {}", command.trim(), took);
+    let mut stdout = io::stdout();
+
+    for method in methods.split([',', ' ']).filter(|m| !m.is_empty()) {
+        match method {
+            "print" => {
+                let line = format!(
+                    "took {} (user {}, sys {})",
+                    took,
+                    format_duration(timing.user),
+                    format_duration(timing.system)
+                );
+                let style = Style::new().fg(Color::Yellow);
+                eprintln!("{}", style.paint_stderr(&line));
+            }
+            "bell" => write!(stdout, "\u{7}")?,
+            "osc9" => write!(stdout, "\u{1b}]9;{}\u{7}", message)?,
+            "osc777" => write!(stdout, "\u{1b}]777;notify;shell;{}\u{7}", message)?,
+            method => eprintln!("shell: LONG_COMMAND_NOTIFY: {}: unknown method", method),
+        }
+    }
+    stdout.flush()
+}
+
+/// A duration the way people read it: `450ms`, `4.2s`, `1m32s` or `2h5m0s`
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    match seconds {
+        0 => format!("{}ms", duration.as_millis()),
+        1..=59 => format!("{:.1}s", duration.as_secs_f64()),
+        60..=3599 => format!("{}m{}s", seconds / 60, seconds % 60),
+        _ => format!("{}h{}m{}s", seconds / 3600, seconds / 60 % 60, seconds % 60),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_millis(450)), "450ms");
+        assert_eq!(format_duration(Duration::from_millis(4230)), "4.2s");
+        assert_eq!(format_duration(Duration::from_secs(92)), "1m32s");
+        assert_eq!(format_duration(Duration::from_secs(7500)), "2h5m0s");
+    }
+}
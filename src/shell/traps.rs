@@ -0,0 +1,202 @@
+// Library
+use std::{collections::BTreeMap, io};
+
+use crate::helpers::{sys, vars};
+
+// -----
+// TRAPS
+// -----
+
+/// What a trap is set on: a signal, or one of the shell's own events
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Condition {
+    /// The shell exits
+    Exit,
+    /// The signal arrives
+    Signal(i32),
+    /// A command fails
+    Err,
+    /// A command is about to run
+    Debug,
+    /// A function returns
+    Return,
+}
+
+impl std::str::FromStr for Condition {
+    type Err = String;
+
+    /// A condition like `EXIT` (or `0`), `ERR`, `DEBUG` or `RETURN`, or a signal like `INT`,
+    /// `SIGINT` or `2`
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s.to_ascii_uppercase().as_str() {
+            "EXIT" | "SIGEXIT" | "0" => Ok(Condition::Exit),
+            "ERR" => Ok(Condition::Err),
+            "DEBUG" => Ok(Condition::Debug),
+            "RETURN" => Ok(Condition::Return),
+            _ => sys::signal_number(s)
+                .map(Condition::Signal)
+                .ok_or_else(|| format!("{}: invalid signal specification", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for Condition {
+    /// The condition the way `trap -p` shows it, like `EXIT` or `SIGINT`
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Condition::Exit => write!(f, "EXIT"),
+            Condition::Signal(signal) => match sys::signal_name(*signal) {
+                Some(name) => write!(f, "SIG{}", name),
+                None => write!(f, "{}", signal),
+            },
+            Condition::Err => write!(f, "ERR"),
+            Condition::Debug => write!(f, "DEBUG"),
+            Condition::Return => write!(f, "RETURN"),
+        }
+    }
+}
+
+/// The traps set with `trap`
+#[derive(Default)]
+pub(super) struct Traps {
+    /// The commands to run, by condition. An empty command ignores the condition.
+    actions: BTreeMap<Condition, String>,
+    /// Whether a trap is running, which holds off the others until it's done
+    running: bool,
+}
+
+impl super::Shell {
+    /// Sets the command to run on the condition, or with `None`, resets it to what the shell
+    /// does by default. An empty command ignores the signal.
+    pub fn set_trap(&mut self, condition: Condition, action: Option<String>) {
+        if let Condition::Signal(signal) = condition {
+            match action.as_deref() {
+                Some("") => sys::ignore_signal(signal, true),
+                Some(_) => sys::catch_signal(signal),
+                None => self.reset_signal(signal),
+            }
+        }
+        match action {
+            Some(action) => self.traps.actions.insert(condition, action),
+            None => self.traps.actions.remove(&condition),
+        };
+    }
+
+    /// The traps that are set, with their commands
+    pub fn traps(&self) -> impl Iterator<Item = (Condition, &str)> {
+        self.traps
+            .actions
+            .iter()
+            .map(|(condition, action)| (*condition, action.as_str()))
+    }
+
+    /// Gives the signal back what the shell does with it when it isn't trapped: the
+    /// interactive shell ignores the signals for the keys meant for jobs, and only takes note
+    /// of interrupts
+    fn reset_signal(&self, signal: i32) {
+        if !self.job_control || !sys::INTERACTIVE_SIGNALS.contains(&signal) {
+            sys::ignore_signal(signal, false);
+        } else if signal == sys::SIGINT {
+            sys::catch_signal(signal);
+        } else {
+            sys::ignore_signal(signal, true);
+        }
+    }
+
+    /// Runs the trap for the condition, if there is one. The exit status of the command that
+    /// set it off is kept, and traps don't set off other traps.
+    pub(super) fn run_trap(&mut self, condition: Condition) -> io::Result<()> {
+        if self.traps.running {
+            return Ok(());
+        }
+        let action = match self.traps.actions.get(&condition) {
+            Some(action) if !action.is_empty() => action.clone(),
+            _ => return Ok(()),
+        };
+
+        let status = self.status;
+        self.traps.running = true;
+        let result = self.eval(&action);
+        self.traps.running = false;
+        // An `exit` in the trap decides the exit status
+        if !self.exiting {
+            self.status = status;
+        }
+        result
+    }
+
+    /// Runs the closure with the traps held off, the way they are while a trap runs
+    pub(super) fn without_traps<T>(&mut self, run: impl FnOnce(&mut Self) -> T) -> T {
+        let running = std::mem::replace(&mut self.traps.running, true);
+        let result = run(self);
+        self.traps.running = running;
+        result
+    }
+
+    /// Runs the traps for the signals that arrived since the last check. This happens between
+    /// commands, where the shell is in a state to run other commands. Signals that aren't
+    /// trapped (like an interrupt the shell only takes note of) are forgotten.
+    pub(super) fn run_signal_traps(&mut self) -> io::Result<()> {
+        if self.traps.running {
+            return Ok(());
+        }
+        while let Some(signal) = sys::pending_signal() {
+            sys::take_signal(signal);
+            self.run_trap(Condition::Signal(signal))?;
+        }
+        Ok(())
+    }
+
+    /// Runs the trap for a command that is about to run, with the command in `BASH_COMMAND`
+    pub(super) fn run_debug_trap(&mut self, command: &str) -> io::Result<()> {
+        if self.traps.actions.contains_key(&Condition::Debug) && !self.traps.running {
+            vars::set("BASH_COMMAND", command.trim());
+            self.run_trap(Condition::Debug)?;
+        }
+        Ok(())
+    }
+
+    /// Asks the shell to exit once the command that's running is done. The commands after it
+    /// are skipped.
+    pub fn request_exit(&mut self) {
+        self.exiting = true;
+    }
+
+    /// Runs the `EXIT` trap and hangs up the jobs, as the shell exits
+    pub(super) fn finish(&mut self) -> io::Result<()> {
+        // The trap runs like any other command, and can itself ask the shell to exit
+        self.exiting = false;
+        let result = self.run_trap(Condition::Exit);
+        self.exiting = true;
+        self.hang_up_jobs();
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conditions() {
+        assert_eq!("EXIT".parse(), Ok(Condition::Exit));
+        assert_eq!("0".parse(), Ok(Condition::Exit));
+        assert_eq!("err".parse(), Ok(Condition::Err));
+        assert_eq!("SIGTERM".parse(), Ok(Condition::Signal(sys::SIGTERM)));
+        assert_eq!("int".parse(), Ok(Condition::Signal(sys::SIGINT)));
+        assert_eq!(
+            "FOO".parse::<Condition>(),
+            Err(String::from("FOO: invalid signal specification"))
+        );
+        assert_eq!(Condition::Signal(sys::SIGHUP).to_string(), "SIGHUP");
+    }
+
+    #[test]
+    fn test_err_trap_sees_the_status() {
+        let mut shell = super::super::Shell::default();
+        let (output, status) =
+            shell.run_captured("trap 'echo failed $?' ERR; false; sh -c 'exit 3'; true && false");
+        assert_eq!(output, "failed 1\nfailed 3\nfailed 1\n");
+        assert_eq!(status, 1);
+    }
+}